@@ -1,6 +1,6 @@
 # Print an optspec for argparse to handle cmd's options that are independent of any subcommand.
 function __fish_velacritty_global_optspecs
-	string join \n print-events ref-test embed= config-file= socket= q v daemon working-directory= hold e/command= T/title= class= o/option= h/help V/version
+	string join \n print-events ref-test embed= config-file= socket= q v daemon restore working-directory= hold record= replay= replay-speed= replay-paused e/command= T/title= class= o/option= h/help V/version
 end
 
 function __fish_velacritty_needs_command
@@ -28,6 +28,9 @@ complete -c velacritty -n "__fish_velacritty_needs_command" -l embed -d 'X11 win
 complete -c velacritty -n "__fish_velacritty_needs_command" -l config-file -d 'Specify alternative configuration file [default: $XDG_CONFIG_HOME/velacritty/velacritty.toml]' -r -F
 complete -c velacritty -n "__fish_velacritty_needs_command" -l socket -d 'Path for IPC socket creation' -r -F
 complete -c velacritty -n "__fish_velacritty_needs_command" -l working-directory -d 'Start the shell in the specified working directory' -r -F
+complete -c velacritty -n "__fish_velacritty_needs_command" -l record -d 'Record the terminal output to an asciicast file' -r -F
+complete -c velacritty -n "__fish_velacritty_needs_command" -l replay -d 'Replay an asciicast or `--ref-test` recording instead of running a shell' -r -F
+complete -c velacritty -n "__fish_velacritty_needs_command" -l replay-speed -d 'Speed up the replay by a factor, like 0.5 or 2, 0 replays without delays [default: 1]' -r
 complete -c velacritty -n "__fish_velacritty_needs_command" -s e -l command -d 'Command and args to execute (must be last argument)' -r
 complete -c velacritty -n "__fish_velacritty_needs_command" -s T -l title -d 'Defines the window title [default: Velacritty]' -r
 complete -c velacritty -n "__fish_velacritty_needs_command" -l class -d 'Defines window class/app_id on X11/Wayland [default: Velacritty]' -r
@@ -37,33 +40,86 @@ complete -c velacritty -n "__fish_velacritty_needs_command" -l ref-test -d 'Gene
 complete -c velacritty -n "__fish_velacritty_needs_command" -s q -d 'Reduces the level of verbosity (the min level is -qq)'
 complete -c velacritty -n "__fish_velacritty_needs_command" -s v -d 'Increases the level of verbosity (the max level is -vvv)'
 complete -c velacritty -n "__fish_velacritty_needs_command" -l daemon -d 'Do not spawn an initial window'
+complete -c velacritty -n "__fish_velacritty_needs_command" -l restore -d 'Reopen the windows saved by the last session'
 complete -c velacritty -n "__fish_velacritty_needs_command" -l hold -d 'Remain open after child process exit'
+complete -c velacritty -n "__fish_velacritty_needs_command" -l replay-paused -d 'Start the replay paused'
 complete -c velacritty -n "__fish_velacritty_needs_command" -s h -l help -d 'Print help'
 complete -c velacritty -n "__fish_velacritty_needs_command" -s V -l version -d 'Print version'
 complete -c velacritty -n "__fish_velacritty_needs_command" -f -a "msg" -d 'Send a message to the Velacritty socket'
 complete -c velacritty -n "__fish_velacritty_needs_command" -f -a "migrate" -d 'Migrate the configuration file'
 complete -c velacritty -n "__fish_velacritty_needs_command" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
-complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config help" -s s -l socket -d 'IPC socket connection path override' -r -F
-complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config help" -s h -l help -d 'Print help'
-complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config help" -f -a "create-window" -d 'Create a new window in the same Velacritty process'
-complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config help" -f -a "config" -d 'Update the Velacritty configuration'
-complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config help" -f -a "get-config" -d 'Read runtime Velacritty configuration'
-complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -s s -l socket -d 'IPC socket connection path override' -r -F
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -s h -l help -d 'Print help'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "create-window" -d 'Create a new window in the same Velacritty process'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "config" -d 'Update the Velacritty configuration'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "get-config" -d 'Read runtime Velacritty configuration'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "get-memory-usage" -d 'Report the memory used by the scrollback of every window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "export-scrollback" -d 'Write the scrollback or the selection of a window to a file'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "toggle-auto-scroll" -d 'Toggle automatic scrolling to new output for a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "get-text" -d 'Print the screen, scrollback or selection text of a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "send-text" -d 'Write text to the terminal of a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "send-keys" -d 'Write key presses to the terminal of a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "list-windows" -d 'List all windows'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "focus-window" -d 'Focus a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "close-window" -d 'Close a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "set-title" -d 'Set the title of a window, ignoring titles set by the terminal application'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and not __fish_seen_subcommand_from create-window config get-config get-memory-usage export-scrollback toggle-auto-scroll get-text send-text send-keys list-windows focus-window close-window set-title help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -l working-directory -d 'Start the shell in the specified working directory' -r -F
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -l record -d 'Record the terminal output to an asciicast file' -r -F
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -l replay -d 'Replay an asciicast or `--ref-test` recording instead of running a shell' -r -F
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -l replay-speed -d 'Speed up the replay by a factor, like 0.5 or 2, 0 replays without delays [default: 1]' -r
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -s e -l command -d 'Command and args to execute (must be last argument)' -r
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -s T -l title -d 'Defines the window title [default: Velacritty]' -r
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -l class -d 'Defines window class/app_id on X11/Wayland [default: Velacritty]' -r
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -s o -l option -d 'Override configuration file options [example: \'cursor.style="Beam"\']' -r
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -l hold -d 'Remain open after child process exit'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -l replay-paused -d 'Start the replay paused'
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from create-window" -s h -l help -d 'Print help'
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from config" -s w -l window-id -d 'Window ID for the new config' -r
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from config" -s r -l reset -d 'Clear all runtime configuration changes'
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from config" -s h -l help -d 'Print help (see more with \'--help\')'
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from get-config" -s w -l window-id -d 'Window ID for the config request' -r
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from get-config" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from get-memory-usage" -s h -l help -d 'Print help'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from export-scrollback" -s f -l format -d 'Format of the file [default: `export.format`]' -r -f -a "html\t'HTML with colors and hyperlinks'
+ansi\t'Text with ANSI escapes for colors and attributes'
+text\t'Plain text'"
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from export-scrollback" -s w -l window-id -d 'Window ID of the exported window [default: focused window]' -r
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from export-scrollback" -l selection -d 'Only export the current selection'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from export-scrollback" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from toggle-auto-scroll" -s w -l window-id -d 'Window ID of the toggled window [default: focused window]' -r
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from toggle-auto-scroll" -s h -l help -d 'Print help'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from get-text" -s f -l format -d 'Format of the text' -r -f -a "html\t'HTML with colors and hyperlinks'
+ansi\t'Text with ANSI escapes for colors and attributes'
+text\t'Plain text'"
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from get-text" -s w -l window-id -d 'Window ID of the read window [default: focused window]' -r
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from get-text" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from send-text" -s w -l window-id -d 'Window ID of the receiving window [default: focused window]' -r
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from send-text" -s b -l bracketed -d 'Paste the text, using bracketed paste if the application requested it'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from send-text" -s h -l help -d 'Print help'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from send-keys" -s w -l window-id -d 'Window ID of the receiving window [default: focused window]' -r
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from send-keys" -s h -l help -d 'Print help'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from list-windows" -l json -d 'Print the windows as JSON'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from list-windows" -s h -l help -d 'Print help'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from focus-window" -s w -l window-id -d 'Window ID of the focused window' -r
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from focus-window" -s h -l help -d 'Print help'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from close-window" -s w -l window-id -d 'Window ID of the closed window' -r
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from close-window" -s h -l help -d 'Print help'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from set-title" -s w -l window-id -d 'Window ID of the renamed window [default: focused window]' -r
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from set-title" -s h -l help -d 'Print help'
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "create-window" -d 'Create a new window in the same Velacritty process'
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "config" -d 'Update the Velacritty configuration'
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "get-config" -d 'Read runtime Velacritty configuration'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "get-memory-usage" -d 'Report the memory used by the scrollback of every window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "export-scrollback" -d 'Write the scrollback or the selection of a window to a file'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "toggle-auto-scroll" -d 'Toggle automatic scrolling to new output for a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "get-text" -d 'Print the screen, scrollback or selection text of a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "send-text" -d 'Write text to the terminal of a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "send-keys" -d 'Write key presses to the terminal of a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "list-windows" -d 'List all windows'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "focus-window" -d 'Focus a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "close-window" -d 'Close a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "set-title" -d 'Set the title of a window, ignoring titles set by the terminal application'
 complete -c velacritty -n "__fish_velacritty_using_subcommand msg; and __fish_seen_subcommand_from help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
 complete -c velacritty -n "__fish_velacritty_using_subcommand migrate" -s c -l config-file -d 'Path to the configuration file' -r -F
 complete -c velacritty -n "__fish_velacritty_using_subcommand migrate" -s d -l dry-run -d 'Only output TOML config to STDOUT'
@@ -77,3 +133,13 @@ complete -c velacritty -n "__fish_velacritty_using_subcommand help; and not __fi
 complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "create-window" -d 'Create a new window in the same Velacritty process'
 complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "config" -d 'Update the Velacritty configuration'
 complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "get-config" -d 'Read runtime Velacritty configuration'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "get-memory-usage" -d 'Report the memory used by the scrollback of every window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "export-scrollback" -d 'Write the scrollback or the selection of a window to a file'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "toggle-auto-scroll" -d 'Toggle automatic scrolling to new output for a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "get-text" -d 'Print the screen, scrollback or selection text of a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "send-text" -d 'Write text to the terminal of a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "send-keys" -d 'Write key presses to the terminal of a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "list-windows" -d 'List all windows'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "focus-window" -d 'Focus a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "close-window" -d 'Close a window'
+complete -c velacritty -n "__fish_velacritty_using_subcommand help; and __fish_seen_subcommand_from msg" -f -a "set-title" -d 'Set the title of a window, ignoring titles set by the terminal application'
@@ -0,0 +1,18 @@
+#if defined(GLES2_RENDERER)
+#define FRAG_COLOR gl_FragColor
+#define TEXTURE texture2D
+
+varying mediump vec2 texCoords;
+#else
+out vec4 FragColor;
+#define FRAG_COLOR FragColor
+#define TEXTURE texture
+
+in vec2 texCoords;
+#endif
+
+uniform sampler2D graphic;
+
+void main() {
+    FRAG_COLOR = TEXTURE(graphic, texCoords);
+}
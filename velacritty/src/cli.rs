@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::rc::Rc;
+#[cfg(not(windows))]
+use std::str::FromStr;
 
 use clap::{ArgAction, Args, Parser, Subcommand, ValueHint};
 use log::{LevelFilter, error};
@@ -13,8 +15,14 @@ use velacritty_config::SerdeReplace;
 use velacritty_terminal::tty::Options as PtyOptions;
 
 use crate::config::UiConfig;
+#[cfg(unix)]
+use crate::config::export::ExportFormat;
 use crate::config::ui_config::Program;
 use crate::config::window::{Class, Identity};
+#[cfg(unix)]
+use crate::export::{ExportOptions, TextRange};
+#[cfg(unix)]
+use crate::input::keyboard::KeyPress;
 use crate::logging::LOG_TARGET_IPC_CONFIG;
 
 /// CLI options for the main Velacritty executable.
@@ -66,6 +74,10 @@ pub struct Options {
     #[clap(long)]
     pub daemon: bool,
 
+    /// Reopen the windows saved by the last session.
+    #[clap(long, conflicts_with("daemon"))]
+    pub restore: bool,
+
     /// CLI options for config overrides.
     #[clap(skip)]
     pub config_options: ParsedOptions,
@@ -163,9 +175,28 @@ pub struct TerminalOptions {
     #[clap(long)]
     pub hold: bool,
 
+    /// Record the terminal output to an asciicast file.
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub record: Option<PathBuf>,
+
+    /// Replay an asciicast or `--ref-test` recording instead of running a shell.
+    #[cfg(not(windows))]
+    #[clap(long, value_hint = ValueHint::FilePath, conflicts_with = "command")]
+    pub replay: Option<PathBuf>,
+
+    /// Speed up the replay by a factor, like 0.5 or 2, 0 replays without delays [default: 1].
+    #[cfg(not(windows))]
+    #[clap(long, value_name = "FACTOR", requires = "replay")]
+    pub replay_speed: Option<ReplaySpeed>,
+
+    /// Start the replay paused.
+    #[cfg(not(windows))]
+    #[clap(long, requires = "replay")]
+    pub replay_paused: bool,
+
     /// Command and args to execute (must be last argument).
     #[clap(short = 'e', long, allow_hyphen_values = true, num_args = 1..)]
-    command: Vec<String>,
+    pub command: Vec<String>,
 }
 
 impl TerminalOptions {
@@ -206,6 +237,36 @@ impl From<TerminalOptions> for PtyOptions {
     }
 }
 
+/// Factor by which the replay of a recording is sped up.
+#[cfg(not(windows))]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct ReplaySpeed(f64);
+
+#[cfg(not(windows))]
+impl ReplaySpeed {
+    /// Factor by which the recorded delays are divided, infinite for replaying without delays.
+    pub fn factor(self) -> f64 {
+        if self.0 == 0. { f64::INFINITY } else { self.0 }
+    }
+}
+
+/// The speed is never NaN, since parsing rejects it.
+#[cfg(not(windows))]
+impl Eq for ReplaySpeed {}
+
+#[cfg(not(windows))]
+impl FromStr for ReplaySpeed {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.parse::<f64>() {
+            Ok(speed) if speed >= 0. => Ok(Self(speed)),
+            _ => Err(format!("invalid replay speed {input:?}, expected a non-negative number")),
+        }
+    }
+}
+
 /// Window specific cli options which can be passed to new windows via IPC.
 #[derive(Serialize, Deserialize, Args, Default, Debug, Clone, PartialEq, Eq)]
 pub struct WindowIdentity {
@@ -234,7 +295,7 @@ impl WindowIdentity {
 #[derive(Subcommand, Debug)]
 pub enum Subcommands {
     #[cfg(unix)]
-    Msg(MessageOptions),
+    Msg(Box<MessageOptions>),
     Migrate(MigrateOptions),
 }
 
@@ -263,6 +324,36 @@ pub enum SocketMessage {
 
     /// Read runtime Velacritty configuration.
     GetConfig(IpcGetConfig),
+
+    /// Report the memory used by the scrollback of every window.
+    GetMemoryUsage,
+
+    /// Write the scrollback or the selection of a window to a file.
+    ExportScrollback(IpcExportScrollback),
+
+    /// Toggle automatic scrolling to new output for a window.
+    ToggleAutoScroll(IpcToggleAutoScroll),
+
+    /// Print the screen, scrollback or selection text of a window.
+    GetText(IpcGetText),
+
+    /// Write text to the terminal of a window.
+    SendText(IpcSendText),
+
+    /// Write key presses to the terminal of a window.
+    SendKeys(IpcSendKeys),
+
+    /// List all windows.
+    ListWindows(IpcListWindows),
+
+    /// Focus a window.
+    FocusWindow(IpcFocusWindow),
+
+    /// Close a window.
+    CloseWindow(IpcCloseWindow),
+
+    /// Set the title of a window, ignoring titles set by the terminal application.
+    SetTitle(IpcSetTitle),
 }
 
 /// Migrate the configuration file.
@@ -352,6 +443,113 @@ pub struct IpcGetConfig {
     pub window_id: Option<i128>,
 }
 
+/// Parameters to the `export-scrollback` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcExportScrollback {
+    #[clap(flatten)]
+    pub options: ExportOptions,
+
+    /// Window ID of the exported window [default: focused window].
+    #[clap(short, long, env = "VELACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
+/// Parameters to the `get-text` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcGetText {
+    /// Part of the content: screen, scrollback, selection or lines(START..END), with 0 as the top
+    /// screen line and negative history lines.
+    #[clap(default_value = "screen", allow_hyphen_values = true)]
+    pub range: TextRange,
+
+    /// Format of the text.
+    #[clap(short, long, default_value = "text")]
+    pub format: ExportFormat,
+
+    /// Window ID of the read window [default: focused window].
+    #[clap(short, long, env = "VELACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
+/// Parameters to the `send-text` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSendText {
+    /// Text which is written.
+    pub text: String,
+
+    /// Paste the text, using bracketed paste if the application requested it.
+    #[clap(short, long)]
+    pub bracketed: bool,
+
+    /// Window ID of the receiving window [default: focused window].
+    #[clap(short, long, env = "VELACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
+/// Parameters to the `send-keys` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSendKeys {
+    /// Keys which are pressed, like `Enter` or `Control|Shift+t`.
+    #[clap(required = true)]
+    pub keys: Vec<KeyPress>,
+
+    /// Window ID of the receiving window [default: focused window].
+    #[clap(short, long, env = "VELACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
+/// Parameters to the `list-windows` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcListWindows {
+    /// Print the windows as JSON.
+    #[clap(long)]
+    pub json: bool,
+}
+
+/// Parameters to the `focus-window` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcFocusWindow {
+    /// Window ID of the focused window.
+    #[clap(short, long, env = "VELACRITTY_WINDOW_ID")]
+    pub window_id: u64,
+}
+
+/// Parameters to the `close-window` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcCloseWindow {
+    /// Window ID of the closed window.
+    #[clap(short, long, env = "VELACRITTY_WINDOW_ID")]
+    pub window_id: u64,
+}
+
+/// Parameters to the `set-title` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcSetTitle {
+    /// New window title.
+    pub title: String,
+
+    /// Window ID of the renamed window [default: focused window].
+    #[clap(short, long, env = "VELACRITTY_WINDOW_ID")]
+    pub window_id: Option<u64>,
+}
+
+/// Parameters to the `toggle-auto-scroll` IPC subcommand.
+#[cfg(unix)]
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct IpcToggleAutoScroll {
+    /// Window ID of the toggled window [default: focused window].
+    #[clap(short, long, env = "VELACRITTY_WINDOW_ID")]
+    pub window_id: Option<i128>,
+}
+
 /// Parsed CLI config overrides.
 #[derive(Debug, Default)]
 pub struct ParsedOptions {
@@ -515,6 +713,15 @@ mod tests {
             assert!(class.is_err());
         }
 
+        #[cfg(not(windows))]
+        #[test]
+        fn parse_replay_speed() {
+            assert_eq!("0.5".parse::<ReplaySpeed>().unwrap().factor(), 0.5);
+            assert_eq!("0".parse::<ReplaySpeed>().unwrap().factor(), f64::INFINITY);
+            assert!("-1".parse::<ReplaySpeed>().is_err());
+            assert!("NaN".parse::<ReplaySpeed>().is_err());
+        }
+
         #[test]
         fn valid_decimal() {
             let value = parse_hex_or_decimal("10485773");
@@ -534,6 +741,46 @@ mod tests {
         }
     }
 
+    /// Tests for parsing IPC messages.
+    #[cfg(unix)]
+    mod message_tests {
+        use super::*;
+
+        fn parse_message(args: &[&str]) -> Result<SocketMessage, clap::Error> {
+            let args = ["velacritty", "msg"].into_iter().chain(args.iter().copied());
+            match Options::try_parse_from(args)?.subcommands {
+                Some(Subcommands::Msg(options)) => Ok(options.message),
+                _ => unreachable!(),
+            }
+        }
+
+        #[test]
+        fn parse_list_windows() {
+            let message = parse_message(&["list-windows", "--json"]).unwrap();
+            assert_eq!(message, SocketMessage::ListWindows(IpcListWindows { json: true }));
+        }
+
+        #[test]
+        fn parse_window_commands() {
+            let message = parse_message(&["focus-window", "-w", "3"]).unwrap();
+            assert_eq!(message, SocketMessage::FocusWindow(IpcFocusWindow { window_id: 3 }));
+
+            let message = parse_message(&["close-window", "--window-id", "4"]).unwrap();
+            assert_eq!(message, SocketMessage::CloseWindow(IpcCloseWindow { window_id: 4 }));
+
+            let message = parse_message(&["set-title", "vim", "-w", "5"]).unwrap();
+            let set_title = IpcSetTitle { title: String::from("vim"), window_id: Some(5) };
+            assert_eq!(message, SocketMessage::SetTitle(set_title));
+        }
+
+        #[test]
+        fn parse_invalid_window_id() {
+            assert!(parse_message(&["focus-window", "-w", "-1"]).is_err());
+            assert!(parse_message(&["close-window", "-w", "x"]).is_err());
+            assert!(parse_message(&["set-title", "vim", "-w", "-1"]).is_err());
+        }
+    }
+
     /// Tests for shell completion generation (Linux only).
     #[cfg(target_os = "linux")]
     mod completion_tests {
@@ -124,6 +124,9 @@ pub enum Action {
     /// Store current selection into selection buffer.
     CopySelection,
 
+    /// Select the output of a shell command and store it into clipboard.
+    CopyCommandOutput,
+
     /// Paste contents of selection buffer.
     PasteSelection,
 
@@ -160,6 +163,9 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll to the first line which arrived while the viewport was not following the output.
+    ScrollToUnread,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
@@ -181,6 +187,21 @@ pub enum Action {
     /// Spawn a new instance of Velacritty.
     SpawnNewInstance,
 
+    /// Save all windows as the session reopened by `--restore`.
+    SaveSession,
+
+    /// Write the selection or the whole scrollback to a file.
+    ExportScrollback,
+
+    /// Start or stop recording the terminal output to an asciicast file.
+    ToggleRecording,
+
+    /// Pause or resume the replay of a recording.
+    ToggleReplayPause,
+
+    /// Pause the replay of a recording and play its next frame.
+    StepReplay,
+
     /// Select next tab.
     SelectNextTab,
 
@@ -452,6 +473,9 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         Backspace, ModifiersState::ALT,     ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::REPORT_ALL_KEYS_AS_ESC, ~BindingMode::DISAMBIGUATE_ESC_CODES; Action::Esc("\x1b\x7f".into());
         Backspace, ModifiersState::SHIFT,   ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::REPORT_ALL_KEYS_AS_ESC, ~BindingMode::DISAMBIGUATE_ESC_CODES; Action::Esc("\x7f".into());
         Enter => KeyLocation::Numpad, ~BindingMode::VI, ~BindingMode::SEARCH, ~BindingMode::REPORT_ALL_KEYS_AS_ESC, ~BindingMode::DISAMBIGUATE_ESC_CODES; Action::Esc("\n".into());
+        // Replay of a recording.
+        Space, +BindingMode::REPLAY, ~BindingMode::VI, ~BindingMode::SEARCH; Action::ToggleReplayPause;
+        ".",   +BindingMode::REPLAY, ~BindingMode::VI, ~BindingMode::SEARCH; Action::StepReplay;
         // Vi mode.
         Space, ModifiersState::SHIFT | ModifiersState::CONTROL, ~BindingMode::SEARCH; Action::ToggleViMode;
         Space, ModifiersState::SHIFT | ModifiersState::CONTROL, +BindingMode::VI, ~BindingMode::SEARCH; Action::ScrollToBottom;
@@ -518,6 +542,8 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         "%",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::Bracket;
         "{",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::ParagraphUp;
         "}",      ModifiersState::SHIFT,    +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::ParagraphDown;
+        "[",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::PreviousPrompt;
+        "]",                                +BindingMode::VI, ~BindingMode::SEARCH; ViMotion::NextPrompt;
         Enter,                              +BindingMode::VI, +BindingMode::SEARCH; SearchAction::SearchConfirm;
         // Plain search.
         Escape,                             +BindingMode::SEARCH; SearchAction::SearchCancel;
@@ -762,17 +788,19 @@ bitflags! {
         const SEARCH                 = 0b0001_0000;
         const DISAMBIGUATE_ESC_CODES = 0b0010_0000;
         const REPORT_ALL_KEYS_AS_ESC = 0b0100_0000;
+        const REPLAY                 = 0b1000_0000;
     }
 }
 
 impl BindingMode {
-    pub fn new(mode: &TermMode, search: bool) -> BindingMode {
+    pub fn new(mode: &TermMode, search: bool, replay: bool) -> BindingMode {
         let mut binding_mode = BindingMode::empty();
         binding_mode.set(BindingMode::APP_CURSOR, mode.contains(TermMode::APP_CURSOR));
         binding_mode.set(BindingMode::APP_KEYPAD, mode.contains(TermMode::APP_KEYPAD));
         binding_mode.set(BindingMode::ALT_SCREEN, mode.contains(TermMode::ALT_SCREEN));
         binding_mode.set(BindingMode::VI, mode.contains(TermMode::VI));
         binding_mode.set(BindingMode::SEARCH, search);
+        binding_mode.set(BindingMode::REPLAY, replay);
         binding_mode.set(
             BindingMode::DISAMBIGUATE_ESC_CODES,
             mode.contains(TermMode::DISAMBIGUATE_ESC_CODES),
@@ -803,7 +831,8 @@ impl<'a> Deserialize<'a> for ModeWrapper {
 
             fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 f.write_str(
-                    "a combination of AppCursor | AppKeypad | Alt | Vi, possibly with negation (~)",
+                    "a combination of AppCursor | AppKeypad | Alt | Vi | Search | Replay, \
+                     possibly with negation (~)",
                 )
             }
 
@@ -826,6 +855,8 @@ impl<'a> Deserialize<'a> for ModeWrapper {
                         "~vi" => res.not_mode |= BindingMode::VI,
                         "search" => res.mode |= BindingMode::SEARCH,
                         "~search" => res.not_mode |= BindingMode::SEARCH,
+                        "replay" => res.mode |= BindingMode::REPLAY,
+                        "~replay" => res.not_mode |= BindingMode::REPLAY,
                         _ => return Err(E::invalid_value(Unexpected::Str(modifier), &self)),
                     }
                 }
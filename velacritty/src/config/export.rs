@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use velacritty_config_derive::ConfigDeserialize;
+use velacritty_terminal::term::export::Format;
+
+#[derive(ConfigDeserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct Export {
+    /// Format of exported terminal content.
+    pub format: ExportFormat,
+
+    /// Directory for exported files and recordings, the home directory when unset.
+    pub directory: Option<PathBuf>,
+}
+
+/// File format of exported terminal content.
+#[derive(ConfigDeserialize, Serialize, ValueEnum, Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// HTML with colors and hyperlinks.
+    #[default]
+    Html,
+
+    /// Text with ANSI escapes for colors and attributes.
+    Ansi,
+
+    /// Plain text.
+    Text,
+}
+
+impl ExportFormat {
+    /// File extension for the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Ansi => "ans",
+            Self::Text => "txt",
+        }
+    }
+}
+
+impl From<ExportFormat> for Format {
+    fn from(format: ExportFormat) -> Self {
+        match format {
+            ExportFormat::Html => Format::Html,
+            ExportFormat::Ansi => Format::Ansi,
+            ExportFormat::Text => Format::Text,
+        }
+    }
+}
@@ -27,6 +27,9 @@ pub struct General {
     /// Offer IPC through a unix socket.
     #[allow(unused)]
     pub ipc_socket: bool,
+
+    /// Save open windows on exit, for restoring them with `--restore`.
+    pub save_session: bool,
 }
 
 impl Default for General {
@@ -35,6 +38,7 @@ impl Default for General {
             live_config_reload: true,
             ipc_socket: true,
             working_directory: Default::default(),
+            save_session: Default::default(),
             import: Default::default(),
         }
     }
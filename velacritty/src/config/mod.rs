@@ -14,9 +14,11 @@ pub mod bell;
 pub mod color;
 pub mod cursor;
 pub mod debug;
+pub mod export;
 pub mod font;
 pub mod general;
 pub mod monitor;
+pub mod notifications;
 pub mod scrolling;
 pub mod selection;
 pub mod serde_utils;
@@ -31,7 +33,8 @@ use crate::cli::Options;
 #[cfg(test)]
 pub use crate::config::bindings::Binding;
 pub use crate::config::bindings::{
-    Action, BindingKey, BindingMode, KeyBinding, MouseAction, SearchAction, ViAction,
+    Action, BindingKey, BindingMode, KeyBinding, KeyLocation, ModsWrapper, MouseAction,
+    SearchAction, ViAction,
 };
 pub use crate::config::ui_config::UiConfig;
 use crate::logging::LOG_TARGET_CONFIG;
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use velacritty_config_derive::ConfigDeserialize;
+use velacritty_terminal::term::notification::Urgency;
+
+use crate::config::ui_config::Program;
+
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Notifications {
+    /// Command to run for showing desktop notifications.
+    pub command: Option<Program>,
+
+    /// Drop notifications while the window is focused.
+    pub suppress_when_focused: bool,
+
+    /// Minimum time between two notifications in milliseconds.
+    cooldown: u16,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self { command: Default::default(), suppress_when_focused: true, cooldown: 1000 }
+    }
+}
+
+impl Notifications {
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_millis(self.cooldown as u64)
+    }
+
+    /// Arguments for the notification command.
+    ///
+    /// The `{title}`, `{body}` and `{urgency}` placeholders are replaced with the notification's
+    /// content. Without any `{title}` or `{body}` placeholder, title and body are appended.
+    pub fn args(&self, title: &str, body: &str, urgency: Urgency) -> Vec<String> {
+        let args = self.command.as_ref().map_or(&[][..], |command| command.args());
+
+        let urgency = match urgency {
+            Urgency::Low => "low",
+            Urgency::Normal => "normal",
+            Urgency::Critical => "critical",
+        };
+
+        let mut has_placeholder = false;
+        let mut args: Vec<String> = args
+            .iter()
+            .map(|arg| {
+                has_placeholder |= arg.contains("{title}") || arg.contains("{body}");
+                substitute(arg, &[("{title}", title), ("{body}", body), ("{urgency}", urgency)])
+            })
+            .collect();
+
+        if !has_placeholder {
+            args.push(title.into());
+            args.push(body.into());
+        }
+
+        args
+    }
+}
+
+/// Replace placeholders in a single pass, so substituted text is never expanded again.
+fn substitute(mut text: &str, replacements: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    while let Some(start) = text.find('{') {
+        result.push_str(&text[..start]);
+        text = &text[start..];
+
+        match replacements.iter().find(|(placeholder, _)| text.starts_with(placeholder)) {
+            Some((placeholder, value)) => {
+                result.push_str(value);
+                text = &text[placeholder.len()..];
+            },
+            None => {
+                result.push('{');
+                text = &text[1..];
+            },
+        }
+    }
+    result.push_str(text);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_args() {
+        let command = Program::WithArgs {
+            program: "notify-send".into(),
+            args: vec!["-u".into(), "{urgency}".into(), "{title}: {body}".into()],
+        };
+        let mut notifications = Notifications { command: Some(command), ..Default::default() };
+
+        let args = notifications.args("{body}", "done", Urgency::Critical);
+        assert_eq!(args, ["-u", "critical", "{body}: done"]);
+
+        notifications.command = Some(Program::Just("notify-send".into()));
+        let args = notifications.args("make", "done", Urgency::Normal);
+        assert_eq!(args, ["make", "done"]);
+    }
+}
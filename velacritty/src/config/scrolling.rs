@@ -1,9 +1,14 @@
-use serde::de::Error as SerdeError;
-use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::{self, Formatter};
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use velacritty_config_derive::{ConfigDeserialize, SerdeReplace};
+use velacritty_terminal::grid::SpillMode;
 
 /// Maximum scrollback amount configurable.
+///
+/// This is also the number of lines kept in memory with unlimited scrollback.
 pub const MAX_SCROLLBACK_LINES: u32 = 100_000;
 
 /// Default value for auto_scroll.
@@ -21,26 +26,63 @@ pub struct Scrolling {
     pub auto_scroll: bool,
 
     history: ScrollingHistory,
+
+    /// Encrypt the part of an unlimited history which is written to disk.
+    pub encrypt_history: bool,
 }
 
 impl Default for Scrolling {
     fn default() -> Self {
-        Self { multiplier: 3, auto_scroll: true, history: Default::default() }
+        Self {
+            multiplier: 3,
+            auto_scroll: true,
+            history: Default::default(),
+            encrypt_history: true,
+        }
     }
 }
 
 impl Scrolling {
+    /// Number of history lines kept in memory.
     pub fn history(self) -> u32 {
-        self.history.0
+        match self.history {
+            ScrollingHistory::Lines(lines) => lines,
+            ScrollingHistory::Unlimited => MAX_SCROLLBACK_LINES,
+        }
+    }
+
+    /// Storage for lines exceeding the history kept in memory.
+    pub fn history_spill(self) -> SpillMode {
+        match self.history {
+            ScrollingHistory::Lines(_) => SpillMode::Disabled,
+            ScrollingHistory::Unlimited if self.encrypt_history => SpillMode::Encrypted,
+            ScrollingHistory::Unlimited => SpillMode::Plaintext,
+        }
     }
 }
 
-#[derive(SerdeReplace, Serialize, Copy, Clone, Debug, PartialEq, Eq)]
-struct ScrollingHistory(u32);
+#[derive(SerdeReplace, Copy, Clone, Debug, PartialEq, Eq)]
+enum ScrollingHistory {
+    Lines(u32),
+    /// Lines exceeding the in-memory limit are written to a temporary file.
+    Unlimited,
+}
 
 impl Default for ScrollingHistory {
     fn default() -> Self {
-        Self(10_000)
+        Self::Lines(10_000)
+    }
+}
+
+impl Serialize for ScrollingHistory {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Lines(lines) => serializer.serialize_u32(*lines),
+            Self::Unlimited => serializer.serialize_str("unlimited"),
+        }
     }
 }
 
@@ -49,15 +91,38 @@ impl<'de> Deserialize<'de> for ScrollingHistory {
     where
         D: Deserializer<'de>,
     {
-        let lines = u32::deserialize(deserializer)?;
-
-        if lines > MAX_SCROLLBACK_LINES {
-            Err(SerdeError::custom(format!(
-                "exceeded maximum scrolling history ({lines}/{MAX_SCROLLBACK_LINES})"
-            )))
-        } else {
-            Ok(Self(lines))
+        struct HistoryVisitor;
+
+        impl Visitor<'_> for HistoryVisitor {
+            type Value = ScrollingHistory;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str("a number of lines or \"unlimited\"")
+            }
+
+            fn visit_i64<E: de::Error>(self, lines: i64) -> Result<Self::Value, E> {
+                match u32::try_from(lines) {
+                    Ok(lines) if lines > MAX_SCROLLBACK_LINES => Err(E::custom(format!(
+                        "exceeded maximum scrolling history ({lines}/{MAX_SCROLLBACK_LINES})"
+                    ))),
+                    Ok(lines) => Ok(ScrollingHistory::Lines(lines)),
+                    Err(_) => Err(E::invalid_value(de::Unexpected::Signed(lines), &self)),
+                }
+            }
+
+            fn visit_u64<E: de::Error>(self, lines: u64) -> Result<Self::Value, E> {
+                self.visit_i64(i64::try_from(lines).unwrap_or(i64::MAX))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                match value {
+                    "unlimited" => Ok(ScrollingHistory::Unlimited),
+                    _ => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+                }
+            }
         }
+
+        deserializer.deserialize_any(HistoryVisitor)
     }
 }
 
@@ -95,6 +160,25 @@ mod tests {
         assert!(scrolling.auto_scroll);
     }
 
+    #[test]
+    fn unlimited_history() {
+        let scrolling: Scrolling = toml::from_str("history = \"unlimited\"").unwrap();
+        assert_eq!(scrolling.history(), MAX_SCROLLBACK_LINES);
+        assert_eq!(scrolling.history_spill(), SpillMode::Encrypted);
+
+        let toml = "history = \"unlimited\"\nencrypt_history = false";
+        let scrolling: Scrolling = toml::from_str(toml).unwrap();
+        assert_eq!(scrolling.history_spill(), SpillMode::Plaintext);
+
+        let scrolling: Scrolling = toml::from_str("history = 5000").unwrap();
+        assert_eq!(scrolling.history_spill(), SpillMode::Disabled);
+
+        assert!(toml::from_str::<ScrollingHistory>("\"infinite\"").is_err());
+        assert!(
+            toml::from_str::<ScrollingHistory>(&(MAX_SCROLLBACK_LINES + 1).to_string()).is_err()
+        );
+    }
+
     #[test]
     fn auto_scroll_deserialize_default_when_missing() {
         let toml = r#"
@@ -12,6 +12,8 @@ pub struct Terminal {
     pub osc52: SerdeOsc52,
     /// Path to a shell program to run on startup.
     pub shell: Option<Program>,
+    /// Reorder right-to-left text for display.
+    pub bidi: bool,
 }
 
 #[derive(SerdeReplace, Serialize, Default, Copy, Clone, Debug, PartialEq)]
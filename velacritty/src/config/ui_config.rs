@@ -27,9 +27,11 @@ use crate::config::bindings::{
 use crate::config::color::Colors;
 use crate::config::cursor::Cursor;
 use crate::config::debug::Debug;
+use crate::config::export::Export;
 use crate::config::font::Font;
 use crate::config::general::General;
 use crate::config::mouse::Mouse;
+use crate::config::notifications::Notifications;
 use crate::config::scrolling::Scrolling;
 use crate::config::selection::Selection;
 use crate::config::terminal::Terminal;
@@ -72,6 +74,12 @@ pub struct UiConfig {
     /// Bell configuration.
     pub bell: BellConfig,
 
+    /// Desktop notification configuration.
+    pub notifications: Notifications,
+
+    /// Terminal content export configuration.
+    pub export: Export,
+
     /// RGB values for colors.
     pub colors: Colors,
 
@@ -120,6 +128,7 @@ impl UiConfig {
         TermConfig {
             semantic_escape_chars: self.selection.semantic_escape_chars.clone(),
             scrolling_history: self.scrolling.history() as usize,
+            history_spill: self.scrolling.history_spill(),
             vi_mode_cursor_style: self.cursor.vi_mode_style(),
             default_cursor_style: self.cursor.style(),
             osc52: self.terminal.osc52.0,
@@ -93,10 +93,7 @@ pub fn foreground_process_path(
     master_fd: RawFd,
     shell_pid: u32,
 ) -> Result<PathBuf, Box<dyn Error>> {
-    let mut pid = unsafe { libc::tcgetpgrp(master_fd) };
-    if pid < 0 {
-        pid = shell_pid as pid_t;
-    }
+    let pid = foreground_pid(master_fd, shell_pid);
 
     #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
     let link_path = format!("/proc/{pid}/cwd");
@@ -111,3 +108,29 @@ pub fn foreground_process_path(
 
     Ok(cwd)
 }
+
+/// Get name of controlling process.
+#[cfg(not(windows))]
+pub fn foreground_process_name(master_fd: RawFd, shell_pid: u32) -> Result<String, Box<dyn Error>> {
+    let pid = foreground_pid(master_fd, shell_pid);
+
+    #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
+    let comm_path = format!("/proc/{pid}/comm");
+    #[cfg(target_os = "freebsd")]
+    let comm_path = format!("/compat/linux/proc/{}/comm", pid);
+
+    #[cfg(not(target_os = "macos"))]
+    let name = fs::read_to_string(comm_path)?.trim_end().to_owned();
+
+    #[cfg(target_os = "macos")]
+    let name = macos::proc::name(pid)?;
+
+    Ok(name)
+}
+
+/// Get the process group in the foreground of the terminal, falling back to the shell.
+#[cfg(not(windows))]
+fn foreground_pid(master_fd: RawFd, shell_pid: u32) -> pid_t {
+    let pid = unsafe { libc::tcgetpgrp(master_fd) };
+    if pid < 0 { shell_pid as pid_t } else { pid }
+}
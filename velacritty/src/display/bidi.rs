@@ -0,0 +1,233 @@
+//! Visual reordering of bidirectional text.
+//!
+//! The terminal grid always stores text in logical order. When enabled, every line containing
+//! right-to-left text is reordered with the Unicode Bidirectional Algorithm before it is drawn,
+//! treating each line as a separate left-to-right paragraph.
+
+use std::iter;
+
+use unicode_bidi::{Level, ParagraphBidiInfo};
+
+use velacritty_terminal::grid::{Dimensions, Row};
+use velacritty_terminal::index::{Column, Line, Point};
+use velacritty_terminal::term::Term;
+use velacritty_terminal::term::cell::{Cell, Flags};
+
+use crate::display::content::RenderableCell;
+
+/// Visual order of the lines in the viewport.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct BidiLayout {
+    /// Lines whose visual order differs from their logical order, sorted by viewport line.
+    lines: Vec<ReorderedLine>,
+}
+
+impl BidiLayout {
+    /// Compute the visual order of all lines in the terminal's viewport.
+    pub fn new<T>(term: &Term<T>) -> Self {
+        let grid = term.grid();
+        let display_offset = grid.display_offset() as i32;
+
+        let lines = (0..grid.screen_lines())
+            .filter_map(|line| {
+                let row = &grid[Line(line as i32 - display_offset)];
+                ReorderedLine::new(line, row)
+            })
+            .collect();
+
+        Self { lines }
+    }
+
+    /// Viewport lines which have been reordered.
+    pub fn lines(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lines.iter().map(|line| line.line)
+    }
+
+    /// Move cells to their visual position.
+    ///
+    /// Characters inside right-to-left runs are mirrored, and the cells are sorted by their new
+    /// position.
+    pub fn reorder(&self, cells: &mut [RenderableCell]) {
+        if self.lines.is_empty() {
+            return;
+        }
+
+        for cell in cells.iter_mut() {
+            let line = match self.line(cell.point.line) {
+                Some(line) => line,
+                None => continue,
+            };
+
+            let column = cell.point.column.0;
+            if line.rtl[column] {
+                cell.character = mirror(cell.character);
+            }
+            cell.point.column = Column(line.visual[column]);
+        }
+
+        cells.sort_by_key(|cell| cell.point);
+    }
+
+    /// Convert a point in logical order to its visual position.
+    pub fn visual_point(&self, mut point: Point<usize>) -> Point<usize> {
+        if let Some(column) = self.line(point.line).and_then(|line| line.visual.get(point.column.0))
+        {
+            point.column = Column(*column);
+        }
+        point
+    }
+
+    /// Convert a visual point to its position in logical order.
+    pub fn logical_point(&self, mut point: Point<usize>) -> Point<usize> {
+        if let Some(column) =
+            self.line(point.line).and_then(|line| line.logical.get(point.column.0))
+        {
+            point.column = Column(*column);
+        }
+        point
+    }
+
+    /// Check if the text at a visual point is drawn right-to-left.
+    pub fn is_rtl(&self, point: Point<usize>) -> bool {
+        self.line(point.line).is_some_and(|line| {
+            line.logical.get(point.column.0).is_some_and(|column| line.rtl[*column])
+        })
+    }
+
+    fn line(&self, line: usize) -> Option<&ReorderedLine> {
+        let index = self.lines.binary_search_by_key(&line, |reordered| reordered.line).ok()?;
+        Some(&self.lines[index])
+    }
+}
+
+/// Column mapping of a single reordered line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ReorderedLine {
+    /// Viewport line.
+    line: usize,
+
+    /// Visual column of every logical column.
+    visual: Vec<usize>,
+
+    /// Logical column of every visual column.
+    logical: Vec<usize>,
+
+    /// Direction of every logical column, `true` for right-to-left.
+    rtl: Vec<bool>,
+}
+
+impl ReorderedLine {
+    /// Reorder a row, returning `None` if it does not contain any right-to-left text.
+    fn new(line: usize, row: &Row<Cell>) -> Option<Self> {
+        // Double-width lines are always drawn left-to-right.
+        if row.line_attribute().is_double_width() {
+            return None;
+        }
+
+        let columns = row.len();
+
+        // Keep wide chars and their spacers together, since they're drawn as a single glyph.
+        let mut units: Vec<(usize, usize)> = Vec::with_capacity(columns);
+        let mut text = String::with_capacity(columns);
+        for column in 0..columns {
+            let cell = &row[Column(column)];
+            match units.last_mut() {
+                Some((_, width)) if cell.flags.contains(Flags::WIDE_CHAR_SPACER) => *width += 1,
+                _ => {
+                    units.push((column, 1));
+                    text.push(cell.c);
+                },
+            }
+        }
+
+        let info = ParagraphBidiInfo::new(&text, Some(Level::ltr()));
+        if !info.has_rtl() {
+            return None;
+        }
+
+        let levels = info.reordered_levels_per_char(0..text.len());
+
+        let mut logical = Vec::with_capacity(columns);
+        for index in ParagraphBidiInfo::reorder_visual(&levels) {
+            let (start, width) = units[index];
+            logical.extend(start..start + width);
+        }
+
+        let mut visual = vec![0; columns];
+        for (visual_column, column) in logical.iter().enumerate() {
+            visual[*column] = visual_column;
+        }
+
+        let rtl = units
+            .iter()
+            .zip(&levels)
+            .flat_map(|(&(_, width), level)| iter::repeat_n(level.is_rtl(), width))
+            .collect();
+
+        Some(Self { line, visual, logical, rtl })
+    }
+}
+
+/// Get the mirrored glyph of a character inside right-to-left text.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '<' => '>',
+        '>' => '<',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use velacritty_terminal::term::test::mock_term;
+
+    #[test]
+    fn ltr_text() {
+        let term = mock_term("hello world");
+        assert_eq!(BidiLayout::new(&term), BidiLayout::default());
+    }
+
+    #[test]
+    fn rtl_run() {
+        // Hebrew "shalom" inside of left-to-right text.
+        let term = mock_term("ab שלום (x)");
+        let layout = BidiLayout::new(&term);
+
+        assert_eq!(layout.lines().collect::<Vec<_>>(), [0]);
+
+        let point = |column| Point::new(0, Column(column));
+        assert_eq!(layout.visual_point(point(0)), point(0));
+        assert_eq!(layout.visual_point(point(3)), point(6));
+        assert_eq!(layout.visual_point(point(6)), point(3));
+        assert_eq!(layout.visual_point(point(8)), point(8));
+        assert_eq!(layout.logical_point(point(6)), point(3));
+        assert_eq!(layout.logical_point(point(4)), point(5));
+
+        assert!(layout.is_rtl(point(4)));
+        assert!(!layout.is_rtl(point(1)));
+        assert!(!layout.is_rtl(point(8)));
+    }
+
+    #[test]
+    fn rtl_brackets() {
+        let term = mock_term("אב (גד)");
+        let layout = BidiLayout::new(&term);
+
+        let line = layout.line(0).unwrap();
+        assert_eq!(&line.logical[..7], [6, 5, 4, 3, 2, 1, 0]);
+        assert!(line.rtl[..7].iter().all(|rtl| *rtl));
+        assert_eq!(mirror('('), ')');
+    }
+}
@@ -4,7 +4,8 @@ use std::ops::Deref;
 use std::{cmp, mem};
 
 use velacritty_terminal::event::EventListener;
-use velacritty_terminal::grid::{Dimensions, Indexed};
+use velacritty_terminal::graphics::GraphicCell;
+use velacritty_terminal::grid::{Dimensions, Indexed, LineAttribute};
 use velacritty_terminal::index::{Column, Line, Point};
 use velacritty_terminal::selection::SelectionRange;
 use velacritty_terminal::term::cell::{Cell, Flags, Hyperlink};
@@ -35,6 +36,7 @@ pub struct RenderableContent<'a> {
     colors: &'a List,
     focused_match: Option<&'a Match>,
     size: &'a SizeInfo,
+    line_attributes: Vec<LineAttribute>,
 }
 
 impl<'a> RenderableContent<'a> {
@@ -64,7 +66,16 @@ impl<'a> RenderableContent<'a> {
         // Convert terminal cursor point to viewport position.
         let cursor_point = terminal_content.cursor.point;
         let display_offset = terminal_content.display_offset;
-        let cursor_point = term::point_to_viewport(display_offset, cursor_point).unwrap();
+        let mut cursor_point = term::point_to_viewport(display_offset, cursor_point).unwrap();
+
+        // Double-width and double-height attributes of all visible lines.
+        let grid = term.grid();
+        let line_attributes: Vec<_> = (0..grid.screen_lines())
+            .map(|line| grid[Line(line as i32 - display_offset as i32)].line_attribute())
+            .collect();
+        if line_attributes[cursor_point.line].is_double_width() {
+            cursor_point.column.0 *= 2;
+        }
 
         let hint = if display.hint_state.active() {
             display.hint_state.update_matches(term);
@@ -84,9 +95,16 @@ impl<'a> RenderableContent<'a> {
             search,
             config,
             hint,
+            line_attributes,
         }
     }
 
+    /// Visible lines with double-width characters.
+    pub fn double_width_lines(&self) -> impl Iterator<Item = usize> + '_ {
+        let attributes = self.line_attributes.iter().enumerate();
+        attributes.filter(|(_, attribute)| attribute.is_double_width()).map(|(line, _)| line)
+    }
+
     /// Viewport offset.
     pub fn display_offset(&self) -> usize {
         self.terminal_content.display_offset
@@ -163,6 +181,17 @@ impl Iterator for RenderableContent<'_> {
             let cell = self.terminal_content.display_iter.next()?;
             let mut cell = RenderableCell::new(self, cell);
 
+            // Spread the cells of double-width lines across two columns.
+            if cell.line_attribute.is_double_width() {
+                // Cells in the right half of double-width lines are not visible.
+                if cell.point.column.0 >= self.size.columns() / 2 {
+                    continue;
+                }
+
+                cell.point.column.0 *= 2;
+                cell.flags.insert(Flags::WIDE_CHAR);
+            }
+
             if self.cursor_point == cell.point {
                 // Store the cursor which should be rendered.
                 self.cursor = self.renderable_cursor(&cell);
@@ -194,6 +223,7 @@ pub struct RenderableCell {
     pub bg_alpha: f32,
     pub underline: Rgb,
     pub flags: Flags,
+    pub line_attribute: LineAttribute,
     pub extra: Option<Box<RenderableCellExtra>>,
 }
 
@@ -203,6 +233,7 @@ pub struct RenderableCell {
 pub struct RenderableCellExtra {
     pub zerowidth: Option<Vec<char>>,
     pub hyperlink: Option<Hyperlink>,
+    pub graphic: Option<GraphicCell>,
 }
 
 impl RenderableCell {
@@ -287,15 +318,29 @@ impl RenderableCell {
 
         let zerowidth = cell.zerowidth();
         let hyperlink = cell.hyperlink();
+        let graphic = cell.graphic().cloned();
 
-        let extra = (zerowidth.is_some() || hyperlink.is_some()).then(|| {
+        let extra = (zerowidth.is_some() || hyperlink.is_some() || graphic.is_some()).then(|| {
             Box::new(RenderableCellExtra {
                 zerowidth: zerowidth.map(|zerowidth| zerowidth.to_vec()),
                 hyperlink,
+                graphic,
             })
         });
 
-        RenderableCell { flags, character, bg_alpha, point, fg, bg, underline, extra }
+        let line_attribute = content.line_attributes[point.line];
+
+        RenderableCell {
+            flags,
+            character,
+            bg_alpha,
+            point,
+            fg,
+            bg,
+            underline,
+            line_attribute,
+            extra,
+        }
     }
 
     /// Check if cell contains any renderable content.
@@ -442,6 +487,10 @@ impl RenderableCursor {
     pub fn point(&self) -> Point<usize> {
         self.point
     }
+
+    pub fn set_point(&mut self, point: Point<usize>) {
+        self.point = point;
+    }
 }
 
 /// Regex hints for keyboard shortcuts.
@@ -31,6 +31,7 @@ use velacritty_terminal::grid::Dimensions as TermDimensions;
 use velacritty_terminal::index::{Column, Direction, Line, Point};
 use velacritty_terminal::selection::Selection;
 use velacritty_terminal::term::cell::Flags;
+use velacritty_terminal::term::progress::{Progress, ProgressState};
 use velacritty_terminal::term::{
     self, LineDamageBounds, MIN_COLUMNS, MIN_SCREEN_LINES, Term, TermDamage, TermMode,
 };
@@ -43,6 +44,7 @@ use crate::config::window::Dimensions;
 #[cfg(not(windows))]
 use crate::config::window::StartupMode;
 use crate::display::bell::VisualBell;
+use crate::display::bidi::BidiLayout;
 use crate::display::color::{List, Rgb};
 use crate::display::content::{RenderableContent, RenderableCursor};
 use crate::display::cursor::IntoRects;
@@ -52,11 +54,13 @@ use crate::display::meter::Meter;
 use crate::display::window::Window;
 use crate::event::{Event, EventType, Mouse, SearchState};
 use crate::message_bar::{MessageBuffer, MessageType};
+use crate::renderer::graphics::RenderGraphic;
 use crate::renderer::rects::{RenderLine, RenderLines, RenderRect};
 use crate::renderer::{self, GlyphCache, Renderer, platform};
 use crate::scheduler::{Scheduler, TimerId, Topic};
 use crate::string::{ShortenDirection, StrShortener};
 
+pub mod bidi;
 pub mod color;
 pub mod content;
 pub mod cursor;
@@ -79,6 +83,9 @@ const SHORTENER: char = '…';
 /// Color which is used to highlight damaged rects when debugging.
 const DAMAGE_RECT_COLOR: Rgb = Rgb::new(255, 0, 255);
 
+/// Height of the progress bar in logical pixels.
+const PROGRESS_BAR_HEIGHT: f32 = 3.;
+
 #[derive(Debug)]
 pub enum Error {
     /// Error with window management.
@@ -361,6 +368,12 @@ pub struct Display {
 
     pub visual_bell: VisualBell,
 
+    /// Progress drawn in the last frame.
+    progress: Option<Progress>,
+
+    /// First column of the scroll badge drawn in the last frame.
+    pub unread_badge: Option<Column>,
+
     /// Mapped RGB values for each terminal color.
     pub colors: List,
 
@@ -388,6 +401,12 @@ pub struct Display {
     /// Runtime override for automatic scrolling on terminal output.
     pub auto_scroll_enabled: bool,
 
+    /// Visual order of lines containing right-to-left text.
+    pub bidi: BidiLayout,
+
+    /// Viewport lines drawn with double-width characters.
+    pub double_width_lines: Vec<usize>,
+
     // Mouse point position when highlighting hints.
     hint_mouse_point: Option<Point>,
 
@@ -520,6 +539,8 @@ impl Display {
         Ok(Self {
             context: ManuallyDrop::new(context),
             visual_bell: VisualBell::from(&config.bell),
+            progress: None,
+            unread_badge: None,
             renderer: ManuallyDrop::new(renderer),
             renderer_preference: config.debug.renderer,
             surface: ManuallyDrop::new(surface),
@@ -532,6 +553,8 @@ impl Display {
             size_info,
             font_size,
             auto_scroll_enabled: config.scrolling.auto_scroll,
+            bidi: BidiLayout::default(),
+            double_width_lines: Vec::new(),
             window,
             pending_renderer_update: Default::default(),
             vi_highlighted_hint_age: Default::default(),
@@ -714,6 +737,9 @@ impl Display {
             self.window.set_resize_increments(PhysicalSize::new(cell_width, cell_height));
         }
 
+        // Update cell size used for placing graphics.
+        terminal.graphics_mut().set_cell_size(cell_width as usize, cell_height as usize);
+
         // Resize when terminal when its dimensions have changed.
         if self.size_info.screen_lines() != new_size.screen_lines
             || self.size_info.columns() != new_size.columns()
@@ -784,6 +810,9 @@ impl Display {
         config: &UiConfig,
         search_state: &mut SearchState,
     ) {
+        // Release history rows decompressed since the last frame, like the ones read by a search.
+        terminal.release_decompressed();
+
         // Collect renderable content before the terminal is dropped.
         let mut content = RenderableContent::new(config, self, &terminal, search_state);
         let mut grid_cells = Vec::new();
@@ -793,8 +822,24 @@ impl Display {
         let selection_range = content.selection_range();
         let foreground_color = content.color(NamedColor::Foreground as usize);
         let background_color = content.color(NamedColor::Background as usize);
+        let progress = terminal.progress().map(|progress| {
+            let color = match progress.state {
+                ProgressState::Normal | ProgressState::Indeterminate => NamedColor::Green,
+                ProgressState::Error => NamedColor::Red,
+                ProgressState::Paused => NamedColor::Yellow,
+            };
+            (progress, content.color(color as usize))
+        });
         let display_offset = content.display_offset();
-        let cursor = content.cursor();
+        let double_width_lines: Vec<_> = content.double_width_lines().collect();
+        let mut cursor = content.cursor();
+
+        // Compute the visual order of right-to-left text.
+        let old_bidi = mem::take(&mut self.bidi);
+        if config.terminal.bidi {
+            self.bidi = BidiLayout::new(&terminal);
+        }
+        cursor.set_point(self.bidi.visual_point(cursor.point()));
 
         let cursor_point = terminal.grid().cursor.point;
         let total_lines = terminal.grid().total_lines();
@@ -803,6 +848,9 @@ impl Display {
 
         let vi_mode = terminal.mode().contains(TermMode::VI);
         let vi_cursor_point = if vi_mode { Some(terminal.vi_mode_cursor.point) } else { None };
+        let graphic_updates = terminal.graphics_mut().take_updates();
+        let unread_lines = terminal.grid().unread_lines();
+        let auto_scroll = terminal.grid().auto_scroll_enabled();
 
         // Add damage from the terminal.
         match terminal.damage() {
@@ -818,6 +866,15 @@ impl Display {
         // Drop terminal as early as possible to free lock.
         drop(terminal);
 
+        // Redraw lines with right-to-left text or double-width characters completely, since
+        // damage is tracked in logical columns.
+        let columns = size_info.columns();
+        self.double_width_lines = double_width_lines;
+        let double_width_lines = self.double_width_lines.iter().copied();
+        for line in old_bidi.lines().chain(self.bidi.lines()).chain(double_width_lines) {
+            self.damage_tracker.frame().damage_line(LineDamageBounds::new(line, 0, columns - 1));
+        }
+
         // Invalidate highlighted hints if grid has changed.
         self.validate_hint_highlights(display_offset);
 
@@ -831,17 +888,41 @@ impl Display {
             self.damage_tracker.next_frame().mark_fully_damaged();
         }
 
-        let vi_cursor_viewport_point =
-            vi_cursor_point.and_then(|cursor| term::point_to_viewport(display_offset, cursor));
+        let vi_cursor_viewport_point = vi_cursor_point
+            .and_then(|cursor| term::point_to_viewport(display_offset, cursor))
+            .map(|point| self.bidi.visual_point(point));
         self.damage_tracker.damage_vi_cursor(vi_cursor_viewport_point);
         self.damage_tracker.damage_selection(selection_range, display_offset);
 
         // Make sure this window's OpenGL context is active.
         self.make_current();
 
+        self.renderer.update_graphics(graphic_updates);
+
         self.renderer.clear(background_color, config.window_opacity());
         let mut lines = RenderLines::new();
 
+        // Collect graphics, which are drawn either below or above the text.
+        let mut graphics = Vec::new();
+        let mut graphics_below_text = Vec::new();
+        for cell in &mut grid_cells {
+            let graphic = match cell.extra.as_mut().and_then(|extra| extra.graphic.take()) {
+                Some(graphic) => graphic,
+                None => continue,
+            };
+
+            let graphic = RenderGraphic { point: cell.point, graphic };
+            if graphic.graphic.z_index < 0 {
+                graphics_below_text.push(graphic);
+            } else {
+                graphics.push(graphic);
+            }
+        }
+        self.renderer.draw_graphics(&size_info, graphics_below_text);
+
+        // Move cells to their visual position.
+        self.bidi.reorder(&mut grid_cells);
+
         // Optimize loop hint comparator.
         let has_highlighted_hint =
             self.highlighted_hint.is_some() || self.vi_highlighted_hint.is_some();
@@ -858,11 +939,13 @@ impl Display {
             let highlighted_hint = &self.highlighted_hint;
             let vi_highlighted_hint = &self.vi_highlighted_hint;
             let damage_tracker = &mut self.damage_tracker;
+            let bidi = &self.bidi;
 
             let cells = grid_cells.into_iter().map(|mut cell| {
                 // Underline hints hovered by mouse or vi mode cursor.
                 if has_highlighted_hint {
-                    let point = term::viewport_to_point(display_offset, cell.point);
+                    let point =
+                        term::viewport_to_point(display_offset, bidi.logical_point(cell.point));
                     let hyperlink = cell.extra.as_ref().and_then(|extra| extra.hyperlink.as_ref());
 
                     let should_highlight = |hint: &Option<HintMatch>| {
@@ -882,6 +965,8 @@ impl Display {
             self.renderer.draw_cells(&size_info, glyph_cache, cells);
         }
 
+        self.renderer.draw_graphics(&size_info, graphics);
+
         let mut rects = lines.rects(&metrics, &size_info);
 
         if let Some(vi_cursor_point) = vi_cursor_point {
@@ -896,9 +981,18 @@ impl Display {
             self.draw_line_indicator(config, total_lines, None, display_offset);
         };
 
+        // Show disabled auto-scroll and the number of lines which arrived below the viewport in
+        // the bottom right corner.
+        let bottom_line = size_info.bottommost_line() - display_offset;
+        let obstructed_column =
+            vi_cursor_point.filter(|point| point.line == bottom_line).map(|point| point.column);
+        self.draw_scroll_badge(config, auto_scroll, unread_lines, obstructed_column);
+
         // Draw cursor.
         rects.extend(cursor.rects(&size_info, config.cursor.thickness()));
 
+        self.draw_progress(progress, &mut rects);
+
         // Push visual bell after url/underline/strikeout rects.
         let visual_bell_intensity = self.visual_bell.intensity();
         if visual_bell_intensity != 0. {
@@ -946,7 +1040,8 @@ impl Display {
                 let num_lines = self.size_info.screen_lines();
                 match vi_cursor_viewport_point {
                     None => term::point_to_viewport(display_offset, cursor_point)
-                        .filter(|point| point.line < num_lines),
+                        .filter(|point| point.line < num_lines)
+                        .map(|point| self.bidi.visual_point(point)),
                     point => point,
                 }
             },
@@ -1050,6 +1145,15 @@ impl Display {
         self.damage_tracker.swap_damage();
     }
 
+    /// Request a window size fitting the grid dimensions.
+    #[cfg(not(windows))]
+    pub fn request_dimensions(&self, config: &UiConfig, dimensions: Dimensions) {
+        let (cell_width, cell_height) = (self.size_info.cell_width, self.size_info.cell_height);
+        let scale_factor = self.window.scale_factor as f32;
+        let size = window_size(config, dimensions, cell_width, cell_height, scale_factor);
+        self.window.request_inner_size(size);
+    }
+
     /// Update to a new configuration.
     pub fn update_config(&mut self, config: &UiConfig) {
         self.damage_tracker.debug = config.debug.highlight_damage;
@@ -1380,6 +1484,78 @@ impl Display {
         }
     }
 
+    /// Draw a badge with the auto-scroll state and the number of unread lines below the viewport.
+    fn draw_scroll_badge(
+        &mut self,
+        config: &UiConfig,
+        auto_scroll: bool,
+        unread_lines: usize,
+        obstructed_column: Option<Column>,
+    ) {
+        self.unread_badge = None;
+
+        let mut labels = Vec::new();
+        if !auto_scroll {
+            labels.push(String::from("auto-scroll off"));
+        }
+        match unread_lines {
+            0 => (),
+            1 => labels.push(String::from("1 new line")),
+            _ => labels.push(format!("{unread_lines} new lines")),
+        }
+        if labels.is_empty() {
+            return;
+        }
+
+        let columns = self.size_info.columns();
+        let text = format!("[{}]", labels.join(", "));
+        let column = Column(columns.saturating_sub(text.len()));
+        let point = Point::new(self.size_info.screen_lines() - 1, column);
+
+        // Damage the badge for current and next frame.
+        let damage = LineDamageBounds::new(point.line, point.column.0, columns - 1);
+        self.damage_tracker.frame().damage_line(damage);
+        self.damage_tracker.next_frame().damage_line(damage);
+
+        // Do not render anything if it would obscure the vi mode cursor.
+        if obstructed_column.is_some_and(|obstructed_column| obstructed_column >= column) {
+            return;
+        }
+
+        let colors = &config.colors;
+        let fg = colors.line_indicator.foreground.unwrap_or(colors.primary.background);
+        let bg = colors.line_indicator.background.unwrap_or(colors.primary.foreground);
+
+        let glyph_cache = &mut self.glyph_cache;
+        self.renderer.draw_string(point, fg, bg, text.chars(), &self.size_info, glyph_cache);
+        self.unread_badge = Some(column);
+    }
+
+    /// Draw the progress reported by the application as a bar at the top of the window.
+    fn draw_progress(&mut self, progress: Option<(Progress, Rgb)>, rects: &mut Vec<RenderRect>) {
+        let size_info = self.size_info;
+        let height = (PROGRESS_BAR_HEIGHT * self.window.scale_factor as f32).round();
+
+        // Clear the bar of the previous frame.
+        if progress.is_some() || self.progress.is_some() {
+            let width = size_info.width() as i32;
+            self.damage_tracker.frame().add_viewport_rect(&size_info, 0, 0, width, height as i32);
+        }
+        self.progress = progress.map(|(progress, _)| progress);
+
+        let (progress, color) = match progress {
+            Some(progress) => progress,
+            None => return,
+        };
+        let alpha = if progress.state == ProgressState::Indeterminate { 0.5 } else { 1. };
+
+        // Show indeterminate progress and states without a percentage as a full bar.
+        let fraction = progress.percent.map_or(1., |percent| f32::from(percent) / 100.);
+        let width = size_info.width() * fraction;
+
+        rects.push(RenderRect::new(0., 0., width, height, color, alpha));
+    }
+
     /// Highlight damaged rects.
     ///
     /// This function is for debug purposes only.
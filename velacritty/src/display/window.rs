@@ -39,6 +39,7 @@ use winit::window::{
 };
 
 use velacritty_terminal::index::Point;
+use velacritty_terminal::term::progress::{Progress, ProgressState};
 
 use crate::cli::WindowOptions;
 use crate::config::UiConfig;
@@ -117,6 +118,9 @@ pub struct Window {
     /// Current window title.
     title: String,
 
+    /// Progress shown in front of the window title.
+    progress: Option<Progress>,
+
     is_x11: bool,
     current_mouse_cursor: CursorIcon,
     mouse_visible: bool,
@@ -205,6 +209,7 @@ impl Window {
             hold: options.terminal_options.hold,
             requested_redraw: false,
             title: identity.title,
+            progress: None,
             current_mouse_cursor,
             mouse_visible: true,
             has_frame: true,
@@ -234,7 +239,7 @@ impl Window {
         self.window.set_visible(visibility);
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(unix)]
     #[inline]
     pub fn focus_window(&self) {
         self.window.focus_window();
@@ -244,7 +249,14 @@ impl Window {
     #[inline]
     pub fn set_title(&mut self, title: String) {
         self.title = title;
-        self.window.set_title(&self.title);
+        self.update_title();
+    }
+
+    /// Set the progress shown in front of the window title.
+    #[inline]
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+        self.update_title();
     }
 
     /// Get the window title.
@@ -253,6 +265,29 @@ impl Window {
         &self.title
     }
 
+    fn update_title(&self) {
+        let progress = match self.progress {
+            Some(progress) => progress,
+            None => {
+                self.window.set_title(&self.title);
+                return;
+            },
+        };
+
+        let label = match progress.state {
+            ProgressState::Indeterminate => Some("…"),
+            ProgressState::Error => Some("error"),
+            ProgressState::Paused => Some("paused"),
+            ProgressState::Normal => None,
+        };
+        let percent = progress.percent.map(|percent| format!("{percent}%"));
+        let prefix = match (percent, label) {
+            (Some(percent), Some(label)) => format!("{percent} {label}"),
+            (percent, label) => percent.or(label.map(String::from)).unwrap_or_default(),
+        };
+        self.window.set_title(&format!("[{prefix}] {}", self.title));
+    }
+
     #[inline]
     pub fn request_redraw(&mut self) {
         if !self.requested_redraw {
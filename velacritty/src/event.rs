@@ -8,7 +8,7 @@ use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::ffi::OsStr;
-use std::fmt::Debug;
+use std::fmt::{Debug, Write as _};
 #[cfg(not(windows))]
 use std::os::unix::io::RawFd;
 #[cfg(unix)]
@@ -41,6 +41,7 @@ use velacritty_terminal::grid::{BidirectionalIterator, Dimensions, Scroll};
 use velacritty_terminal::index::{Boundary, Column, Direction, Line, Point, Side};
 use velacritty_terminal::selection::{Selection, SelectionType};
 use velacritty_terminal::term::cell::Flags;
+use velacritty_terminal::term::notification::Urgency;
 use velacritty_terminal::term::search::{Match, RegexSearch};
 use velacritty_terminal::term::{self, ClipboardType, Term, TermMode};
 use velacritty_terminal::vte::ansi::NamedColor;
@@ -49,21 +50,29 @@ use velacritty_terminal::vte::ansi::NamedColor;
 use crate::cli::{IpcConfig, ParsedOptions};
 use crate::cli::{Options as CliOptions, WindowOptions};
 use crate::clipboard::Clipboard;
+#[cfg(unix)]
+use crate::config::export::ExportFormat;
 use crate::config::ui_config::{HintAction, HintInternalAction};
 use crate::config::{self, UiConfig};
 #[cfg(not(windows))]
 use crate::daemon::foreground_process_path;
 use crate::daemon::spawn_daemon;
+use crate::display::bidi::BidiLayout;
 use crate::display::color::Rgb;
 use crate::display::hint::HintMatch;
 use crate::display::window::Window;
 use crate::display::{Display, Preedit, SizeInfo};
+#[cfg(unix)]
+use crate::export::{ExportOptions, TextRange};
+#[cfg(unix)]
+use crate::input::keyboard::KeyPress;
 use crate::input::{self, ActionContext as _, FONT_SIZE_STEP};
 #[cfg(unix)]
-use crate::ipc::{self, SocketReply};
+use crate::ipc::{self, SocketReply, WindowCommand};
 use crate::logging::{LOG_TARGET_CONFIG, LOG_TARGET_WINIT};
 use crate::message_bar::{Message, MessageBuffer};
 use crate::scheduler::{Scheduler, TimerId, Topic};
+use crate::session::{self, Session, WindowSession};
 use crate::window_context::WindowContext;
 
 /// Duration after the last user input until an unlimited search is performed.
@@ -103,6 +112,9 @@ pub struct Processor {
     global_ipc_options: ParsedOptions,
     cli_options: CliOptions,
     config: Rc<UiConfig>,
+
+    /// Windows closed while others are still open, saved along with the remaining ones.
+    closed_sessions: Vec<WindowSession>,
 }
 
 impl Processor {
@@ -145,6 +157,7 @@ impl Processor {
             windows: Default::default(),
             #[cfg(unix)]
             global_ipc_options: Default::default(),
+            closed_sessions: Default::default(),
             config_monitor,
         }
     }
@@ -157,7 +170,7 @@ impl Processor {
         &mut self,
         event_loop: &ActiveEventLoop,
         window_options: WindowOptions,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<WindowId, Box<dyn Error>> {
         let window_context = WindowContext::initial(
             event_loop,
             self.proxy.clone(),
@@ -166,9 +179,11 @@ impl Processor {
         )?;
 
         self.gl_config = Some(window_context.display.gl_context().config());
-        self.windows.insert(window_context.id(), window_context);
 
-        Ok(())
+        let window_id = window_context.id();
+        self.windows.insert(window_id, window_context);
+
+        Ok(window_id)
     }
 
     /// Create a new terminal window.
@@ -176,7 +191,7 @@ impl Processor {
         &mut self,
         event_loop: &ActiveEventLoop,
         options: WindowOptions,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<WindowId, Box<dyn Error>> {
         let gl_config = self.gl_config.as_ref().unwrap();
 
         // Override config with CLI/IPC options.
@@ -195,10 +210,77 @@ impl Processor {
             config_overrides,
         )?;
 
-        self.windows.insert(window_context.id(), window_context);
+        let window_id = window_context.id();
+        self.windows.insert(window_id, window_context);
+        Ok(window_id)
+    }
+
+    /// Reopen the windows of a saved session.
+    fn restore_session(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        mut window_options: WindowOptions,
+        session: Session,
+    ) -> Result<(), Box<dyn Error>> {
+        for window in session.windows {
+            let options = window.window_options(window_options.clone());
+
+            // Only record the first window, since all of them would write to the same file.
+            window_options.terminal_options.record = None;
+
+            let window_id = if self.gl_config.is_none() {
+                self.create_initial_window(event_loop, options)?
+            } else {
+                for window_context in self.windows.values_mut() {
+                    window_context.display.make_not_current();
+                }
+
+                match self.create_window(event_loop, options) {
+                    Ok(window_id) => window_id,
+                    Err(err) => {
+                        error!("Could not open window: {err:?}");
+                        continue;
+                    },
+                }
+            };
+
+            if let Some(window_context) = self.windows.get_mut(&window_id) {
+                window_context.restore(window);
+            }
+        }
+
         Ok(())
     }
 
+    /// Window addressed by an IPC message, defaulting to the focused window.
+    #[cfg(unix)]
+    fn ipc_window(&self, window_id: Option<WindowId>) -> Option<&WindowContext> {
+        match window_id {
+            Some(window_id) => self.windows.get(&window_id),
+            None => self
+                .windows
+                .values()
+                .find(|window_context| window_context.is_focused())
+                .or_else(|| self.windows.values().next()),
+        }
+    }
+
+    /// Mutable version of [`Self::ipc_window`].
+    #[cfg(unix)]
+    fn ipc_window_mut(&mut self, window_id: Option<WindowId>) -> Option<&mut WindowContext> {
+        let window_id = self.ipc_window(window_id)?.id();
+        self.windows.get_mut(&window_id)
+    }
+
+    /// Save `windows` as the session reopened by `--restore`.
+    fn save_session(windows: Vec<WindowSession>) {
+        let session = Session { windows };
+        match session::save(&session) {
+            Ok(path) => info!("Saved session to {path:?}"),
+            Err(err) => error!("Unable to save session: {err}"),
+        }
+    }
+
     /// Run the event loop.
     ///
     /// The result is exit code generate from the loop.
@@ -241,7 +323,21 @@ impl ApplicationHandler<Event> for Processor {
         }
 
         if let Some(window_options) = self.initial_window_options.take() {
-            if let Err(err) = self.create_initial_window(event_loop, window_options) {
+            let session = match self.cli_options.restore.then(session::load) {
+                Some(Ok(session)) if !session.windows.is_empty() => Some(session),
+                Some(Err(err)) => {
+                    error!("Unable to restore session: {err}");
+                    None
+                },
+                _ => None,
+            };
+
+            let result = match session {
+                Some(session) => self.restore_session(event_loop, window_options, session),
+                None => self.create_initial_window(event_loop, window_options).map(|_| ()),
+            };
+
+            if let Err(err) = result {
                 self.initial_window_error = Some(err);
                 event_loop.exit();
                 return;
@@ -345,6 +441,105 @@ impl ApplicationHandler<Event> for Processor {
                     ipc::send_reply(&mut stream, SocketReply::GetConfig(config_json));
                 }
             },
+            // Process IPC memory usage requests.
+            #[cfg(unix)]
+            (EventType::IpcGetMemoryUsage(stream), _) => {
+                let mut report = String::new();
+                for (window_id, window_context) in &self.windows {
+                    let usage = window_context.memory_usage();
+                    let _ = writeln!(
+                        report,
+                        "{}: {:.1} MiB ({} rows, {} compressed, {} on disk)",
+                        u64::from(*window_id),
+                        usage.bytes as f64 / (1024. * 1024.),
+                        usage.rows,
+                        usage.compressed_rows,
+                        usage.spilled_rows,
+                    );
+                }
+
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, SocketReply::GetMemoryUsage(report));
+                }
+            },
+            // Process IPC export requests.
+            #[cfg(unix)]
+            (EventType::IpcExportScrollback(stream, options), window_id) => {
+                let result = match self.ipc_window(window_id.copied()) {
+                    Some(window_context) => window_context.export(&options),
+                    None => Err("no window found".into()),
+                };
+
+                let reply = SocketReply::ExportScrollback(result.map_err(|err| err.to_string()));
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, reply);
+                }
+            },
+            // Process IPC text requests.
+            #[cfg(unix)]
+            (EventType::IpcGetText(stream, range, format), window_id) => {
+                let result = match self.ipc_window(window_id.copied()) {
+                    Some(window_context) => window_context.text(&range, format),
+                    None => Err("no window found".into()),
+                };
+
+                let reply = SocketReply::GetText(result.map_err(|err| err.to_string()));
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, reply);
+                }
+            },
+            // Process IPC window list requests.
+            #[cfg(unix)]
+            (EventType::IpcListWindows(stream), _) => {
+                let mut windows: Vec<_> = self.windows.values().map(WindowContext::info).collect();
+                windows.sort_by_key(|window| window.id);
+
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, SocketReply::ListWindows(windows));
+                }
+            },
+            // Process IPC window commands.
+            #[cfg(unix)]
+            (EventType::IpcWindowCommand(stream, command), window_id) => {
+                let window_id = self.ipc_window(window_id.copied()).map(WindowContext::id);
+                let result = match window_id.and_then(|id| self.windows.get_mut(&id)) {
+                    Some(window_context) => {
+                        let window_id = window_context.id();
+                        match command {
+                            WindowCommand::Focus => window_context.focus(),
+                            WindowCommand::Close => window_context.close(),
+                            WindowCommand::SetTitle(title) => window_context.set_title(title),
+                            // Input is written by the input processor of the window.
+                            WindowCommand::SendText(text, bracketed) => {
+                                let event_type = EventType::IpcSendText(text, bracketed);
+                                let _ = self.proxy.send_event(Event::new(event_type, window_id));
+                            },
+                            WindowCommand::SendKeys(keys) => {
+                                let event_type = EventType::IpcSendKeys(keys);
+                                let _ = self.proxy.send_event(Event::new(event_type, window_id));
+                            },
+                        }
+                        Ok(())
+                    },
+                    None => Err(String::from("no window found")),
+                };
+
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, SocketReply::WindowCommand(result));
+                }
+            },
+            // Process IPC auto-scroll toggles.
+            #[cfg(unix)]
+            (EventType::IpcToggleAutoScroll(stream), window_id) => {
+                let result = match self.ipc_window_mut(window_id.copied()) {
+                    Some(window_context) => Ok(window_context.toggle_auto_scroll()),
+                    None => Err(String::from("no window found")),
+                };
+
+                if let Ok(mut stream) = stream.try_clone() {
+                    ipc::send_reply(&mut stream, SocketReply::ToggleAutoScroll(result));
+                }
+            },
             (EventType::ConfigReload(path), _) => {
                 // Clear config logs from message bar for all terminals.
                 for window_context in self.windows.values_mut() {
@@ -374,6 +569,9 @@ impl ApplicationHandler<Event> for Processor {
                     }
                 }
             },
+            (EventType::SaveSession, _) => {
+                Self::save_session(self.windows.values().map(WindowContext::session).collect())
+            },
             // Create a new terminal window.
             (EventType::CreateWindow(options), _) => {
                 // XXX Ensure that no context is current when creating a new window,
@@ -416,6 +614,11 @@ impl ApplicationHandler<Event> for Processor {
                     }
                 }
             },
+            (EventType::Terminal(TerminalEvent::RecordingFailed(err)), Some(window_id)) => {
+                if let Some(window_context) = self.windows.get_mut(window_id) {
+                    window_context.recording_failed(&err);
+                }
+            },
             (EventType::Terminal(TerminalEvent::Exit), Some(window_id)) => {
                 // Remove the closed terminal.
                 let window_context = match self.windows.entry(*window_id) {
@@ -431,6 +634,14 @@ impl ApplicationHandler<Event> for Processor {
                 // Unschedule pending events.
                 self.scheduler.unschedule_window(window_context.id());
 
+                // Keep the window, so it is restored even if it isn't the last one closed.
+                if self.config.general.save_session {
+                    self.closed_sessions.push(window_context.session());
+                    if self.windows.is_empty() {
+                        Self::save_session(mem::take(&mut self.closed_sessions));
+                    }
+                }
+
                 // Shutdown if no more terminals are open.
                 if self.windows.is_empty() && !self.cli_options.daemon {
                     // Write ref tests of last window to disk.
@@ -450,6 +661,28 @@ impl ApplicationHandler<Event> for Processor {
                     }
                 }
             },
+            (EventType::ExportScrollback, Some(window_id)) => {
+                if let Some(window_context) = self.windows.get_mut(window_id) {
+                    window_context.export_scrollback();
+                }
+            },
+            (EventType::ToggleRecording, Some(window_id)) => {
+                if let Some(window_context) = self.windows.get_mut(window_id) {
+                    window_context.toggle_recording();
+                }
+            },
+            #[cfg(not(windows))]
+            (EventType::ToggleReplayPause, Some(window_id)) => {
+                if let Some(window_context) = self.windows.get(window_id) {
+                    window_context.toggle_replay_pause();
+                }
+            },
+            #[cfg(not(windows))]
+            (EventType::StepReplay, Some(window_id)) => {
+                if let Some(window_context) = self.windows.get(window_id) {
+                    window_context.step_replay();
+                }
+            },
             (payload, Some(window_id)) => {
                 if let Some(window_context) = self.windows.get_mut(window_id) {
                     window_context.handle_event(
@@ -496,6 +729,13 @@ impl ApplicationHandler<Event> for Processor {
             info!("Exiting the event loop");
         }
 
+        // Save windows which are still open, when exiting without closing them first.
+        if self.config.general.save_session && !self.windows.is_empty() {
+            let mut windows = mem::take(&mut self.closed_sessions);
+            windows.extend(self.windows.values().map(WindowContext::session));
+            Self::save_session(windows);
+        }
+
         match self.gl_config.take().map(|config| config.display()) {
             #[cfg(not(target_os = "macos"))]
             Some(glutin::display::Display::Egl(display)) => {
@@ -552,9 +792,32 @@ pub enum EventType {
     IpcConfig(IpcConfig),
     #[cfg(unix)]
     IpcGetConfig(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcGetMemoryUsage(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcExportScrollback(Arc<UnixStream>, ExportOptions),
+    #[cfg(unix)]
+    IpcToggleAutoScroll(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcGetText(Arc<UnixStream>, TextRange, ExportFormat),
+    #[cfg(unix)]
+    IpcSendText(String, bool),
+    #[cfg(unix)]
+    IpcSendKeys(Vec<KeyPress>),
+    #[cfg(unix)]
+    IpcListWindows(Arc<UnixStream>),
+    #[cfg(unix)]
+    IpcWindowCommand(Arc<UnixStream>, WindowCommand),
     BlinkCursor,
     BlinkCursorTimeout,
     SearchNext,
+    SaveSession,
+    ExportScrollback,
+    ToggleRecording,
+    #[cfg(not(windows))]
+    ToggleReplayPause,
+    #[cfg(not(windows))]
+    StepReplay,
     Frame,
     Resize(PhysicalSize<u32>),
 }
@@ -673,6 +936,7 @@ pub struct ActionContext<'a, N, T> {
     pub config: &'a UiConfig,
     pub cursor_blink_timed_out: &'a mut bool,
     pub prev_bell_cmd: &'a mut Option<Instant>,
+    pub prev_notification: &'a mut Option<Instant>,
     #[cfg(target_os = "macos")]
     pub event_loop: &'a ActiveEventLoop,
     pub event_proxy: &'a EventLoopProxy<Event>,
@@ -686,6 +950,8 @@ pub struct ActionContext<'a, N, T> {
     pub master_fd: RawFd,
     #[cfg(not(windows))]
     pub shell_pid: u32,
+    #[cfg(not(windows))]
+    pub replay_active: bool,
 }
 
 impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionContext<'a, N, T> {
@@ -754,6 +1020,28 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.clipboard.store(ty, text);
     }
 
+    /// Select and copy the output of the command below the vi cursor or mouse.
+    ///
+    /// If there is no command output at that point, the most recent output is used instead.
+    fn copy_command_output(&mut self) {
+        let point = if self.terminal.mode().contains(TermMode::VI) {
+            self.terminal.vi_mode_cursor.point
+        } else {
+            let display_offset = self.terminal.grid().display_offset();
+            self.mouse.point(&self.size_info(), display_offset)
+        };
+
+        let output =
+            self.terminal.command_output(point).or_else(|| self.terminal.last_command_output());
+        let start = match output {
+            Some(output) => *output.start(),
+            None => return,
+        };
+
+        self.start_selection(SelectionType::CommandOutput, start, Side::Left);
+        self.copy_selection(ClipboardType::Clipboard);
+    }
+
     fn selection_is_empty(&self) -> bool {
         self.terminal.selection.as_ref().is_none_or(Selection::is_empty)
     }
@@ -869,6 +1157,11 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
                 break;
             }
 
+            // New instances shouldn't reopen the saved session.
+            if arg == "--restore" {
+                continue;
+            }
+
             // On unix, the working directory of the foreground shell is used by `start_daemon`.
             #[cfg(not(windows))]
             if arg == "--working-directory" {
@@ -885,8 +1178,9 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     #[cfg(not(windows))]
     fn create_new_window(&mut self, #[cfg(target_os = "macos")] tabbing_id: Option<String>) {
         let mut options = WindowOptions::default();
-        options.terminal_options.working_directory =
-            foreground_process_path(self.master_fd, self.shell_pid).ok();
+        options.terminal_options.working_directory = self
+            .shell_working_directory()
+            .or_else(|| foreground_process_path(self.master_fd, self.shell_pid).ok());
 
         #[cfg(target_os = "macos")]
         {
@@ -898,9 +1192,37 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
     #[cfg(windows)]
     fn create_new_window(&mut self) {
-        let _ = self
-            .event_proxy
-            .send_event(Event::new(EventType::CreateWindow(WindowOptions::default()), None));
+        let mut options = WindowOptions::default();
+        options.terminal_options.working_directory = self.shell_working_directory();
+
+        let _ = self.event_proxy.send_event(Event::new(EventType::CreateWindow(options), None));
+    }
+
+    fn save_session(&mut self) {
+        let _ = self.event_proxy.send_event(Event::new(EventType::SaveSession, None));
+    }
+
+    fn export_scrollback(&mut self) {
+        // Export outside of the terminal lock, since writing the file might take a while.
+        let window_id = self.display.window.id();
+        let _ = self.event_proxy.send_event(Event::new(EventType::ExportScrollback, window_id));
+    }
+
+    fn toggle_recording(&mut self) {
+        let window_id = self.display.window.id();
+        let _ = self.event_proxy.send_event(Event::new(EventType::ToggleRecording, window_id));
+    }
+
+    #[cfg(not(windows))]
+    fn toggle_replay_pause(&mut self) {
+        let window_id = self.display.window.id();
+        let _ = self.event_proxy.send_event(Event::new(EventType::ToggleReplayPause, window_id));
+    }
+
+    #[cfg(not(windows))]
+    fn step_replay(&mut self) {
+        let window_id = self.display.window.id();
+        let _ = self.event_proxy.send_event(Event::new(EventType::StepReplay, window_id));
     }
 
     fn spawn_daemon<I, S>(&self, program: &str, args: I)
@@ -1179,7 +1501,7 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.search_state
             .dfas
             .as_mut()
-            .and_then(|dfas| self.terminal.search_next(dfas, origin, direction, side, None))
+            .and_then(|dfas| self.terminal.search_next_paged(dfas, origin, direction, side, None))
     }
 
     #[inline]
@@ -1192,6 +1514,11 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.search_state.history_index.is_some()
     }
 
+    #[cfg(not(windows))]
+    fn replay_active(&self) -> bool {
+        self.replay_active
+    }
+
     /// Handle keyboard typing start.
     ///
     /// This will temporarily disable some features like terminal cursor blinking or the mouse
@@ -1382,9 +1709,16 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
             }
         } else if self.inline_search_state.char_pending {
             self.inline_search_input(text);
-        } else if bracketed && self.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
-            self.on_terminal_input_start();
+        } else {
+            self.paste_to_pty(text, bracketed);
+        }
+    }
+
+    /// Write pasted text to the PTY, even while the search is active.
+    fn paste_to_pty(&mut self, text: &str, bracketed: bool) {
+        self.on_terminal_input_start();
 
+        if bracketed && self.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
             self.write_to_pty(&b"\x1b[200~"[..]);
 
             // Write filtered escape sequences.
@@ -1397,8 +1731,6 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
             self.write_to_pty(&b"\x1b[201~"[..]);
         } else {
-            self.on_terminal_input_start();
-
             let payload = if bracketed {
                 // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
                 // pasted data from keystrokes.
@@ -1509,6 +1841,12 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 }
 
 impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
+    /// Working directory reported by the shell, if it is accessible from this machine.
+    fn shell_working_directory(&self) -> Option<PathBuf> {
+        let cwd = self.terminal.working_directory().filter(|cwd| cwd.is_local())?;
+        cwd.path.is_dir().then(|| cwd.path.clone())
+    }
+
     fn update_search(&mut self) {
         let regex = match self.search_state.regex() {
             Some(regex) => regex,
@@ -1572,7 +1910,7 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         // Jump to the next match.
         let direction = self.search_state.direction;
         let clamped_origin = self.search_state.origin.grid_clamp(self.terminal, Boundary::Grid);
-        match self.terminal.search_next(dfas, clamped_origin, direction, Side::Left, limit) {
+        match self.terminal.search_next_paged(dfas, clamped_origin, direction, Side::Left, limit) {
             Some(regex_match) => {
                 let old_offset = self.terminal.grid().display_offset() as i32;
 
@@ -1626,6 +1964,32 @@ impl<'a, N: Notify + 'a, T: EventListener> ActionContext<'a, N, T> {
         self.search_state.focused_match = None;
     }
 
+    /// Run the notification command for a desktop notification.
+    fn show_notification(&mut self, title: String, body: String, urgency: Urgency) {
+        let config = &self.config.notifications;
+        let command = match &config.command {
+            Some(command) => command,
+            None => return,
+        };
+
+        if config.suppress_when_focused && self.terminal.is_focused {
+            return;
+        }
+
+        if self.prev_notification.is_some_and(|instant| instant.elapsed() < config.cooldown()) {
+            debug!("Dropping notification {title:?} during cooldown");
+            return;
+        }
+
+        // Fall back to the window title for notifications without one.
+        let title = if title.is_empty() { self.display.window.title().to_owned() } else { title };
+
+        let args = config.args(&title, &body, urgency);
+        self.spawn_daemon(command.program(), &args);
+
+        *self.prev_notification = Some(Instant::now());
+    }
+
     /// Update the cursor blinking state.
     fn update_cursor_blinking(&mut self) {
         // Get config cursor style.
@@ -1791,6 +2155,8 @@ pub struct Mouse {
     pub block_hint_launcher: bool,
     pub hint_highlight_dirty: bool,
     pub inside_text_area: bool,
+    pub bidi: BidiLayout,
+    pub double_width_lines: Vec<usize>,
     pub x: usize,
     pub y: usize,
 }
@@ -1809,6 +2175,8 @@ impl Default for Mouse {
             block_hint_launcher: Default::default(),
             inside_text_area: Default::default(),
             accumulated_scroll: Default::default(),
+            bidi: Default::default(),
+            double_width_lines: Default::default(),
             x: Default::default(),
             y: Default::default(),
         }
@@ -1822,13 +2190,39 @@ impl Mouse {
     /// coordinates will be clamped to the closest grid coordinates.
     #[inline]
     pub fn point(&self, size: &SizeInfo, display_offset: usize) -> Point {
-        let col = self.x.saturating_sub(size.padding_x() as usize) / (size.cell_width() as usize);
+        let point = self.bidi.logical_point(self.viewport_point(size));
+        term::viewport_to_point(display_offset, point)
+    }
+
+    /// Check if the mouse is on top of right-to-left text.
+    pub fn is_rtl(&self, size: &SizeInfo) -> bool {
+        self.bidi.is_rtl(self.viewport_point(size))
+    }
+
+    /// Width of the cells below the mouse.
+    ///
+    /// Cells on double-width lines are drawn twice as wide as regular cells.
+    pub fn cell_width(&self, size: &SizeInfo) -> f32 {
+        if self.double_width_lines.contains(&self.viewport_line(size)) {
+            size.cell_width() * 2.
+        } else {
+            size.cell_width()
+        }
+    }
+
+    /// Visual position of the mouse in the viewport.
+    fn viewport_point(&self, size: &SizeInfo) -> Point<usize> {
+        let col =
+            self.x.saturating_sub(size.padding_x() as usize) / (self.cell_width(size) as usize);
         let col = min(Column(col), size.last_column());
 
-        let line = self.y.saturating_sub(size.padding_y() as usize) / (size.cell_height() as usize);
-        let line = min(line, size.bottommost_line().0 as usize);
+        Point::new(self.viewport_line(size), col)
+    }
 
-        term::viewport_to_point(display_offset, Point::new(line, col))
+    /// Viewport line below the mouse.
+    fn viewport_line(&self, size: &SizeInfo) -> usize {
+        let line = self.y.saturating_sub(size.padding_y() as usize) / (size.cell_height() as usize);
+        min(line, size.bottommost_line().0 as usize)
     }
 }
 
@@ -1913,6 +2307,9 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                             }
                         }
                     },
+                    TerminalEvent::Notification { title, body, urgency } => {
+                        self.ctx.show_notification(title, body, urgency);
+                    },
                     TerminalEvent::ClipboardStore(clipboard_type, content) => {
                         if self.ctx.terminal.is_focused {
                             self.ctx.clipboard.store(clipboard_type, content);
@@ -1940,10 +2337,27 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                     TerminalEvent::PtyWrite(text) => self.ctx.write_to_pty(text.into_bytes()),
                     TerminalEvent::MouseCursorDirty => self.reset_mouse_cursor(),
                     TerminalEvent::CursorBlinkingChange => self.ctx.update_cursor_blinking(),
-                    TerminalEvent::Exit | TerminalEvent::ChildExit(_) | TerminalEvent::Wakeup => (),
+                    // The working directory is read from the terminal once it is required.
+                    TerminalEvent::Progress(progress) => self.ctx.window().set_progress(progress),
+                    TerminalEvent::WorkingDirectory(_) => (),
+                    TerminalEvent::Exit
+                    | TerminalEvent::ChildExit(_)
+                    | TerminalEvent::Wakeup
+                    | TerminalEvent::RecordingFailed(_) => (),
                 },
                 #[cfg(unix)]
-                EventType::IpcConfig(_) | EventType::IpcGetConfig(..) => (),
+                EventType::IpcConfig(_)
+                | EventType::IpcGetConfig(..)
+                | EventType::IpcGetMemoryUsage(..)
+                | EventType::IpcExportScrollback(..)
+                | EventType::IpcToggleAutoScroll(..)
+                | EventType::IpcGetText(..)
+                | EventType::IpcListWindows(_)
+                | EventType::IpcWindowCommand(..) => (),
+                #[cfg(unix)]
+                EventType::IpcSendText(text, bracketed) => self.ctx.paste_to_pty(&text, bracketed),
+                #[cfg(unix)]
+                EventType::IpcSendKeys(keys) => self.write_keys(&keys),
                 EventType::Resize(size) => {
                     // Apply the debounced resize operation.
                     self.ctx.display.pending_update.set_dimensions(size);
@@ -1951,7 +2365,12 @@ impl input::Processor<EventProxy, ActionContext<'_, Notifier, EventProxy>> {
                 EventType::Message(_)
                 | EventType::ConfigReload(_)
                 | EventType::CreateWindow(_)
+                | EventType::SaveSession
+                | EventType::ExportScrollback
+                | EventType::ToggleRecording
                 | EventType::Frame => (),
+                #[cfg(not(windows))]
+                EventType::ToggleReplayPause | EventType::StepReplay => (),
             },
             WinitEvent::WindowEvent { event, .. } => {
                 match event {
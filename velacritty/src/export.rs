@@ -0,0 +1,244 @@
+//! Exporting terminal content to files.
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Args, ValueHint};
+use serde::{Deserialize, Serialize};
+
+use velacritty_terminal::grid::Dimensions;
+use velacritty_terminal::index::{Column, Line, Point};
+use velacritty_terminal::selection::SelectionRange;
+use velacritty_terminal::term::Term;
+use velacritty_terminal::term::color::{COUNT, Colors};
+
+use crate::config::export::{Export, ExportFormat};
+use crate::display::color::List;
+
+/// Options for exporting the terminal content.
+#[derive(Args, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// File which is written [default: new file in `export.directory`].
+    #[clap(value_hint = ValueHint::FilePath)]
+    pub path: Option<PathBuf>,
+
+    /// Format of the file [default: `export.format`].
+    #[clap(short, long)]
+    pub format: Option<ExportFormat>,
+
+    /// Only export the current selection.
+    #[clap(long)]
+    pub selection: bool,
+}
+
+/// Part of the terminal content.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub enum TextRange {
+    /// Lines of the terminal screen, ignoring the scroll position.
+    #[default]
+    Screen,
+
+    /// Lines of the history followed by the screen.
+    Scrollback,
+
+    /// Current selection.
+    Selection,
+
+    /// Lines from `start` up to `end`, where 0 is the top line of the screen and history lines
+    /// are negative.
+    Lines(Range<i32>),
+}
+
+impl FromStr for TextRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "screen" => Ok(Self::Screen),
+            "scrollback" | "all" => Ok(Self::Scrollback),
+            "selection" => Ok(Self::Selection),
+            _ => {
+                let error =
+                    || String::from("expected screen, scrollback, selection or lines(START..END)");
+                let lines = s.strip_prefix("lines(").and_then(|s| s.strip_suffix(')')).unwrap_or(s);
+                let (start, end) = lines.split_once("..").ok_or_else(error)?;
+                let start = start.parse().map_err(|_| error())?;
+                let end = end.parse().map_err(|_| error())?;
+                Ok(Self::Lines(start..end))
+            },
+        }
+    }
+}
+
+/// Terminal content which is ready to be written to a file.
+#[derive(Debug)]
+pub struct ExportedText {
+    text: String,
+    format: ExportFormat,
+}
+
+impl ExportedText {
+    /// Write the content to the path in `options`, or a new file in the export directory.
+    ///
+    /// Returns the path of the written file.
+    pub fn write(
+        self,
+        config: &Export,
+        options: &ExportOptions,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        let path = match &options.path {
+            Some(path) => {
+                fs::write(path, self.text)?;
+                path.clone()
+            },
+            None => {
+                let (path, mut file) = new_file(config, self.format.extension())?;
+                file.write_all(self.text.as_bytes())?;
+                path
+            },
+        };
+
+        Ok(path)
+    }
+}
+
+/// Format the scrollback or the selection for writing it to a file.
+///
+/// Lines of an unlimited history which were written to disk are not included.
+pub fn export<T>(
+    terminal: &Term<T>,
+    colors: &List,
+    config: &Export,
+    options: &ExportOptions,
+) -> Result<ExportedText, Box<dyn Error>> {
+    let range = if options.selection {
+        selection_range(terminal)?
+    } else {
+        lines_range(terminal, terminal.topmost_line().0..terminal.bottommost_line().0 + 1)?
+    };
+
+    let format = options.format.unwrap_or(config.format);
+    let text = terminal.export(&range, format.into(), &palette(colors));
+
+    Ok(ExportedText { text, format })
+}
+
+/// Read a part of the terminal content.
+#[cfg(unix)]
+pub fn text<T>(
+    terminal: &Term<T>,
+    colors: &List,
+    range: &TextRange,
+    format: ExportFormat,
+) -> Result<String, Box<dyn Error>> {
+    let range = match range {
+        // Use the clipboard text, which takes the selection type into account.
+        TextRange::Selection if format == ExportFormat::Text => {
+            return Ok(terminal.selection_to_string().ok_or("no active selection")?);
+        },
+        TextRange::Selection => selection_range(terminal)?,
+        TextRange::Screen => lines_range(terminal, 0..terminal.screen_lines() as i32)?,
+        TextRange::Scrollback => {
+            lines_range(terminal, terminal.topmost_line().0..terminal.bottommost_line().0 + 1)?
+        },
+        TextRange::Lines(lines) => lines_range(terminal, lines.clone())?,
+    };
+
+    Ok(match format {
+        ExportFormat::Text => terminal.bounds_to_string(range.start, range.end),
+        format => terminal.export(&range, format.into(), &palette(colors)),
+    })
+}
+
+/// Range of the active selection.
+fn selection_range<T>(terminal: &Term<T>) -> Result<SelectionRange, Box<dyn Error>> {
+    let selection = terminal.selection.as_ref().and_then(|selection| selection.to_range(terminal));
+    Ok(selection.ok_or("no active selection")?)
+}
+
+/// Range of complete lines, limited to the lines which exist.
+fn lines_range<T>(terminal: &Term<T>, lines: Range<i32>) -> Result<SelectionRange, Box<dyn Error>> {
+    let start = lines.start.max(terminal.topmost_line().0);
+    let end = lines.end.min(terminal.bottommost_line().0 + 1);
+    if start >= end {
+        return Err("no lines in range".into());
+    }
+
+    let start = Point::new(Line(start), Column(0));
+    let end = Point::new(Line(end - 1), terminal.last_column());
+    Ok(SelectionRange::new(start, end, false))
+}
+
+/// Colors used for the terminal content.
+fn palette(colors: &List) -> Colors {
+    let mut palette = Colors::default();
+    for index in 0..COUNT {
+        palette[index] = Some(colors[index].0);
+    }
+    palette
+}
+
+/// Create a new recording file in the export directory.
+pub fn recording_file(config: &Export) -> Result<(PathBuf, File), Box<dyn Error>> {
+    new_file(config, "cast")
+}
+
+/// Create a new file in the export directory.
+///
+/// Existing files are never replaced, a numeric suffix is added to the name instead.
+fn new_file(config: &Export, extension: &str) -> Result<(PathBuf, File), Box<dyn Error>> {
+    let directory = config.directory.clone().or_else(home::home_dir);
+    let directory = directory.ok_or("unable to locate home directory")?;
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut suffix = 0;
+    loop {
+        let name = match suffix {
+            0 => format!("velacritty-{timestamp}.{extension}"),
+            suffix => format!("velacritty-{timestamp}-{suffix}.{extension}"),
+        };
+
+        let path = directory.join(name);
+        match File::create_new(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => suffix += 1,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_text_range() {
+        assert_eq!("screen".parse(), Ok(TextRange::Screen));
+        assert_eq!("scrollback".parse(), Ok(TextRange::Scrollback));
+        assert_eq!("all".parse(), Ok(TextRange::Scrollback));
+        assert_eq!("selection".parse(), Ok(TextRange::Selection));
+        assert_eq!("lines(-10..5)".parse(), Ok(TextRange::Lines(-10..5)));
+        assert_eq!("-10..5".parse(), Ok(TextRange::Lines(-10..5)));
+        assert!("10".parse::<TextRange>().is_err());
+        assert!("a..b".parse::<TextRange>().is_err());
+        assert!("lines(0..1".parse::<TextRange>().is_err());
+    }
+
+    #[test]
+    fn new_file_collision() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = Export { directory: Some(directory.path().into()), ..Export::default() };
+
+        let (first, _) = new_file(&config, "txt").unwrap();
+        fs::write(&first, "first").unwrap();
+        let (second, _) = new_file(&config, "txt").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "first");
+    }
+}
@@ -1,9 +1,12 @@
 use std::borrow::Cow;
+use std::str::FromStr;
 
+use serde::de::{IntoDeserializer, value};
+use serde::{Deserialize, Serialize};
 use winit::event::{ElementState, KeyEvent};
 #[cfg(target_os = "macos")]
 use winit::keyboard::ModifiersKeyState;
-use winit::keyboard::{Key, KeyLocation, ModifiersState, NamedKey};
+use winit::keyboard::{Key, KeyLocation, ModifiersState, NamedKey, SmolStr};
 #[cfg(target_os = "macos")]
 use winit::platform::macos::OptionAsAlt;
 
@@ -11,7 +14,9 @@ use velacritty_terminal::event::EventListener;
 use velacritty_terminal::term::TermMode;
 use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
 
-use crate::config::{Action, BindingKey, BindingMode, KeyBinding};
+use crate::config::{
+    Action, BindingKey, BindingMode, KeyBinding, KeyLocation as BindingKeyLocation, ModsWrapper,
+};
 use crate::event::TYPING_SEARCH_DELAY;
 use crate::input::{ActionContext, Execute, Processor};
 use crate::scheduler::{TimerId, Topic};
@@ -76,20 +81,8 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         // Mask `Alt` modifier from input when we won't send esc.
         let mods = if self.alt_send_esc(&key, text) { mods } else { mods & !ModifiersState::ALT };
 
-        let build_key_sequence = Self::should_build_sequence(&key, text, mode, mods);
         let is_modifier_key = Self::is_modifier_key(&key);
-
-        let bytes = if build_key_sequence {
-            build_sequence(key, mods, mode)
-        } else {
-            let mut bytes = Vec::with_capacity(text.len() + 1);
-            if mods.alt_key() {
-                bytes.push(b'\x1b');
-            }
-
-            bytes.extend_from_slice(text.as_bytes());
-            bytes
-        };
+        let bytes = Self::encode_key(&KeyInput::from(&key), mods, mode);
 
         // Write only if we have something to write.
         if !bytes.is_empty() {
@@ -139,9 +132,56 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         )
     }
 
-    /// Check whether we should try to build escape sequence for the [`KeyEvent`].
+    /// Write keys which were not pressed on the keyboard to the PTY.
+    ///
+    /// Key bindings which send escape sequences are applied, other actions are ignored.
+    pub fn write_keys(&mut self, keys: &[KeyPress]) {
+        // Keys are written to the PTY even when vi mode is active.
+        let mode = *self.ctx.terminal().mode() & !TermMode::VI;
+        let binding_mode = BindingMode::new(&mode, false, false);
+
+        let mut bytes = Vec::new();
+        for key in keys {
+            let binding_key =
+                BindingKey::Keycode { key: key.key.clone(), location: key.location.into() };
+            let binding = self
+                .ctx
+                .config()
+                .key_bindings()
+                .iter()
+                .find(|binding| binding.is_triggered_by(binding_mode, key.mods, &binding_key));
+
+            match binding.map(|binding| &binding.action) {
+                Some(Action::Esc(escape)) => bytes.extend_from_slice(escape.as_bytes()),
+                _ => bytes.extend(Self::encode_key(&key.input(), key.mods, mode)),
+            }
+        }
+
+        if !bytes.is_empty() {
+            self.ctx.on_terminal_input_start();
+            self.ctx.write_to_pty(bytes);
+        }
+    }
+
+    /// Encode a key press for the PTY.
+    fn encode_key(key: &KeyInput, mods: ModifiersState, mode: TermMode) -> Vec<u8> {
+        let text = key.text.as_deref().unwrap_or_default();
+        if !Self::should_build_sequence(key, text, mode, mods) {
+            let mut bytes = Vec::with_capacity(text.len() + 1);
+            if mods.alt_key() {
+                bytes.push(b'\x1b');
+            }
+
+            bytes.extend_from_slice(text.as_bytes());
+            return bytes;
+        }
+
+        build_sequence(key, mods, mode)
+    }
+
+    /// Check whether we should try to build escape sequence for the [`KeyInput`].
     fn should_build_sequence(
-        key: &KeyEvent,
+        key: &KeyInput,
         text: &str,
         mode: TermMode,
         mods: ModifiersState,
@@ -175,7 +215,11 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
     fn process_key_bindings(&mut self, key: &KeyEvent) -> bool {
-        let mode = BindingMode::new(self.ctx.terminal().mode(), self.ctx.search_active());
+        let mode = BindingMode::new(
+            self.ctx.terminal().mode(),
+            self.ctx.search_active(),
+            self.ctx.replay_active(),
+        );
         let mods = self.ctx.modifiers().state();
 
         // Don't suppress char if no bindings were triggered.
@@ -269,7 +313,7 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             {
                 return;
             },
-            _ => build_sequence(key, mods, mode),
+            _ => build_sequence(&KeyInput::from(&key), mods, mode),
         };
 
         self.ctx.write_to_pty(bytes);
@@ -287,11 +331,117 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     }
 }
 
+/// Key event which is encoded for the PTY.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyInput {
+    pub logical_key: Key,
+
+    /// Key with all modifiers except `Shift` removed, used to find the base of shifted keys.
+    pub key_without_modifiers: Key,
+
+    /// Text produced by the key with all modifiers applied.
+    pub text: Option<SmolStr>,
+
+    pub location: KeyLocation,
+    pub state: ElementState,
+    pub repeat: bool,
+}
+
+impl From<&KeyEvent> for KeyInput {
+    fn from(key: &KeyEvent) -> Self {
+        Self {
+            logical_key: key.logical_key.clone(),
+            key_without_modifiers: key.key_without_modifiers(),
+            text: key.text_with_all_modifiers().map(SmolStr::new),
+            location: key.location,
+            state: key.state,
+            repeat: key.repeat,
+        }
+    }
+}
+
+/// Key press which did not come from the keyboard.
+///
+/// Keys are written as `[MODS+]KEY`, using the key and modifier names of key bindings, like
+/// `Control|Shift+t`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyPress {
+    /// Key without modifiers, using lowercase for characters.
+    pub key: Key,
+    pub location: KeyLocation,
+    pub mods: ModifiersState,
+}
+
+impl KeyPress {
+    /// Key event produced by the key press on a US layout.
+    pub fn input(&self) -> KeyInput {
+        let (logical_key, text) = match &self.key {
+            Key::Character(character) => {
+                let character = if self.mods.shift_key() {
+                    SmolStr::new(character.to_uppercase())
+                } else {
+                    character.clone()
+                };
+
+                let mut chars = character.chars();
+                let text = match (chars.next(), chars.next()) {
+                    _ if !self.mods.control_key() => character.clone(),
+                    // Control characters for `@`, letters, `[`, `\`, `]`, `^` and `_`.
+                    (Some(c @ ('@'..='_' | 'a'..='z')), None) => {
+                        SmolStr::new(char::from(c as u8 & 0x1f).to_string())
+                    },
+                    (Some('?'), None) => SmolStr::new("\x7f"),
+                    _ => character.clone(),
+                };
+
+                (Key::Character(character), Some(text))
+            },
+            Key::Named(named) => (self.key.clone(), named.to_text().map(SmolStr::new)),
+            key => (key.clone(), None),
+        };
+
+        KeyInput {
+            logical_key,
+            key_without_modifiers: self.key.clone(),
+            text,
+            location: self.location,
+            state: ElementState::Pressed,
+            repeat: false,
+        }
+    }
+}
+
+impl FromStr for KeyPress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Modifiers are separated by `|`, so the first `+` ends them.
+        let (mods, key) = match s.split_once('+') {
+            Some((mods, key)) if !key.is_empty() => (mods, key),
+            _ => ("None", s),
+        };
+
+        let mods = ModsWrapper::deserialize(mods.into_deserializer())
+            .map_err(|err: value::Error| err.to_string())?;
+        let (key, location) = match BindingKey::deserialize(key.into_deserializer())
+            .map_err(|err: value::Error| format!("invalid key {key:?}: {err}"))?
+        {
+            BindingKey::Keycode { key, location: BindingKeyLocation::Numpad } => {
+                (key, KeyLocation::Numpad)
+            },
+            BindingKey::Keycode { key, .. } => (key, KeyLocation::Standard),
+            BindingKey::Scancode(_) => return Err(format!("invalid key {key:?}")),
+        };
+
+        Ok(Self { key, location, mods: mods.into_inner() })
+    }
+}
+
 /// Build a key's keyboard escape sequence based on the given `key`, `mods`, and `mode`.
 ///
 /// The key sequences for `APP_KEYPAD` and alike are handled inside the bindings.
 #[inline(never)]
-fn build_sequence(key: KeyEvent, mods: ModifiersState, mode: TermMode) -> Vec<u8> {
+fn build_sequence(key: &KeyInput, mods: ModifiersState, mode: TermMode) -> Vec<u8> {
     let mut modifiers = mods.into();
 
     let kitty_seq = mode.intersects(
@@ -308,7 +458,7 @@ fn build_sequence(key: KeyEvent, mods: ModifiersState, mode: TermMode) -> Vec<u8
     let context =
         SequenceBuilder { mode, modifiers, kitty_seq, kitty_encode_all, kitty_event_type };
 
-    let associated_text = key.text_with_all_modifiers().filter(|text| {
+    let associated_text = key.text.as_deref().filter(|text| {
         mode.contains(TermMode::REPORT_ASSOCIATED_TEXT)
             && key.state != ElementState::Released
             && !text.is_empty()
@@ -316,11 +466,11 @@ fn build_sequence(key: KeyEvent, mods: ModifiersState, mode: TermMode) -> Vec<u8
     });
 
     let sequence_base = context
-        .try_build_numpad(&key)
-        .or_else(|| context.try_build_named_kitty(&key))
-        .or_else(|| context.try_build_named_normal(&key, associated_text.is_some()))
-        .or_else(|| context.try_build_control_char_or_mod(&key, &mut modifiers))
-        .or_else(|| context.try_build_textual(&key, associated_text));
+        .try_build_numpad(key)
+        .or_else(|| context.try_build_named_kitty(key))
+        .or_else(|| context.try_build_named_normal(key, associated_text.is_some()))
+        .or_else(|| context.try_build_control_char_or_mod(key, &mut modifiers))
+        .or_else(|| context.try_build_textual(key, associated_text));
 
     let (payload, terminator) = match sequence_base {
         Some(SequenceBase { payload, terminator }) => (payload, terminator),
@@ -360,7 +510,7 @@ fn build_sequence(key: KeyEvent, mods: ModifiersState, mode: TermMode) -> Vec<u8
     payload.into_bytes()
 }
 
-/// Helper to build escape sequence payloads from [`KeyEvent`].
+/// Helper to build escape sequence payloads from [`KeyInput`].
 pub struct SequenceBuilder {
     mode: TermMode,
     /// The emitted sequence should follow the kitty keyboard protocol.
@@ -376,7 +526,7 @@ impl SequenceBuilder {
     /// Try building sequence from the event's emitting text.
     fn try_build_textual(
         &self,
-        key: &KeyEvent,
+        key: &KeyInput,
         associated_text: Option<&str>,
     ) -> Option<SequenceBase> {
         let character = match key.logical_key.as_ref() {
@@ -397,7 +547,7 @@ impl SequenceBuilder {
             //
             // However it should only be performed when `SHIFT` is pressed.
             if shift && alternate_key_code == unicode_key_code {
-                if let Key::Character(unmodded) = key.key_without_modifiers().as_ref() {
+                if let Key::Character(unmodded) = key.key_without_modifiers.as_ref() {
                     unicode_key_code = u32::from(unmodded.chars().next().unwrap_or(unshifted_ch));
                 }
             }
@@ -425,7 +575,7 @@ impl SequenceBuilder {
     /// Try building from numpad key.
     ///
     /// `None` is returned when the key is neither known nor numpad.
-    fn try_build_numpad(&self, key: &KeyEvent) -> Option<SequenceBase> {
+    fn try_build_numpad(&self, key: &KeyInput) -> Option<SequenceBase> {
         if !self.kitty_seq || key.location != KeyLocation::Numpad {
             return None;
         }
@@ -469,7 +619,7 @@ impl SequenceBuilder {
 
     /// Try building from [`NamedKey`] using the kitty keyboard protocol encoding
     /// for functional keys.
-    fn try_build_named_kitty(&self, key: &KeyEvent) -> Option<SequenceBase> {
+    fn try_build_named_kitty(&self, key: &KeyInput) -> Option<SequenceBase> {
         let named = match key.logical_key {
             Key::Named(named) if self.kitty_seq => named,
             _ => return None,
@@ -526,7 +676,7 @@ impl SequenceBuilder {
     /// Try building from [`NamedKey`].
     fn try_build_named_normal(
         &self,
-        key: &KeyEvent,
+        key: &KeyInput,
         has_associated_text: bool,
     ) -> Option<SequenceBase> {
         let named = match key.logical_key {
@@ -581,7 +731,7 @@ impl SequenceBuilder {
     /// Try building escape from control characters (e.g. Enter) and modifiers.
     fn try_build_control_char_or_mod(
         &self,
-        key: &KeyEvent,
+        key: &KeyInput,
         mods: &mut SequenceModifiers,
     ) -> Option<SequenceBase> {
         if !self.kitty_encode_all && !self.kitty_seq {
@@ -715,3 +865,30 @@ fn is_control_character(text: &str) -> bool {
     let codepoint = text.bytes().next().unwrap();
     text.len() == 1 && (codepoint < 0x20 || (0x7f..=0x9f).contains(&codepoint))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_key_press() {
+        let key: KeyPress = "Control|Shift+c".parse().unwrap();
+        assert_eq!(key.key, Key::Character("c".into()));
+        assert_eq!(key.mods, ModifiersState::CONTROL | ModifiersState::SHIFT);
+        assert_eq!(key.input().logical_key, Key::Character("C".into()));
+        assert_eq!(key.input().text.as_deref(), Some("\x03"));
+
+        let key: KeyPress = "Alt++".parse().unwrap();
+        assert_eq!((key.key, key.mods), (Key::Character("+".into()), ModifiersState::ALT));
+
+        let key: KeyPress = "NumpadEnter".parse().unwrap();
+        assert_eq!(key.location, KeyLocation::Numpad);
+        assert_eq!(key.input().text.as_deref(), Some("\r"));
+
+        let key: KeyPress = "Up".parse().unwrap();
+        assert_eq!(build_sequence(&key.input(), key.mods, TermMode::empty()), b"\x1b[A");
+
+        assert!("Hyper+a".parse::<KeyPress>().is_err());
+        assert!("NotAKey".parse::<KeyPress>().is_err());
+    }
+}
@@ -82,6 +82,7 @@ pub trait ActionContext<T: EventListener> {
     fn mark_dirty(&mut self) {}
     fn size_info(&self) -> SizeInfo;
     fn copy_selection(&mut self, _ty: ClipboardType) {}
+    fn copy_command_output(&mut self) {}
     fn start_selection(&mut self, _ty: SelectionType, _point: Point, _side: Side) {}
     fn toggle_selection(&mut self, _ty: SelectionType, _point: Point, _side: Side) {}
     fn update_selection(&mut self, _point: Point, _side: Side) {}
@@ -97,6 +98,11 @@ pub trait ActionContext<T: EventListener> {
     fn terminal(&self) -> &Term<T>;
     fn terminal_mut(&mut self) -> &mut Term<T>;
     fn spawn_new_instance(&mut self) {}
+    fn save_session(&mut self) {}
+    fn export_scrollback(&mut self) {}
+    fn toggle_recording(&mut self) {}
+    fn toggle_replay_pause(&mut self) {}
+    fn step_replay(&mut self) {}
     #[cfg(target_os = "macos")]
     fn create_new_window(&mut self, _tabbing_id: Option<String>) {}
     #[cfg(not(target_os = "macos"))]
@@ -123,6 +129,9 @@ pub trait ActionContext<T: EventListener> {
     fn advance_search_origin(&mut self, _direction: Direction) {}
     fn search_direction(&self) -> Direction;
     fn search_active(&self) -> bool;
+    fn replay_active(&self) -> bool {
+        false
+    }
     fn on_typing_start(&mut self) {}
     fn toggle_vi_mode(&mut self) {}
     fn inline_search_state(&mut self) -> &mut InlineSearchState;
@@ -136,6 +145,7 @@ pub trait ActionContext<T: EventListener> {
     fn semantic_word(&self, point: Point) -> String;
     fn on_terminal_input_start(&mut self) {}
     fn paste(&mut self, _text: &str, _bracketed: bool) {}
+    fn paste_to_pty(&mut self, _text: &str, _bracketed: bool) {}
     fn spawn_daemon<I, S>(&self, _program: &str, _args: I)
     where
         I: IntoIterator<Item = S> + Debug + Copy,
@@ -321,6 +331,7 @@ impl<T: EventListener> Execute<T> for Action {
             Action::Copy => ctx.copy_selection(ClipboardType::Clipboard),
             #[cfg(not(any(target_os = "macos", windows)))]
             Action::CopySelection => ctx.copy_selection(ClipboardType::Selection),
+            Action::CopyCommandOutput => ctx.copy_command_output(),
             Action::ClearSelection => ctx.clear_selection(),
             Action::Paste => {
                 let text = ctx.clipboard_mut().load(ClipboardType::Clipboard);
@@ -399,6 +410,15 @@ impl<T: EventListener> Execute<T> for Action {
                 term.vi_motion(ViMotion::FirstOccupied);
                 ctx.mark_dirty();
             },
+            Action::ScrollToUnread => {
+                let grid = ctx.terminal().grid();
+                let unread_lines = grid.unread_lines();
+                if unread_lines != 0 {
+                    // Show the first unread line at the top of the viewport.
+                    let offset = unread_lines.saturating_sub(grid.screen_lines());
+                    ctx.scroll(Scroll::Delta(offset as i32 - grid.display_offset() as i32));
+                }
+            },
             Action::ToggleAutoScroll => {
                 let new_value = !ctx.display().auto_scroll_enabled;
                 ctx.display().auto_scroll_enabled = new_value;
@@ -413,6 +433,11 @@ impl<T: EventListener> Execute<T> for Action {
             #[cfg(not(target_os = "macos"))]
             Action::CreateNewWindow => ctx.create_new_window(),
             Action::SpawnNewInstance => ctx.spawn_new_instance(),
+            Action::SaveSession => ctx.save_session(),
+            Action::ExportScrollback => ctx.export_scrollback(),
+            Action::ToggleRecording => ctx.toggle_recording(),
+            Action::ToggleReplayPause => ctx.toggle_replay_pause(),
+            Action::StepReplay => ctx.step_replay(),
             #[cfg(target_os = "macos")]
             Action::CreateNewWindow => ctx.create_new_window(None),
             #[cfg(target_os = "macos")]
@@ -527,22 +552,25 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     fn cell_side(&self, x: usize) -> Side {
         let size_info = self.ctx.size_info();
 
-        let cell_x =
-            x.saturating_sub(size_info.padding_x() as usize) % size_info.cell_width() as usize;
-        let half_cell_width = (size_info.cell_width() / 2.0) as usize;
+        let cell_width = self.ctx.mouse().cell_width(&size_info);
+        let cell_x = x.saturating_sub(size_info.padding_x() as usize) % cell_width as usize;
+        let half_cell_width = (cell_width / 2.0) as usize;
 
         let additional_padding =
             (size_info.width() - size_info.padding_x() * 2.) % size_info.cell_width();
         let end_of_grid = size_info.width() - size_info.padding_x() - additional_padding;
 
-        if cell_x > half_cell_width
+        let side = if cell_x > half_cell_width
             // Edge case when mouse leaves the window.
             || x as f32 >= end_of_grid
         {
             Side::Right
         } else {
             Side::Left
-        }
+        };
+
+        // Sides are swapped inside of right-to-left text.
+        if self.ctx.mouse().is_rtl(&size_info) { side.opposite() } else { side }
     }
 
     fn mouse_report(&mut self, button: u8, state: ElementState) {
@@ -1022,6 +1050,11 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
             };
 
             self.ctx.window().set_mouse_cursor(new_icon);
+        } else if button == MouseButton::Left
+            && state == ElementState::Pressed
+            && self.unread_badge_hovered()
+        {
+            self.ctx.scroll(Scroll::Bottom);
         } else {
             match state {
                 ElementState::Pressed => {
@@ -1039,7 +1072,11 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
     fn process_mouse_bindings(&mut self, button: MouseButton) {
-        let mode = BindingMode::new(self.ctx.terminal().mode(), self.ctx.search_active());
+        let mode = BindingMode::new(
+            self.ctx.terminal().mode(),
+            self.ctx.search_active(),
+            self.ctx.replay_active(),
+        );
         let mouse_mode = self.ctx.mouse_mode();
         let mods = self.ctx.modifiers().state();
         let mouse_bindings = self.ctx.config().mouse_bindings().to_owned();
@@ -1091,6 +1128,17 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
         }
     }
 
+    /// Check if the mouse is on top of the unread lines badge.
+    fn unread_badge_hovered(&mut self) -> bool {
+        let size = self.ctx.size_info();
+        let point = self.ctx.mouse().point(&size, 0);
+
+        self.ctx.terminal().grid().unread_lines() != 0
+            && self.ctx.mouse().inside_text_area
+            && point.line == size.bottommost_line()
+            && self.ctx.display().unread_badge.is_some_and(|column| point.column >= column)
+    }
+
     /// Icon state of the cursor.
     fn cursor_state(&mut self) -> CursorIcon {
         let display_offset = self.ctx.terminal().grid().display_offset();
@@ -1102,7 +1150,9 @@ impl<T: EventListener, A: ActionContext<T>> Processor<T, A> {
 
         if let Some(mouse_state) = self.message_bar_cursor_state() {
             mouse_state
-        } else if self.ctx.display().highlighted_hint.as_ref().is_some_and(hint_highlighted) {
+        } else if self.unread_badge_hovered()
+            || self.ctx.display().highlighted_hint.as_ref().is_some_and(hint_highlighted)
+        {
             CursorIcon::Pointer
         } else if !self.ctx.modifiers().state().shift_key() && self.ctx.mouse_mode() {
             CursorIcon::Default
@@ -1156,6 +1206,7 @@ mod tests {
     use winit::window::WindowId;
 
     use velacritty_terminal::event::Event as TerminalEvent;
+    use velacritty_terminal::index::Line;
 
     use crate::config::Binding;
     use crate::message_bar::MessageBuffer;
@@ -1555,4 +1606,18 @@ mod tests {
         mode: BindingMode::empty(),
         mods: ModifiersState::ALT | ModifiersState::SUPER,
     }
+
+    #[test]
+    fn double_width_mouse_point() {
+        let size = SizeInfo::new(21.0, 51.0, 3.0, 3.0, 0., 0., false);
+        let mut mouse = Mouse { x: 7, y: 4, double_width_lines: vec![1], ..Mouse::default() };
+
+        // Columns are halved on double-width lines.
+        assert_eq!(mouse.point(&size, 0), Point::new(Line(1), Column(1)));
+        assert_eq!(mouse.cell_width(&size), 6.);
+
+        mouse.y = 1;
+        assert_eq!(mouse.point(&size, 0), Point::new(Line(0), Column(2)));
+        assert_eq!(mouse.cell_width(&size), 3.);
+    }
 }
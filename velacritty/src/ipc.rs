@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
+use std::fmt::Write as _;
 use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Result as IoResult, Write};
 use std::net::Shutdown;
 use std::os::unix::net::{UnixListener, UnixStream};
@@ -18,6 +19,7 @@ use velacritty_terminal::thread;
 
 use crate::cli::{Options, SocketMessage};
 use crate::event::{Event, EventType};
+use crate::input::keyboard::KeyPress;
 
 /// Environment variable name for the IPC socket path.
 const VELACRITTY_SOCKET_ENV: &str = "VELACRITTY_SOCKET";
@@ -83,6 +85,72 @@ pub fn spawn_ipc_socket(
                     let event = Event::new(EventType::IpcGetConfig(Arc::new(stream)), window_id);
                     let _ = event_proxy.send_event(event);
                 },
+                SocketMessage::GetMemoryUsage => {
+                    let event = Event::new(EventType::IpcGetMemoryUsage(Arc::new(stream)), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::ExportScrollback(export) => {
+                    let window_id =
+                        export.window_id.and_then(|id| u64::try_from(id).ok()).map(WindowId::from);
+                    let event_type =
+                        EventType::IpcExportScrollback(Arc::new(stream), export.options);
+                    let _ = event_proxy.send_event(Event::new(event_type, window_id));
+                },
+                SocketMessage::ToggleAutoScroll(toggle) => {
+                    let window_id =
+                        toggle.window_id.and_then(|id| u64::try_from(id).ok()).map(WindowId::from);
+                    let event_type = EventType::IpcToggleAutoScroll(Arc::new(stream));
+                    let _ = event_proxy.send_event(Event::new(event_type, window_id));
+                },
+                SocketMessage::GetText(get_text) => {
+                    let window_id = get_text
+                        .window_id
+                        .and_then(|id| u64::try_from(id).ok())
+                        .map(WindowId::from);
+                    let event_type =
+                        EventType::IpcGetText(Arc::new(stream), get_text.range, get_text.format);
+                    let _ = event_proxy.send_event(Event::new(event_type, window_id));
+                },
+                SocketMessage::SendText(send_text) => {
+                    let window_id = send_text
+                        .window_id
+                        .and_then(|id| u64::try_from(id).ok())
+                        .map(WindowId::from);
+                    let command = WindowCommand::SendText(send_text.text, send_text.bracketed);
+                    let event_type = EventType::IpcWindowCommand(Arc::new(stream), command);
+                    let _ = event_proxy.send_event(Event::new(event_type, window_id));
+                },
+                SocketMessage::SendKeys(send_keys) => {
+                    let window_id = send_keys
+                        .window_id
+                        .and_then(|id| u64::try_from(id).ok())
+                        .map(WindowId::from);
+                    let command = WindowCommand::SendKeys(send_keys.keys);
+                    let event_type = EventType::IpcWindowCommand(Arc::new(stream), command);
+                    let _ = event_proxy.send_event(Event::new(event_type, window_id));
+                },
+                SocketMessage::ListWindows(_) => {
+                    let event = Event::new(EventType::IpcListWindows(Arc::new(stream)), None);
+                    let _ = event_proxy.send_event(event);
+                },
+                SocketMessage::FocusWindow(focus) => {
+                    let window_id = WindowId::from(focus.window_id);
+                    let event_type =
+                        EventType::IpcWindowCommand(Arc::new(stream), WindowCommand::Focus);
+                    let _ = event_proxy.send_event(Event::new(event_type, window_id));
+                },
+                SocketMessage::CloseWindow(close) => {
+                    let window_id = WindowId::from(close.window_id);
+                    let event_type =
+                        EventType::IpcWindowCommand(Arc::new(stream), WindowCommand::Close);
+                    let _ = event_proxy.send_event(Event::new(event_type, window_id));
+                },
+                SocketMessage::SetTitle(set_title) => {
+                    let window_id = set_title.window_id.map(WindowId::from);
+                    let command = WindowCommand::SetTitle(set_title.title);
+                    let event_type = EventType::IpcWindowCommand(Arc::new(stream), command);
+                    let _ = event_proxy.send_event(Event::new(event_type, window_id));
+                },
             }
         }
     });
@@ -121,15 +189,64 @@ fn handle_reply(stream: &UnixStream, message: &SocketMessage) -> IoResult<()> {
     let reply: SocketReply = serde_json::from_str(&buffer)
         .map_err(|err| IoError::other(format!("Invalid IPC format: {err}")))?;
 
+    print!("{}", reply_output(message, &reply)?);
+
+    Ok(())
+}
+
+/// Format an IPC reply for STDOUT.
+fn reply_output(message: &SocketMessage, reply: &SocketReply) -> IoResult<String> {
     // Ensure reply matches request.
-    match (message, &reply) {
+    match (message, reply) {
         // Write requested config to STDOUT.
-        (SocketMessage::GetConfig(..), SocketReply::GetConfig(config)) => {
-            println!("{config}");
-            Ok(())
+        (SocketMessage::GetConfig(..), SocketReply::GetConfig(config)) => Ok(format!("{config}\n")),
+        // Write memory usage report to STDOUT.
+        (SocketMessage::GetMemoryUsage, SocketReply::GetMemoryUsage(report)) => Ok(report.clone()),
+        // Write path of the exported file to STDOUT.
+        (SocketMessage::ExportScrollback(..), SocketReply::ExportScrollback(result)) => {
+            let path = result.as_ref().map_err(|err| IoError::other(err.clone()))?;
+            Ok(format!("{}\n", path.display()))
+        },
+        // Write the new auto-scroll state to STDOUT.
+        (SocketMessage::ToggleAutoScroll(..), SocketReply::ToggleAutoScroll(result)) => {
+            let enabled = result.as_ref().map_err(|err| IoError::other(err.clone()))?;
+            Ok(format!("{}\n", if *enabled { "enabled" } else { "disabled" }))
+        },
+        // Write the list of windows to STDOUT.
+        (SocketMessage::ListWindows(options), SocketReply::ListWindows(windows)) => {
+            if options.json {
+                return Ok(format!("{}\n", serde_json::to_string_pretty(windows)?));
+            }
+
+            let mut output = String::from("ID\tFOCUSED\tSIZE\tPROCESS\tDIRECTORY\tTITLE\n");
+            for window in windows {
+                let directory = window.working_directory.as_ref().map(|path| path.display());
+                let _ = writeln!(
+                    output,
+                    "{}\t{}\t{}x{}\t{}\t{}\t{}",
+                    window.id,
+                    if window.focused { "yes" } else { "no" },
+                    window.columns,
+                    window.lines,
+                    window.foreground_process.as_deref().unwrap_or("-"),
+                    directory.map_or_else(|| String::from("-"), |path| path.to_string()),
+                    window.title,
+                );
+            }
+            Ok(output)
+        },
+        // Report failed window commands.
+        (_, SocketReply::WindowCommand(result)) => {
+            result.as_ref().map_err(|err| IoError::other(err.clone()))?;
+            Ok(String::new())
+        },
+        // Write the requested text to STDOUT.
+        (SocketMessage::GetText(..), SocketReply::GetText(result)) => {
+            let text = result.as_ref().map_err(|err| IoError::other(err.clone()))?;
+            if text.ends_with('\n') { Ok(text.clone()) } else { Ok(format!("{text}\n")) }
         },
         // Ignore requests without reply.
-        _ => Ok(()),
+        _ => Ok(String::new()),
     }
 }
 
@@ -240,4 +357,89 @@ fn socket_prefix() -> String {
 #[derive(Serialize, Deserialize, Debug)]
 pub enum SocketReply {
     GetConfig(String),
+    GetMemoryUsage(String),
+    ExportScrollback(Result<PathBuf, String>),
+    ToggleAutoScroll(Result<bool, String>),
+    GetText(Result<String, String>),
+    ListWindows(Vec<WindowInfo>),
+    WindowCommand(Result<(), String>),
+}
+
+/// Description of a window for `list-windows`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WindowInfo {
+    pub id: u64,
+    pub title: String,
+    pub working_directory: Option<PathBuf>,
+
+    /// Name of the process in the foreground of the terminal.
+    pub foreground_process: Option<String>,
+
+    pub columns: usize,
+    pub lines: usize,
+    pub focused: bool,
+}
+
+/// Operation on a window requested over IPC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowCommand {
+    Focus,
+    Close,
+    SetTitle(String),
+    SendText(String, bool),
+    SendKeys(Vec<KeyPress>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cli::{IpcGetText, IpcListWindows, IpcSetTitle};
+
+    #[test]
+    fn list_windows_output() {
+        let window = WindowInfo {
+            id: 7,
+            title: String::from("vim"),
+            working_directory: Some(PathBuf::from("/tmp")),
+            foreground_process: None,
+            columns: 80,
+            lines: 24,
+            focused: true,
+        };
+        let reply = SocketReply::ListWindows(vec![window]);
+
+        let message = SocketMessage::ListWindows(IpcListWindows { json: false });
+        assert_eq!(
+            reply_output(&message, &reply).unwrap(),
+            "ID\tFOCUSED\tSIZE\tPROCESS\tDIRECTORY\tTITLE\n7\tyes\t80x24\t-\t/tmp\tvim\n"
+        );
+
+        let message = SocketMessage::ListWindows(IpcListWindows { json: true });
+        let output = reply_output(&message, &reply).unwrap();
+        let windows: Vec<WindowInfo> = serde_json::from_str(&output).unwrap();
+        assert_eq!(windows[0].id, 7);
+    }
+
+    #[test]
+    fn window_command_output() {
+        let message = SocketMessage::SetTitle(IpcSetTitle::default());
+
+        let reply = SocketReply::WindowCommand(Ok(()));
+        assert_eq!(reply_output(&message, &reply).unwrap(), "");
+
+        let reply = SocketReply::WindowCommand(Err(String::from("no window found")));
+        assert_eq!(reply_output(&message, &reply).unwrap_err().to_string(), "no window found");
+    }
+
+    #[test]
+    fn get_text_output() {
+        let message = SocketMessage::GetText(IpcGetText::default());
+
+        let reply = SocketReply::GetText(Ok(String::from("a\nb")));
+        assert_eq!(reply_output(&message, &reply).unwrap(), "a\nb\n");
+
+        let reply = SocketReply::GetText(Ok(String::from("a\n")));
+        assert_eq!(reply_output(&message, &reply).unwrap(), "a\n");
+    }
 }
@@ -68,6 +68,20 @@ pub fn cwd(pid: c_int) -> Result<PathBuf, Error> {
     Ok(CString::from(c_str).into_string().map(PathBuf::from)?)
 }
 
+/// Get the name of a process.
+pub fn name(pid: c_int) -> Result<String, Error> {
+    // Names are limited to twice `MAXCOMLEN`, plus the null terminator.
+    let mut buffer = [0u8; 2 * 16 + 1];
+    let buffer_ptr = buffer.as_mut_ptr() as *mut c_void;
+
+    let len = unsafe { sys::proc_name(pid, buffer_ptr, buffer.len() as u32) };
+    if len <= 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    Ok(String::from_utf8_lossy(&buffer[..len as usize]).into_owned())
+}
+
 /// Bindings for libproc.
 #[allow(non_camel_case_types)]
 mod sys {
@@ -143,6 +157,8 @@ mod sys {
             buffer: *mut c_void,
             buffersize: c_int,
         ) -> c_int;
+
+        pub fn proc_name(pid: c_int, buffer: *mut c_void, buffersize: u32) -> c_int;
     }
 }
 
@@ -33,6 +33,7 @@ mod config;
 mod daemon;
 mod display;
 mod event;
+mod export;
 mod input;
 #[cfg(unix)]
 mod ipc;
@@ -44,7 +45,10 @@ mod migrate;
 #[cfg(windows)]
 mod panic;
 mod renderer;
+#[cfg(not(windows))]
+mod replay;
 mod scheduler;
+mod session;
 mod string;
 mod window_context;
 
@@ -81,7 +85,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     match options.subcommands {
         #[cfg(unix)]
-        Some(Subcommands::Msg(options)) => msg(options)?,
+        Some(Subcommands::Msg(options)) => msg(*options)?,
         Some(Subcommands::Migrate(options)) => migrate::migrate(options),
         None => velacritty(options)?,
     }
@@ -98,6 +102,20 @@ fn msg(mut options: MessageOptions) -> Result<(), Box<dyn Error>> {
         window_options.activation_token =
             env::var("XDG_ACTIVATION_TOKEN").or_else(|_| env::var("DESKTOP_STARTUP_ID")).ok();
     }
+
+    // Resolve relative paths, since the working directory of Velacritty is different.
+    let paths = match &mut options.message {
+        SocketMessage::CreateWindow(window_options) => {
+            let terminal_options = &mut window_options.terminal_options;
+            vec![&mut terminal_options.record, &mut terminal_options.replay]
+        },
+        SocketMessage::ExportScrollback(export) => vec![&mut export.options.path],
+        _ => Vec::new(),
+    };
+    for path in paths.into_iter().flatten() {
+        *path = std::path::absolute(&*path)?;
+    }
+
     ipc::send_message(options.socket, options.message).map_err(|err| err.into())
 }
 
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::mem;
+
+use ahash::RandomState;
+
+use velacritty_terminal::graphics::{GraphicCell, GraphicId, GraphicUpdates};
+use velacritty_terminal::index::Point;
+
+use crate::display::SizeInfo;
+use crate::gl::types::*;
+use crate::renderer::shader::{ShaderProgram, ShaderVersion};
+use crate::{gl, renderer};
+
+/// Shader sources for graphics rendering program.
+const GRAPHICS_SHADER_F: &str = include_str!("../../res/graphics.f.glsl");
+const GRAPHICS_SHADER_V: &str = include_str!("../../res/graphics.v.glsl");
+
+/// Section of a graphic to be drawn in a cell.
+#[derive(Debug, Clone)]
+pub struct RenderGraphic {
+    pub point: Point<usize>,
+    pub graphic: GraphicCell,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    // Normalized screen coordinates.
+    x: f32,
+    y: f32,
+
+    // Texture coordinates.
+    u: f32,
+    v: f32,
+}
+
+/// Texture storing the pixels of a graphic.
+#[derive(Debug)]
+struct GraphicTexture {
+    id: GLuint,
+    width: usize,
+    height: usize,
+}
+
+#[derive(Debug)]
+pub struct GraphicsRenderer {
+    // GL buffer objects.
+    vao: GLuint,
+    vbo: GLuint,
+
+    program: ShaderProgram,
+
+    /// Uploaded graphics.
+    textures: HashMap<GraphicId, GraphicTexture, RandomState>,
+
+    /// Vertices for each texture.
+    vertices: HashMap<GraphicId, Vec<Vertex>, RandomState>,
+}
+
+impl GraphicsRenderer {
+    pub fn new(shader_version: ShaderVersion) -> Result<Self, renderer::Error> {
+        let mut vao: GLuint = 0;
+        let mut vbo: GLuint = 0;
+
+        let program =
+            ShaderProgram::new(shader_version, None, GRAPHICS_SHADER_V, GRAPHICS_SHADER_F)?;
+
+        unsafe {
+            // Allocate buffers.
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+
+            // VBO binding is not part of VAO itself, but VBO binding is stored in attributes.
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            let mut attribute_offset = 0;
+
+            // Position.
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vertex>() as i32,
+                attribute_offset as *const _,
+            );
+            gl::EnableVertexAttribArray(0);
+            attribute_offset += mem::size_of::<f32>() * 2;
+
+            // Texture coordinates.
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vertex>() as i32,
+                attribute_offset as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+
+            // Reset buffer bindings.
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+
+        Ok(Self { vao, vbo, program, textures: Default::default(), vertices: Default::default() })
+    }
+
+    /// Upload new graphics and release the textures of removed ones.
+    pub fn update(&mut self, updates: GraphicUpdates) {
+        for data in updates.added {
+            let mut id: GLuint = 0;
+            unsafe {
+                gl::GenTextures(1, &mut id);
+                gl::BindTexture(gl::TEXTURE_2D, id);
+
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::RGBA as i32,
+                    data.image.width as i32,
+                    data.image.height as i32,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    data.image.pixels.as_ptr() as *const _,
+                );
+
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+            }
+
+            let texture = GraphicTexture { id, width: data.image.width, height: data.image.height };
+            if let Some(old) = self.textures.insert(data.id, texture) {
+                unsafe { gl::DeleteTextures(1, &old.id) };
+            }
+        }
+
+        for id in updates.removed {
+            if let Some(texture) = self.textures.remove(&id) {
+                unsafe { gl::DeleteTextures(1, &texture.id) };
+            }
+        }
+    }
+
+    pub fn draw(&mut self, size_info: &SizeInfo, graphics: Vec<RenderGraphic>) {
+        let half_width = size_info.width() / 2.;
+        let half_height = size_info.height() / 2.;
+
+        // Build quad vertices for every texture.
+        self.vertices.values_mut().for_each(|vertices| vertices.clear());
+        for graphic in &graphics {
+            let id = graphic.graphic.graphic.id();
+            let texture = match self.textures.get(&id) {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            let vertices = self.vertices.entry(id).or_default();
+            Self::add_quad(vertices, size_info, half_width, half_height, texture, graphic);
+        }
+
+        unsafe {
+            gl::UseProgram(self.program.id());
+
+            // Bind VAO to enable vertex attribute slots.
+            gl::BindVertexArray(self.vao);
+
+            // Bind VBO only once for buffer data upload only.
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            for (id, vertices) in self.vertices.iter().filter(|(_, v)| !v.is_empty()) {
+                gl::BindTexture(gl::TEXTURE_2D, self.textures[id].id);
+
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (vertices.len() * mem::size_of::<Vertex>()) as isize,
+                    vertices.as_ptr() as *const _,
+                    gl::STREAM_DRAW,
+                );
+
+                // Draw all vertices as list of triangles.
+                gl::DrawArrays(gl::TRIANGLES, 0, vertices.len() as i32);
+            }
+
+            // Reset bindings to nothing.
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::UseProgram(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+
+        // Release vertex buffers of graphics which are no longer visible.
+        self.vertices.retain(|_, vertices| !vertices.is_empty());
+    }
+
+    fn add_quad(
+        vertices: &mut Vec<Vertex>,
+        size_info: &SizeInfo,
+        half_width: f32,
+        half_height: f32,
+        texture: &GraphicTexture,
+        graphic: &RenderGraphic,
+    ) {
+        let cell = &graphic.graphic;
+
+        // Clip the cell's section to the graphic, to avoid stretching its edges.
+        let source_x = cell.offset_x.max(0) as f32;
+        let source_y = cell.offset_y.max(0) as f32;
+        let source_end_x = (cell.offset_x + cell.width as i32).min(texture.width as i32) as f32;
+        let source_end_y = (cell.offset_y + cell.height as i32).min(texture.height as i32) as f32;
+        if source_end_x <= source_x || source_end_y <= source_y {
+            return;
+        }
+
+        // Scale the clipped section to the current cell size.
+        let scale_x = size_info.cell_width() / cell.width as f32;
+        let scale_y = size_info.cell_height() / cell.height as f32;
+        let x = size_info.padding_x()
+            + graphic.point.column.0 as f32 * size_info.cell_width()
+            + (source_x - cell.offset_x as f32) * scale_x;
+        let y = size_info.padding_y()
+            + graphic.point.line as f32 * size_info.cell_height()
+            + (source_y - cell.offset_y as f32) * scale_y;
+        let width = (source_end_x - source_x) * scale_x;
+        let height = (source_end_y - source_y) * scale_y;
+
+        // Calculate vertex positions in normalized device coordinates.
+        // NDC range from -1 to +1, with Y pointing up.
+        let x = x / half_width - 1.0;
+        let y = -y / half_height + 1.0;
+        let width = width / half_width;
+        let height = height / half_height;
+
+        let u = source_x / texture.width as f32;
+        let v = source_y / texture.height as f32;
+        let u_end = source_end_x / texture.width as f32;
+        let v_end = source_end_y / texture.height as f32;
+
+        // Make quad vertices.
+        let quad = [
+            Vertex { x, y, u, v },
+            Vertex { x, y: y - height, u, v: v_end },
+            Vertex { x: x + width, y, u: u_end, v },
+            Vertex { x: x + width, y: y - height, u: u_end, v: v_end },
+        ];
+
+        // Append the vertices to form two triangles.
+        vertices.push(quad[0]);
+        vertices.push(quad[1]);
+        vertices.push(quad[2]);
+        vertices.push(quad[2]);
+        vertices.push(quad[3]);
+        vertices.push(quad[1]);
+    }
+}
+
+impl Drop for GraphicsRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            for texture in self.textures.values() {
+                gl::DeleteTextures(1, &texture.id);
+            }
+
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
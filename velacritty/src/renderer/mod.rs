@@ -12,6 +12,8 @@ use glutin::display::{GetGlDisplay, GlDisplay};
 use log::{LevelFilter, debug, info};
 use unicode_width::UnicodeWidthChar;
 
+use velacritty_terminal::graphics::GraphicUpdates;
+use velacritty_terminal::grid::LineAttribute;
 use velacritty_terminal::index::Point;
 use velacritty_terminal::term::cell::Flags;
 
@@ -20,9 +22,11 @@ use crate::display::SizeInfo;
 use crate::display::color::Rgb;
 use crate::display::content::RenderableCell;
 use crate::gl;
+use crate::renderer::graphics::{GraphicsRenderer, RenderGraphic};
 use crate::renderer::rects::{RectRenderer, RenderRect};
 use crate::renderer::shader::ShaderError;
 
+pub mod graphics;
 pub mod platform;
 pub mod rects;
 mod shader;
@@ -89,6 +93,7 @@ enum TextRendererProvider {
 pub struct Renderer {
     text_renderer: TextRendererProvider,
     rect_renderer: RectRenderer,
+    graphics_renderer: GraphicsRenderer,
     robustness: bool,
 }
 
@@ -150,16 +155,16 @@ impl Renderer {
             None => (shader_version.as_ref() >= "3.3" && !is_gles_context, true),
         };
 
-        let (text_renderer, rect_renderer) = if use_glsl3 {
+        let (text_renderer, shader_version) = if use_glsl3 {
             let text_renderer = TextRendererProvider::Glsl3(Glsl3Renderer::new()?);
-            let rect_renderer = RectRenderer::new(ShaderVersion::Glsl3)?;
-            (text_renderer, rect_renderer)
+            (text_renderer, ShaderVersion::Glsl3)
         } else {
             let text_renderer =
                 TextRendererProvider::Gles2(Gles2Renderer::new(allow_dsb, is_gles_context)?);
-            let rect_renderer = RectRenderer::new(ShaderVersion::Gles2)?;
-            (text_renderer, rect_renderer)
+            (text_renderer, ShaderVersion::Gles2)
         };
+        let rect_renderer = RectRenderer::new(shader_version)?;
+        let graphics_renderer = GraphicsRenderer::new(shader_version)?;
 
         // Enable debug logging for OpenGL as well.
         if log::max_level() >= LevelFilter::Debug && GlExtensions::contains("GL_KHR_debug") {
@@ -171,7 +176,7 @@ impl Renderer {
             }
         }
 
-        Ok(Self { text_renderer, rect_renderer, robustness })
+        Ok(Self { text_renderer, rect_renderer, graphics_renderer, robustness })
     }
 
     pub fn draw_cells<I: Iterator<Item = RenderableCell>>(
@@ -223,6 +228,7 @@ impl Renderer {
                 fg,
                 bg,
                 underline: fg,
+                line_attribute: LineAttribute::Normal,
             })
         });
 
@@ -264,6 +270,38 @@ impl Renderer {
         }
     }
 
+    /// Upload new graphics and release the removed ones.
+    pub fn update_graphics(&mut self, updates: GraphicUpdates) {
+        if !updates.is_empty() {
+            self.graphics_renderer.update(updates);
+        }
+    }
+
+    /// Draw graphics on top of their cells.
+    pub fn draw_graphics(&mut self, size_info: &SizeInfo, graphics: Vec<RenderGraphic>) {
+        if graphics.is_empty() {
+            return;
+        }
+
+        // Prepare graphics rendering state.
+        unsafe {
+            // Remove padding from viewport.
+            gl::Viewport(0, 0, size_info.width() as i32, size_info.height() as i32);
+            gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::SRC_ALPHA, gl::ONE);
+        }
+
+        self.graphics_renderer.draw(size_info, graphics);
+
+        // Activate regular state again.
+        unsafe {
+            // Reset blending strategy.
+            gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
+
+            // Restore viewport with padding.
+            self.set_viewport(size_info);
+        }
+    }
+
     /// Fill the window with `color` and `alpha`.
     pub fn clear(&self, color: Rgb, alpha: f32) {
         unsafe {
@@ -1,6 +1,10 @@
+use std::cmp;
+
 use bitflags::bitflags;
 use crossfont::{GlyphKey, RasterizedGlyph};
 
+use velacritty_terminal::grapheme::{is_emoji_component, is_extended_pictographic};
+use velacritty_terminal::grid::LineAttribute;
 use velacritty_terminal::term::cell::Flags;
 
 use crate::display::SizeInfo;
@@ -118,6 +122,12 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
     /// Add item to the rendering queue.
     #[inline]
     fn add_render_item(&mut self, cell: &RenderableCell, glyph: &Glyph, size_info: &SizeInfo) {
+        let glyph = match scale_glyph(*glyph, cell.line_attribute, size_info) {
+            Some(glyph) => glyph,
+            None => return,
+        };
+        let glyph = &glyph;
+
         // Flush batch if tex changing.
         if !self.batch().is_empty() && self.batch().tex() != glyph.tex_id {
             self.render_batch();
@@ -164,6 +174,12 @@ pub trait TextRenderApi<T: TextRenderBatch>: LoadGlyph {
             cell.extra.as_mut().and_then(|extra| extra.zerowidth.take().filter(|_| !hidden))
         {
             for character in zerowidth {
+                // Emoji sequences cannot be combined into a single glyph without shaping, so only
+                // the first emoji of a grapheme cluster is drawn.
+                if is_emoji_component(character) || is_extended_pictographic(character) {
+                    continue;
+                }
+
                 glyph_key.character = character;
                 let glyph = glyph_cache.get(glyph_key, self, false);
                 self.add_render_item(&cell, &glyph, size_info);
@@ -196,6 +212,46 @@ impl LoadGlyph for LoaderApi<'_> {
     }
 }
 
+/// Stretch a glyph across the cells of double-width and double-height lines.
+///
+/// Since double-height lines only show half of their characters, the glyph is cropped to the
+/// visible half. Returns `None` if no part of the glyph is visible.
+fn scale_glyph(mut glyph: Glyph, attribute: LineAttribute, size_info: &SizeInfo) -> Option<Glyph> {
+    let cell_height = size_info.cell_height() as i16;
+    let visible_top = match attribute {
+        LineAttribute::Normal => return Some(glyph),
+        LineAttribute::DoubleWidth | LineAttribute::DoubleHeightTop => 0,
+        LineAttribute::DoubleHeightBottom => cell_height,
+    };
+
+    glyph.left *= 2;
+    glyph.width *= 2;
+
+    if attribute == LineAttribute::DoubleWidth {
+        return Some(glyph);
+    }
+
+    // Vertical extent of the scaled glyph, relative to the top of the upper line.
+    let top = 2 * (cell_height - glyph.top);
+    let bottom = top + 2 * glyph.height;
+
+    let cropped_top = cmp::max(top, visible_top);
+    let cropped_bottom = cmp::min(bottom, visible_top + cell_height);
+    if cropped_top >= cropped_bottom {
+        return None;
+    }
+
+    // Adjust the texture coordinates to the cropped part of the glyph.
+    let uv_per_pixel = glyph.uv_height / (bottom - top) as f32;
+    glyph.uv_bot += (cropped_top - top) as f32 * uv_per_pixel;
+    glyph.uv_height = (cropped_bottom - cropped_top) as f32 * uv_per_pixel;
+
+    glyph.top = cell_height - (cropped_top - visible_top);
+    glyph.height = cropped_bottom - cropped_top;
+
+    Some(glyph)
+}
+
 fn update_projection(u_projection: GLint, size: &SizeInfo) {
     let width = size.width();
     let height = size.height();
@@ -219,3 +275,41 @@ fn update_projection(u_projection: GLint, size: &SizeInfo) {
         gl::Uniform4f(u_projection, offset_x, offset_y, scale_x, scale_y);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_height_glyph() {
+        let size_info = SizeInfo::new(100., 100., 10., 20., 0., 0., false);
+        let glyph = Glyph {
+            tex_id: 0,
+            multicolor: false,
+            top: 15,
+            left: 1,
+            width: 8,
+            height: 10,
+            uv_bot: 0.,
+            uv_left: 0.,
+            uv_width: 0.4,
+            uv_height: 0.5,
+        };
+
+        let wide = scale_glyph(glyph, LineAttribute::DoubleWidth, &size_info).unwrap();
+        assert_eq!((wide.left, wide.width, wide.top, wide.height), (2, 16, 15, 10));
+
+        // The scaled glyph covers pixels 10 to 30, split across both lines.
+        let top = scale_glyph(glyph, LineAttribute::DoubleHeightTop, &size_info).unwrap();
+        assert_eq!((top.left, top.width, top.top, top.height), (2, 16, 10, 10));
+        assert_eq!((top.uv_bot, top.uv_height), (0., 0.25));
+
+        let bottom = scale_glyph(glyph, LineAttribute::DoubleHeightBottom, &size_info).unwrap();
+        assert_eq!((bottom.top, bottom.height), (20, 10));
+        assert_eq!((bottom.uv_bot, bottom.uv_height), (0.25, 0.25));
+
+        // Glyphs entirely in the other half are skipped.
+        let glyph = Glyph { top: 4, height: 4, ..glyph };
+        assert!(scale_glyph(glyph, LineAttribute::DoubleHeightTop, &size_info).is_none());
+    }
+}
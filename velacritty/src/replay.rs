@@ -0,0 +1,92 @@
+//! Loading recorded terminal output for `--replay`.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use velacritty_terminal::tty::replay::Frame;
+
+use crate::config::window::Dimensions;
+
+/// Recorded terminal output.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Recording {
+    pub frames: Vec<Frame>,
+
+    /// Terminal dimensions at the start of the recording, if they are known.
+    pub dimensions: Option<Dimensions>,
+}
+
+/// First line of an asciicast file.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+    width: usize,
+    height: usize,
+}
+
+/// Load an asciicast v2 recording, or a `--ref-test` recording of the raw PTY output.
+pub fn load(path: &Path) -> Result<Recording, Box<dyn Error>> {
+    parse(&fs::read(path)?)
+}
+
+fn parse(data: &[u8]) -> Result<Recording, Box<dyn Error>> {
+    let header_len = data.iter().position(|&byte| byte == b'\n').unwrap_or(data.len());
+    let header = match serde_json::from_slice::<Header>(&data[..header_len]) {
+        Ok(header) if header.version == 2 => header,
+        Ok(header) => {
+            return Err(format!("unsupported asciicast version {}", header.version).into());
+        },
+        // Raw recordings have no timing, so use a frame per line for stepping through them.
+        Err(_) => {
+            let lines = data.split_inclusive(|&byte| byte == b'\n');
+            let frames = lines.map(|line| Frame { time: Duration::ZERO, data: line.into() });
+            return Ok(Recording { frames: frames.collect(), dimensions: None });
+        },
+    };
+
+    let events = String::from_utf8(data[header_len..].into())?;
+    let mut frames = Vec::new();
+    for (index, line) in events.lines().enumerate().filter(|(_, line)| !line.is_empty()) {
+        let (time, code, data): (f64, String, String) = serde_json::from_str(line)
+            .map_err(|err| format!("invalid event on line {}: {err}", index + 1))?;
+
+        // Only output is replayed, resizing the window is left to the user.
+        if code == "o" {
+            frames.push(Frame { time: Duration::try_from_secs_f64(time)?, data: data.into() });
+        }
+    }
+
+    let dimensions = Dimensions { columns: header.width, lines: header.height };
+    Ok(Recording { frames, dimensions: Some(dimensions) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_recordings() {
+        let asciicast = b"{\"version\": 2, \"width\": 80, \"height\": 24, \"timestamp\": 1}\n[0.5, \
+                          \"o\", \"a\\u001b[m\"]\n[1.0, \"r\", \"90x30\"]\n\n[1.25, \"o\", \"\xc3\xa9\"]\n";
+        let recording = parse(asciicast).unwrap();
+        assert_eq!(recording.dimensions, Some(Dimensions { columns: 80, lines: 24 }));
+        assert_eq!(recording.frames, [
+            Frame { time: Duration::from_millis(500), data: b"a\x1b[m".into() },
+            Frame { time: Duration::from_millis(1250), data: "é".into() },
+        ]);
+
+        let recording = parse(b"a\r\n\x1b[mb").unwrap();
+        assert_eq!(recording.dimensions, None);
+        assert_eq!(recording.frames, [
+            Frame { time: Duration::ZERO, data: b"a\r\n".into() },
+            Frame { time: Duration::ZERO, data: b"\x1b[mb".into() },
+        ]);
+
+        assert!(parse(b"{\"version\": 1, \"width\": 80, \"height\": 24}\n").is_err());
+        assert!(parse(b"{\"version\": 2, \"width\": 80, \"height\": 24}\n[0.5, \"o\"]").is_err());
+    }
+}
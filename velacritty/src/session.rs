@@ -0,0 +1,147 @@
+//! Saving and restoring terminal sessions.
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use velacritty_terminal::grid::{Dimensions, Grid, Row};
+use velacritty_terminal::index::Line;
+use velacritty_terminal::term::cell::Cell;
+
+use crate::cli::WindowOptions;
+
+/// File name of the saved session.
+const SESSION_FILE: &str = "session.json.gz";
+
+/// Windows saved for restoring them with `--restore`.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Session {
+    pub windows: Vec<WindowSession>,
+}
+
+/// State of a single terminal window.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WindowSession {
+    /// Window title.
+    pub title: String,
+
+    /// Working directory of the shell.
+    pub working_directory: Option<PathBuf>,
+
+    /// Command the window was launched with, empty for the default shell.
+    pub command: Vec<String>,
+
+    /// Lines of the primary screen and its history up to the cursor, oldest line first.
+    pub history: Vec<Row<Cell>>,
+}
+
+impl WindowSession {
+    /// Options for reopening the window, based on the `options` passed on the command line.
+    pub fn window_options(&self, mut options: WindowOptions) -> WindowOptions {
+        options.terminal_options.working_directory =
+            self.working_directory.clone().filter(|directory| directory.is_dir());
+        options.terminal_options.command = self.command.clone();
+        options
+    }
+}
+
+/// Copy the lines of a grid up to its cursor, oldest line first.
+///
+/// Compressed lines are only decompressed for the copy, without caching them in the grid.
+pub fn history(grid: &Grid<Cell>) -> Vec<Row<Cell>> {
+    let end = grid.cursor.point.line.min(grid.bottommost_line());
+    (grid.topmost_line().0..=end.0).map(|line| grid.with_row(Line(line), Row::clone)).collect()
+}
+
+/// Save the session, replacing the previously saved one.
+///
+/// Returns the path of the session file.
+pub fn save(session: &Session) -> Result<PathBuf, Box<dyn Error>> {
+    let path = session_path().ok_or("unable to locate state directory")?;
+    write(session, &path)?;
+    Ok(path)
+}
+
+/// Load the saved session.
+pub fn load() -> Result<Session, Box<dyn Error>> {
+    let path = session_path().ok_or("unable to locate state directory")?;
+    read(&path)
+}
+
+fn write(session: &Session, path: &Path) -> Result<(), Box<dyn Error>> {
+    let directory = path.parent().ok_or("invalid session path")?;
+    fs::create_dir_all(directory)?;
+
+    // Write to a temporary file first, so a failed save never destroys the previous session.
+    let mut file = NamedTempFile::new_in(directory)?;
+    let mut encoder = GzEncoder::new(BufWriter::new(file.as_file_mut()), Compression::fast());
+    serde_json::to_writer(&mut encoder, session)?;
+    encoder.finish()?.flush()?;
+
+    file.persist(path)?;
+
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Session, Box<dyn Error>> {
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(GzDecoder::new(BufReader::new(file)))?)
+}
+
+#[cfg(not(windows))]
+fn session_path() -> Option<PathBuf> {
+    xdg::BaseDirectories::with_prefix("velacritty")
+        .get_state_home()
+        .map(|directory| directory.join(SESSION_FILE))
+}
+
+#[cfg(windows)]
+fn session_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|directory| directory.join("velacritty").join(SESSION_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use velacritty_terminal::index::{Column, Point};
+
+    #[test]
+    fn roundtrip() {
+        let mut grid = Grid::<Cell>::new(3, 5, 10);
+        grid[Line(0)][Column(0)].c = 'a';
+        grid[Line(1)][Column(0)].c = 'b';
+        grid.cursor.point = Point::new(Line(1), Column(1));
+
+        let window = WindowSession {
+            title: String::from("title"),
+            working_directory: None,
+            command: vec![String::from("vim")],
+            history: history(&grid),
+        };
+        let session = Session { windows: vec![window] };
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("state").join(SESSION_FILE);
+        write(&session, &path).unwrap();
+        let session = read(&path).unwrap();
+
+        assert_eq!(session.windows.len(), 1);
+        let window = &session.windows[0];
+        assert_eq!(window.title, "title");
+        assert_eq!(window.command, ["vim"]);
+
+        // Lines below the cursor are not saved.
+        let history = &window.history;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0][Column(0)].c, 'a');
+        assert_eq!(history[1][Column(0)].c, 'b');
+    }
+}
@@ -2,10 +2,15 @@
 
 use std::error::Error;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, Write};
 use std::mem;
 #[cfg(not(windows))]
 use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+#[cfg(not(windows))]
+use std::process;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::time::Instant;
@@ -14,35 +19,54 @@ use glutin::config::Config as GlutinConfig;
 use glutin::display::GetGlDisplay;
 #[cfg(all(feature = "x11", not(any(target_os = "macos", windows))))]
 use glutin::platform::x11::X11GlConfigExt;
-use log::info;
+use log::{error, info};
 use serde_json as json;
 use winit::event::{Event as WinitEvent, Modifiers, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
 use winit::raw_window_handle::HasDisplayHandle;
 use winit::window::WindowId;
 
-use velacritty_terminal::event::Event as TerminalEvent;
-use velacritty_terminal::event_loop::{EventLoop as PtyEventLoop, Msg, Notifier};
-use velacritty_terminal::grid::{Dimensions, Scroll};
+use velacritty_terminal::event::{Event as TerminalEvent, OnResize};
+use velacritty_terminal::event_loop::{EventLoop as PtyEventLoop, EventLoopSender, Msg, Notifier};
+use velacritty_terminal::grid::{Dimensions, MemoryUsage, Scroll};
 use velacritty_terminal::index::Direction;
+use velacritty_terminal::recording::Recorder;
 use velacritty_terminal::sync::FairMutex;
 use velacritty_terminal::term::test::TermSize;
 use velacritty_terminal::term::{Term, TermMode};
-use velacritty_terminal::tty;
+#[cfg(not(windows))]
+use velacritty_terminal::tty::replay::{Replay, ReplayControl};
+use velacritty_terminal::tty::{self, EventedPty};
 
 use crate::cli::{ParsedOptions, WindowOptions};
 use crate::clipboard::Clipboard;
 use crate::config::UiConfig;
+#[cfg(unix)]
+use crate::config::export::ExportFormat;
+#[cfg(unix)]
+use crate::daemon::foreground_process_name;
+#[cfg(not(windows))]
+use crate::daemon::foreground_process_path;
 use crate::display::Display;
 use crate::display::window::Window;
 use crate::event::{
     ActionContext, Event, EventProxy, InlineSearchState, Mouse, SearchState, TouchPurpose,
 };
 #[cfg(unix)]
+use crate::export::{ExportOptions, TextRange};
+#[cfg(unix)]
+use crate::ipc::WindowInfo;
+#[cfg(unix)]
 use crate::logging::LOG_TARGET_IPC_CONFIG;
-use crate::message_bar::MessageBuffer;
+use crate::message_bar::{Message, MessageBuffer, MessageType};
+#[cfg(not(windows))]
+use crate::replay;
 use crate::scheduler::Scheduler;
-use crate::{input, renderer};
+use crate::session::{self, WindowSession};
+use crate::{export, input, renderer};
+
+/// Message bar target of the recording indicator.
+const RECORDING_MESSAGE_TARGET: &str = "velacritty_recording";
 
 /// Event context for one individual Alacritty window.
 pub struct WindowContext {
@@ -53,6 +77,7 @@ pub struct WindowContext {
     terminal: Arc<FairMutex<Term<EventProxy>>>,
     cursor_blink_timed_out: bool,
     prev_bell_cmd: Option<Instant>,
+    prev_notification: Option<Instant>,
     modifiers: Modifiers,
     inline_search_state: InlineSearchState,
     search_state: SearchState,
@@ -61,12 +86,16 @@ pub struct WindowContext {
     touch: TouchPurpose,
     occluded: bool,
     preserve_title: bool,
+    command: Vec<String>,
     #[cfg(not(windows))]
     master_fd: RawFd,
     #[cfg(not(windows))]
     shell_pid: u32,
     window_config: ParsedOptions,
     config: Rc<UiConfig>,
+    #[cfg(not(windows))]
+    replay: Option<ReplayControl>,
+    recording: bool,
 }
 
 impl WindowContext {
@@ -176,6 +205,7 @@ impl WindowContext {
         options.terminal_options.override_pty_config(&mut pty_config);
 
         let preserve_title = options.window_identity.title.is_some();
+        let command = options.terminal_options.command.clone();
 
         info!(
             "PTY dimensions: {:?} x {:?}",
@@ -193,52 +223,93 @@ impl WindowContext {
         let terminal = Term::new(config.term_options(), &display.size_info, event_proxy.clone());
         let mut terminal_lock = terminal;
 
+        // Allow placing graphics before the first resize.
+        let (cell_width, cell_height) =
+            (display.size_info.cell_width(), display.size_info.cell_height());
+        terminal_lock.graphics_mut().set_cell_size(cell_width as usize, cell_height as usize);
+
         // Sync initial auto_scroll config to grid (critical for startup behavior).
         terminal_lock.grid_mut().set_auto_scroll_enabled(config.scrolling.auto_scroll);
 
         let terminal = Arc::new(FairMutex::new(terminal_lock));
 
-        // Create the PTY.
-        //
-        // The PTY forks a process to run the shell on the slave side of the
-        // pseudoterminal. A file descriptor for the master side is retained for
-        // reading/writing to the shell.
-        let pty = tty::new(&pty_config, display.size_info.into(), display.window.id().into())?;
+        // Start recording before the I/O thread reads any output.
+        let record = options.terminal_options.record.as_deref();
+        let recorder = record.and_then(|path| {
+            Recorder::create(path, display.size_info.into())
+                .inspect_err(|err| error!("Unable to record to {path:?}: {err}"))
+                .ok()
+        });
+        let recording = recorder.is_some();
+
+        let (drain_on_exit, ref_test) = (pty_config.drain_on_exit, config.debug.ref_test);
 
+        // Play back a recording instead of running a shell.
         #[cfg(not(windows))]
-        let master_fd = pty.file().as_raw_fd();
+        let replay = match &options.terminal_options.replay {
+            Some(path) => {
+                let recording = replay::load(path).map_err(|err| format!("{path:?}: {err}"))?;
+                if let Some(dimensions) = recording.dimensions {
+                    display.request_dimensions(&config, dimensions);
+                }
+
+                let speed =
+                    options.terminal_options.replay_speed.map_or(1., |speed| speed.factor());
+                let paused = options.terminal_options.replay_paused;
+                Some(Replay::new(recording.frames, speed, paused)?)
+            },
+            None => None,
+        };
         #[cfg(not(windows))]
-        let shell_pid = pty.child().id();
+        let replay_control = replay.as_ref().map(Replay::control);
 
-        // Create the pseudoterminal I/O loop.
-        //
-        // PTY I/O is ran on another thread as to not occupy cycles used by the
-        // renderer and input processing. Note that access to the terminal state is
-        // synchronized since the I/O loop updates the state, and the display
-        // consumes it periodically.
-        let event_loop = PtyEventLoop::new(
-            Arc::clone(&terminal),
-            event_proxy.clone(),
-            pty,
-            pty_config.drain_on_exit,
-            config.debug.ref_test,
+        #[cfg(not(windows))]
+        let (loop_tx, master_fd, shell_pid) = match replay {
+            // Without a shell, new windows and daemons use Velacritty's working directory.
+            Some(replay) => {
+                let loop_tx = Self::spawn_event_loop(
+                    &terminal,
+                    &event_proxy,
+                    replay,
+                    drain_on_exit,
+                    ref_test,
+                    recorder,
+                )?;
+                (loop_tx, -1, process::id())
+            },
+            None => {
+                let pty = Self::create_pty(&pty_config, &display)?;
+                let (master_fd, shell_pid) = (pty.file().as_raw_fd(), pty.child().id());
+                let loop_tx = Self::spawn_event_loop(
+                    &terminal,
+                    &event_proxy,
+                    pty,
+                    drain_on_exit,
+                    ref_test,
+                    recorder,
+                )?;
+                (loop_tx, master_fd, shell_pid)
+            },
+        };
+        #[cfg(windows)]
+        let loop_tx = Self::spawn_event_loop(
+            &terminal,
+            &event_proxy,
+            Self::create_pty(&pty_config, &display)?,
+            drain_on_exit,
+            ref_test,
+            recorder,
         )?;
 
-        // The event loop channel allows write requests from the event processor
-        // to be sent to the pty loop and ultimately written to the pty.
-        let loop_tx = event_loop.channel();
-
-        // Kick off the I/O thread.
-        let _io_thread = event_loop.spawn();
-
         // Start cursor blinking, in case `Focused` isn't sent on startup.
         if config.cursor.style().blinking {
             event_proxy.send_event(TerminalEvent::CursorBlinkingChange.into());
         }
 
         // Create context for the Alacritty window.
-        Ok(WindowContext {
+        let mut window_context = WindowContext {
             preserve_title,
+            command,
             terminal,
             display,
             #[cfg(not(windows))]
@@ -249,6 +320,7 @@ impl WindowContext {
             notifier: Notifier(loop_tx),
             cursor_blink_timed_out: Default::default(),
             prev_bell_cmd: Default::default(),
+            prev_notification: Default::default(),
             inline_search_state: Default::default(),
             message_buffer: Default::default(),
             window_config: Default::default(),
@@ -259,7 +331,64 @@ impl WindowContext {
             mouse: Default::default(),
             touch: Default::default(),
             dirty: Default::default(),
-        })
+            #[cfg(not(windows))]
+            replay: replay_control,
+            recording,
+        };
+
+        if let Some(path) = record.filter(|_| recording) {
+            window_context.show_recording_indicator(path);
+        }
+
+        Ok(window_context)
+    }
+
+    /// Create the PTY.
+    ///
+    /// The PTY forks a process to run the shell on the slave side of the
+    /// pseudoterminal. A file descriptor for the master side is retained for
+    /// reading/writing to the shell.
+    fn create_pty(pty_config: &tty::Options, display: &Display) -> io::Result<tty::Pty> {
+        tty::new(pty_config, display.size_info.into(), display.window.id().into())
+    }
+
+    /// Create the pseudoterminal I/O loop.
+    ///
+    /// PTY I/O is ran on another thread as to not occupy cycles used by the
+    /// renderer and input processing. Note that access to the terminal state is
+    /// synchronized since the I/O loop updates the state, and the display
+    /// consumes it periodically.
+    ///
+    /// The returned channel allows write requests from the event processor
+    /// to be sent to the pty loop and ultimately written to the pty.
+    fn spawn_event_loop<T>(
+        terminal: &Arc<FairMutex<Term<EventProxy>>>,
+        event_proxy: &EventProxy,
+        pty: T,
+        drain_on_exit: bool,
+        ref_test: bool,
+        recorder: Option<Recorder>,
+    ) -> io::Result<EventLoopSender>
+    where
+        T: EventedPty + OnResize + Send + 'static,
+    {
+        let event_loop = PtyEventLoop::new(
+            Arc::clone(terminal),
+            event_proxy.clone(),
+            pty,
+            drain_on_exit,
+            ref_test,
+        )?;
+        let loop_tx = event_loop.channel();
+
+        if let Some(recorder) = recorder {
+            let _ = loop_tx.send(Msg::StartRecording(recorder));
+        }
+
+        // Kick off the I/O thread.
+        let _io_thread = event_loop.spawn();
+
+        Ok(loop_tx)
     }
 
     /// Update the terminal window to the latest config.
@@ -288,6 +417,11 @@ impl WindowContext {
             self.display.pending_update.set_font(font);
         }
 
+        // Only override auto-scroll toggled at runtime when the configured value changes.
+        if old_config.scrolling.auto_scroll != self.config.scrolling.auto_scroll {
+            self.set_auto_scroll(self.config.scrolling.auto_scroll);
+        }
+
         // Always reload the theme to account for auto-theme switching.
         self.display.window.set_theme(self.config.window.theme());
 
@@ -343,6 +477,217 @@ impl WindowContext {
         &self.config
     }
 
+    /// Approximate memory used by the terminal's scrollback.
+    #[cfg(unix)]
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.terminal.lock().memory_usage()
+    }
+
+    /// Check if the window has keyboard focus.
+    #[cfg(unix)]
+    pub fn is_focused(&self) -> bool {
+        self.terminal.lock().is_focused
+    }
+
+    /// Write the scrollback or the selection to a file.
+    #[cfg(unix)]
+    pub fn export(&self, options: &ExportOptions) -> Result<PathBuf, Box<dyn Error>> {
+        let terminal = self.terminal.lock();
+        let text = export::export(&terminal, &self.display.colors, &self.config.export, options);
+        drop(terminal);
+
+        text?.write(&self.config.export, options)
+    }
+
+    /// Export the selection, or the scrollback without a selection, showing the result in the
+    /// message bar.
+    pub fn export_scrollback(&mut self) {
+        let selection = self.terminal.lock().selection.as_ref().is_some_and(|s| !s.is_empty());
+        let options = ExportOptions { selection, ..Default::default() };
+
+        let message = match self.export(&options) {
+            Ok(path) => {
+                info!("Exported terminal content to {path:?}");
+                Message::new(format!("Exported to {}", path.display()), MessageType::Warning)
+            },
+            Err(err) => {
+                error!("Unable to export terminal content: {err}");
+                Message::new(format!("Unable to export: {err}"), MessageType::Error)
+            },
+        };
+
+        self.message_buffer.push(message);
+        self.display.pending_update.dirty = true;
+        self.dirty = true;
+    }
+
+    /// Read a part of the terminal content.
+    #[cfg(unix)]
+    pub fn text(&self, range: &TextRange, format: ExportFormat) -> Result<String, Box<dyn Error>> {
+        let terminal = self.terminal.lock();
+        export::text(&terminal, &self.display.colors, range, format)
+    }
+
+    /// Pause or resume the replay of a recording.
+    #[cfg(not(windows))]
+    pub fn toggle_replay_pause(&self) {
+        if let Some(replay) = &self.replay {
+            replay.toggle_pause();
+        }
+    }
+
+    /// Pause the replay of a recording and play its next frame.
+    #[cfg(not(windows))]
+    pub fn step_replay(&self) {
+        if let Some(replay) = &self.replay {
+            replay.step();
+        }
+    }
+
+    /// Toggle automatic scrolling to new output, returning whether it is enabled now.
+    pub fn toggle_auto_scroll(&mut self) -> bool {
+        let enabled = !self.display.auto_scroll_enabled;
+        self.set_auto_scroll(enabled);
+        enabled
+    }
+
+    fn set_auto_scroll(&mut self, enabled: bool) {
+        self.display.auto_scroll_enabled = enabled;
+        self.terminal.lock().grid_mut().set_auto_scroll_enabled(enabled);
+
+        // Redraw the auto-scroll indicator.
+        self.dirty = true;
+        if self.display.window.has_frame {
+            self.display.window.request_redraw();
+        }
+    }
+
+    /// Start or stop recording the terminal output.
+    pub fn toggle_recording(&mut self) {
+        if !self.recording {
+            match export::recording_file(&self.config.export) {
+                Ok((path, file)) => self.start_recording(&path, file),
+                Err(err) => error!("Unable to start recording: {err}"),
+            }
+            return;
+        }
+
+        let _ = self.notifier.0.send(Msg::StopRecording);
+        self.recording = false;
+
+        self.message_buffer.remove_target(RECORDING_MESSAGE_TARGET);
+        self.display.pending_update.dirty = true;
+        self.dirty = true;
+    }
+
+    /// Clear the recording state, after the recording was stopped due to a write error.
+    pub fn recording_failed(&mut self, err: &str) {
+        self.recording = false;
+
+        self.message_buffer.remove_target(RECORDING_MESSAGE_TARGET);
+        let message = Message::new(format!("Recording stopped: {err}"), MessageType::Error);
+        self.message_buffer.push(message);
+        self.display.pending_update.dirty = true;
+        self.dirty = true;
+    }
+
+    /// Record the terminal output to an asciicast file.
+    fn start_recording(&mut self, path: &Path, file: File) {
+        let recorder = match Recorder::new(file, self.display.size_info.into()) {
+            Ok(recorder) => recorder,
+            Err(err) => {
+                error!("Unable to record to {path:?}: {err}");
+                return;
+            },
+        };
+
+        let _ = self.notifier.0.send(Msg::StartRecording(recorder));
+        self.recording = true;
+
+        self.show_recording_indicator(path);
+    }
+
+    /// Show an indicator in the message bar until the recording is stopped.
+    fn show_recording_indicator(&mut self, path: &Path) {
+        let text = format!("Recording to {}", path.display());
+        let mut message = Message::new(text, MessageType::Warning);
+        message.set_target(RECORDING_MESSAGE_TARGET.into());
+        self.message_buffer.push(message);
+        self.display.pending_update.dirty = true;
+        self.dirty = true;
+    }
+
+    /// Current state of the window, for restoring it later.
+    pub fn session(&self) -> WindowSession {
+        let history = session::history(self.terminal.lock().primary_grid());
+        let working_directory = self.working_directory();
+
+        WindowSession {
+            title: self.display.window.title().to_owned(),
+            command: self.command.clone(),
+            working_directory,
+            history,
+        }
+    }
+
+    /// Description of the window for IPC clients.
+    #[cfg(unix)]
+    pub fn info(&self) -> WindowInfo {
+        let focused = self.terminal.lock().is_focused;
+        WindowInfo {
+            id: self.id().into(),
+            title: self.display.window.title().to_owned(),
+            working_directory: self.working_directory(),
+            foreground_process: foreground_process_name(self.master_fd, self.shell_pid).ok(),
+            columns: self.display.size_info.columns(),
+            lines: self.display.size_info.screen_lines(),
+            focused,
+        }
+    }
+
+    /// Working directory reported by the shell, or of the foreground process.
+    ///
+    /// The foreground process is looked up without holding the terminal lock.
+    fn working_directory(&self) -> Option<PathBuf> {
+        let terminal = self.terminal.lock();
+        let working_directory =
+            terminal.working_directory().filter(|cwd| cwd.is_local()).map(|cwd| cwd.path.clone());
+        drop(terminal);
+
+        #[cfg(not(windows))]
+        let working_directory = working_directory
+            .or_else(|| foreground_process_path(self.master_fd, self.shell_pid).ok());
+        working_directory
+    }
+
+    /// Restore and focus the window.
+    #[cfg(unix)]
+    pub fn focus(&self) {
+        self.display.window.set_minimized(false);
+        self.display.window.focus_window();
+    }
+
+    /// Close the window, like the window manager's close button.
+    #[cfg(unix)]
+    pub fn close(&mut self) {
+        self.display.window.hold = false;
+        self.terminal.lock().exit();
+    }
+
+    /// Set the window title, ignoring titles set by the terminal application from now on.
+    #[cfg(unix)]
+    pub fn set_title(&mut self, title: String) {
+        self.preserve_title = true;
+        self.display.window.set_title(title);
+    }
+
+    /// Restore the title and history of a saved window.
+    pub fn restore(&mut self, session: WindowSession) {
+        self.display.window.set_title(session.title);
+        self.terminal.lock().grid_mut().prepend_history(session.history);
+        self.dirty = true;
+    }
+
     /// Clear the window config overrides.
     #[cfg(unix)]
     pub fn reset_window_config(&mut self, config: Rc<UiConfig>) {
@@ -400,6 +745,10 @@ impl WindowContext {
             &self.config,
             &mut self.search_state,
         );
+
+        // Map mouse positions using the order and width of the text on screen.
+        self.mouse.bidi.clone_from(&self.display.bidi);
+        self.mouse.double_width_lines.clone_from(&self.display.double_width_lines);
     }
 
     /// Process events for this terminal window.
@@ -434,6 +783,7 @@ impl WindowContext {
         let context = ActionContext {
             cursor_blink_timed_out: &mut self.cursor_blink_timed_out,
             prev_bell_cmd: &mut self.prev_bell_cmd,
+            prev_notification: &mut self.prev_notification,
             message_buffer: &mut self.message_buffer,
             inline_search_state: &mut self.inline_search_state,
             search_state: &mut self.search_state,
@@ -449,6 +799,8 @@ impl WindowContext {
             master_fd: self.master_fd,
             #[cfg(not(windows))]
             shell_pid: self.shell_pid,
+            #[cfg(not(windows))]
+            replay_active: self.replay.is_some(),
             preserve_title: self.preserve_title,
             config: &self.config,
             event_proxy,
@@ -3,6 +3,9 @@ use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
 
 use crate::term::ClipboardType;
+use crate::term::notification::Urgency;
+use crate::term::progress::Progress;
+use crate::term::shell_integration::WorkingDirectory;
 use crate::vte::ansi::Rgb;
 
 /// Terminal event.
@@ -20,6 +23,9 @@ pub enum Event {
     /// Reset to the default window title.
     ResetTitle,
 
+    /// Shell's working directory change.
+    WorkingDirectory(WorkingDirectory),
+
     /// Request to store a text string in the clipboard.
     ClipboardStore(ClipboardType, String),
 
@@ -50,6 +56,15 @@ pub enum Event {
     /// Terminal bell ring.
     Bell,
 
+    /// Desktop notification request.
+    Notification { title: String, body: String, urgency: Urgency },
+
+    /// Progress report has changed.
+    Progress(Option<Progress>),
+
+    /// Recording the PTY output was stopped, since writing the recording failed.
+    RecordingFailed(String),
+
     /// Shutdown request.
     Exit,
 
@@ -69,8 +84,14 @@ impl Debug for Event {
             Event::CursorBlinkingChange => write!(f, "CursorBlinkingChange"),
             Event::MouseCursorDirty => write!(f, "MouseCursorDirty"),
             Event::ResetTitle => write!(f, "ResetTitle"),
+            Event::WorkingDirectory(cwd) => write!(f, "WorkingDirectory({cwd:?})"),
             Event::Wakeup => write!(f, "Wakeup"),
             Event::Bell => write!(f, "Bell"),
+            Event::Notification { title, body, urgency } => {
+                write!(f, "Notification({title}, {body}, {urgency:?})")
+            },
+            Event::Progress(progress) => write!(f, "Progress({progress:?})"),
+            Event::RecordingFailed(err) => write!(f, "RecordingFailed({err})"),
             Event::Exit => write!(f, "Exit"),
             Event::ChildExit(code) => write!(f, "ChildExit({code})"),
         }
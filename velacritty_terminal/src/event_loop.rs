@@ -15,10 +15,11 @@ use log::error;
 use polling::{Event as PollingEvent, Events, PollMode};
 
 use crate::event::{self, Event, EventListener, WindowSize};
+use crate::parser::Processor;
+use crate::recording::Recorder;
 use crate::sync::FairMutex;
 use crate::term::Term;
 use crate::{thread, tty};
-use vte::ansi;
 
 /// Max bytes to read from the PTY before forced terminal synchronization.
 pub(crate) const READ_BUFFER_SIZE: usize = 0x10_0000;
@@ -37,6 +38,12 @@ pub enum Msg {
 
     /// Instruction to resize the PTY.
     Resize(WindowSize),
+
+    /// Start recording the PTY output, replacing any active recording.
+    StartRecording(Recorder),
+
+    /// Stop recording the PTY output.
+    StopRecording,
 }
 
 /// The main event loop.
@@ -52,6 +59,10 @@ pub struct EventLoop<T: tty::EventedPty, U: EventListener> {
     event_proxy: U,
     drain_on_exit: bool,
     ref_test: bool,
+    recorder: Option<Recorder>,
+
+    /// PTY output which is recorded once the terminal is unlocked.
+    recorded: Vec<u8>,
 }
 
 impl<T, U> EventLoop<T, U>
@@ -78,6 +89,8 @@ where
             event_proxy,
             drain_on_exit,
             ref_test,
+            recorder: None,
+            recorded: Vec::new(),
         })
     }
 
@@ -92,7 +105,14 @@ where
         while let Some(msg) = self.rx.recv() {
             match msg {
                 Msg::Input(input) => state.write_list.push_back(input),
-                Msg::Resize(window_size) => self.pty.on_resize(window_size),
+                Msg::Resize(window_size) => {
+                    self.pty.on_resize(window_size);
+                    if let Some(Err(err)) = self.recorder.as_mut().map(|r| r.resize(window_size)) {
+                        self.stop_recording(err);
+                    }
+                },
+                Msg::StartRecording(recorder) => self.recorder = Some(recorder),
+                Msg::StopRecording => self.recorder = None,
                 Msg::Shutdown => return false,
             }
         }
@@ -100,8 +120,48 @@ where
         true
     }
 
+    /// Stop recording after writing the recording failed.
+    fn stop_recording(&mut self, err: io::Error) {
+        error!("Unable to write recording: {err}");
+        self.recorder = None;
+        self.event_proxy.send_event(Event::RecordingFailed(err.to_string()));
+    }
+
+    /// Write the PTY output which was read while the terminal was locked to the recording.
+    fn write_recording(&mut self) {
+        if self.recorded.is_empty() {
+            return;
+        }
+
+        let result = self.recorder.as_mut().map(|recorder| recorder.output(&self.recorded));
+        self.recorded.clear();
+
+        if let Some(Err(err)) = result {
+            self.stop_recording(err);
+        }
+    }
+
     #[inline]
     fn pty_read<X>(
+        &mut self,
+        state: &mut State,
+        buf: &mut [u8],
+        writer: Option<&mut X>,
+    ) -> io::Result<()>
+    where
+        X: Write,
+    {
+        let result = self.pty_read_locked(state, buf, writer);
+
+        // Record the output without blocking the terminal.
+        self.write_recording();
+
+        result
+    }
+
+    /// Read from the PTY and parse the output, while holding the terminal lock.
+    #[inline]
+    fn pty_read_locked<X>(
         &mut self,
         state: &mut State,
         buf: &mut [u8],
@@ -150,6 +210,11 @@ where
                 writer.write_all(&buf[..unprocessed]).unwrap();
             }
 
+            // Keep a copy of the bytes for the recording.
+            if self.recorder.is_some() {
+                self.recorded.extend_from_slice(&buf[..unprocessed]);
+            }
+
             // Parse the incoming bytes.
             state.parser.advance(&mut **terminal, &buf[..unprocessed]);
 
@@ -400,7 +465,7 @@ impl EventLoopSender {
 pub struct State {
     write_list: VecDeque<Cow<'static, [u8]>>,
     writing: Option<Writing>,
-    parser: ansi::Processor,
+    parser: Processor,
 }
 
 impl State {
@@ -0,0 +1,233 @@
+//! Grapheme cluster segmentation.
+//!
+//! This uses the extended grapheme cluster boundaries of [UAX #29], which are used to store
+//! user-perceived characters like emoji ZWJ sequences, flags and Indic conjuncts in a single cell
+//! when grapheme clustering (mode 2027) is enabled.
+//!
+//! [UAX #29]: https://www.unicode.org/reports/tr29/
+
+use std::cmp::{Ordering, max};
+use std::{iter, str};
+
+use unicode_segmentation::GraphemeCursor;
+use unicode_width::UnicodeWidthChar;
+
+/// Zero width joiner, used to combine multiple emoji into one.
+pub const ZWJ: char = '\u{200D}';
+
+/// Variation selector requesting emoji presentation.
+pub const VS16: char = '\u{FE0F}';
+
+/// Maximum number of bytes at the end of a cluster considered when looking for its boundary.
+const MAX_CONTEXT_LEN: usize = 128;
+
+/// Grapheme cluster stored in a cell, as its first character and the characters extending it.
+#[derive(Debug, Copy, Clone)]
+pub struct GraphemeCluster<'a> {
+    c: char,
+    extend: &'a [char],
+}
+
+impl<'a> GraphemeCluster<'a> {
+    pub fn new(c: char, extend: &'a [char]) -> Self {
+        Self { c, extend }
+    }
+
+    /// Check if `c` continues the cluster, instead of starting a new one.
+    pub fn continues(&self, c: char) -> bool {
+        // Skip the start of clusters which are too long to be checked in their entirety.
+        let mut chars = self.chars();
+        let mut len: usize = self.chars().map(char::len_utf8).sum();
+        while len > MAX_CONTEXT_LEN {
+            len -= chars.next().map_or(0, char::len_utf8);
+        }
+
+        let mut buf = [0; MAX_CONTEXT_LEN + 4];
+        let mut offset = 0;
+        for c in chars {
+            offset += c.encode_utf8(&mut buf[offset..]).len();
+        }
+        let len = offset + c.encode_utf8(&mut buf[offset..]).len();
+
+        let text = str::from_utf8(&buf[..len]).unwrap();
+        GraphemeCursor::new(offset, len, true).is_boundary(text, 0).is_ok_and(|boundary| !boundary)
+    }
+
+    /// Number of cells required to display the cluster.
+    pub fn width(&self) -> usize {
+        let width = self.c.width().unwrap_or(0);
+
+        // Emoji presentation and flags are always displayed using two cells.
+        let flag =
+            is_regional_indicator(self.c) && self.extend.iter().any(|&c| is_regional_indicator(c));
+        if flag || self.extend.contains(&VS16) { max(width, 2) } else { width }
+    }
+
+    fn chars(&self) -> impl DoubleEndedIterator<Item = char> + use<'a> {
+        iter::once(self.c).chain(self.extend.iter().copied())
+    }
+}
+
+/// Check if a character is one of the regional indicators, which form flags in pairs.
+pub fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// Check if a character is an emoji which can be joined with others using a [`ZWJ`].
+pub fn is_extended_pictographic(c: char) -> bool {
+    EXTENDED_PICTOGRAPHIC
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                Ordering::Less
+            } else if start > c {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Check if a character only changes the appearance of the emoji preceding it.
+///
+/// This includes joiners, variation selectors, skin tone modifiers and tags, but also the second
+/// regional indicator of a flag.
+pub fn is_emoji_component(c: char) -> bool {
+    matches!(c, ZWJ | '\u{FE0E}' | VS16 | '\u{1F3FB}'..='\u{1F3FF}' | '\u{E0020}'..='\u{E007F}')
+        || is_regional_indicator(c)
+}
+
+/// Ranges of characters with the `Extended_Pictographic` property, as of Unicode 16.0.0.
+///
+/// Generated from the grapheme cluster break table of `unicode-segmentation`, which uses the same
+/// Unicode version.
+#[rustfmt::skip]
+const EXTENDED_PICTOGRAPHIC: &[(char, char)] = &[
+    ('\u{A9}', '\u{A9}'), ('\u{AE}', '\u{AE}'), ('\u{203C}', '\u{203C}'), ('\u{2049}', '\u{2049}'),
+    ('\u{2122}', '\u{2122}'), ('\u{2139}', '\u{2139}'), ('\u{2194}', '\u{2199}'),
+    ('\u{21A9}', '\u{21AA}'), ('\u{231A}', '\u{231B}'), ('\u{2328}', '\u{2328}'),
+    ('\u{2388}', '\u{2388}'), ('\u{23CF}', '\u{23CF}'), ('\u{23E9}', '\u{23F3}'),
+    ('\u{23F8}', '\u{23FA}'), ('\u{24C2}', '\u{24C2}'), ('\u{25AA}', '\u{25AB}'),
+    ('\u{25B6}', '\u{25B6}'), ('\u{25C0}', '\u{25C0}'), ('\u{25FB}', '\u{25FE}'),
+    ('\u{2600}', '\u{2605}'), ('\u{2607}', '\u{2612}'), ('\u{2614}', '\u{2685}'),
+    ('\u{2690}', '\u{2705}'), ('\u{2708}', '\u{2712}'), ('\u{2714}', '\u{2714}'),
+    ('\u{2716}', '\u{2716}'), ('\u{271D}', '\u{271D}'), ('\u{2721}', '\u{2721}'),
+    ('\u{2728}', '\u{2728}'), ('\u{2733}', '\u{2734}'), ('\u{2744}', '\u{2744}'),
+    ('\u{2747}', '\u{2747}'), ('\u{274C}', '\u{274C}'), ('\u{274E}', '\u{274E}'),
+    ('\u{2753}', '\u{2755}'), ('\u{2757}', '\u{2757}'), ('\u{2763}', '\u{2767}'),
+    ('\u{2795}', '\u{2797}'), ('\u{27A1}', '\u{27A1}'), ('\u{27B0}', '\u{27B0}'),
+    ('\u{27BF}', '\u{27BF}'), ('\u{2934}', '\u{2935}'), ('\u{2B05}', '\u{2B07}'),
+    ('\u{2B1B}', '\u{2B1C}'), ('\u{2B50}', '\u{2B50}'), ('\u{2B55}', '\u{2B55}'),
+    ('\u{3030}', '\u{3030}'), ('\u{303D}', '\u{303D}'), ('\u{3297}', '\u{3297}'),
+    ('\u{3299}', '\u{3299}'), ('\u{1F000}', '\u{1F0FF}'), ('\u{1F10D}', '\u{1F10F}'),
+    ('\u{1F12F}', '\u{1F12F}'), ('\u{1F16C}', '\u{1F171}'), ('\u{1F17E}', '\u{1F17F}'),
+    ('\u{1F18E}', '\u{1F18E}'), ('\u{1F191}', '\u{1F19A}'), ('\u{1F1AD}', '\u{1F1E5}'),
+    ('\u{1F201}', '\u{1F20F}'), ('\u{1F21A}', '\u{1F21A}'), ('\u{1F22F}', '\u{1F22F}'),
+    ('\u{1F232}', '\u{1F23A}'), ('\u{1F23C}', '\u{1F23F}'), ('\u{1F249}', '\u{1F3FA}'),
+    ('\u{1F400}', '\u{1F53D}'), ('\u{1F546}', '\u{1F64F}'), ('\u{1F680}', '\u{1F6FF}'),
+    ('\u{1F774}', '\u{1F77F}'), ('\u{1F7D5}', '\u{1F7FF}'), ('\u{1F80C}', '\u{1F80F}'),
+    ('\u{1F848}', '\u{1F84F}'), ('\u{1F85A}', '\u{1F85F}'), ('\u{1F888}', '\u{1F88F}'),
+    ('\u{1F8AE}', '\u{1F8FF}'), ('\u{1F90C}', '\u{1F93A}'), ('\u{1F93C}', '\u{1F945}'),
+    ('\u{1F947}', '\u{1FAFF}'), ('\u{1FC00}', '\u{1FFFD}'),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clusters(text: &str) -> Vec<(String, usize)> {
+        let mut clusters: Vec<(char, Vec<char>)> = Vec::new();
+        for c in text.chars() {
+            match clusters.last_mut() {
+                Some((base, extend)) if GraphemeCluster::new(*base, extend).continues(c) => {
+                    extend.push(c)
+                },
+                _ => clusters.push((c, Vec::new())),
+            }
+        }
+
+        clusters
+            .into_iter()
+            .map(|(base, extend)| {
+                let width = GraphemeCluster::new(base, &extend).width();
+                (iter::once(base).chain(extend).collect(), width)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn combining_marks() {
+        assert_eq!(clusters("e\u{301}x"), [("e\u{301}".into(), 1), ("x".into(), 1)]);
+    }
+
+    #[test]
+    fn emoji_zwj_sequence() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(clusters(family), [(family.into(), 2)]);
+
+        let skin_tone = "\u{1F44B}\u{1F3FD}";
+        assert_eq!(clusters(skin_tone), [(skin_tone.into(), 2)]);
+
+        // ZWJ only joins pictographs.
+        assert_eq!(clusters("a\u{200D}b"), [("a\u{200D}".into(), 1), ("b".into(), 1)]);
+    }
+
+    #[test]
+    fn emoji_presentation() {
+        assert_eq!(clusters("\u{2764}\u{FE0F}"), [("\u{2764}\u{FE0F}".into(), 2)]);
+        assert_eq!(clusters("\u{2764}"), [("\u{2764}".into(), 1)]);
+    }
+
+    #[test]
+    fn flags() {
+        let flags = "\u{1F1FA}\u{1F1F8}\u{1F1E9}\u{1F1EA}\u{1F1EB}";
+        assert_eq!(clusters(flags), [
+            ("\u{1F1FA}\u{1F1F8}".into(), 2),
+            ("\u{1F1E9}\u{1F1EA}".into(), 2),
+            ("\u{1F1EB}".into(), 1),
+        ]);
+    }
+
+    #[test]
+    fn indic_conjuncts() {
+        // Devanagari KSSA followed by the vowel sign I.
+        let kssa = "\u{0915}\u{094D}\u{0937}\u{093F}";
+        assert_eq!(clusters(kssa), [(kssa.into(), 1)]);
+
+        // Without a virama, consonants are separate clusters.
+        assert_eq!(clusters("\u{0915}\u{0937}"), [("\u{0915}".into(), 1), ("\u{0937}".into(), 1)]);
+    }
+
+    #[test]
+    fn extended_pictographic() {
+        assert!(is_extended_pictographic('\u{1F468}'));
+        assert!(is_extended_pictographic('\u{2764}'));
+        assert!(!is_extended_pictographic('a'));
+        assert!(!is_extended_pictographic(ZWJ));
+        assert!(!is_extended_pictographic('\u{1F3FD}'));
+        assert!(!is_extended_pictographic('\u{1F1E6}'));
+    }
+
+    #[test]
+    fn grapheme_break_test() {
+        let tests = include_str!("../tests/unicode/GraphemeBreakTest.txt");
+        for test in tests.lines().filter(|line| !line.starts_with('#')) {
+            let expected: Vec<String> = test
+                .trim_matches(|c| c == '÷' || c == ' ')
+                .split('÷')
+                .map(|cluster| {
+                    cluster
+                        .split('×')
+                        .map(|c| {
+                            char::from_u32(u32::from_str_radix(c.trim(), 16).unwrap()).unwrap()
+                        })
+                        .collect()
+                })
+                .collect();
+
+            let text: String = expected.concat();
+            let actual: Vec<String> = clusters(&text).into_iter().map(|(text, _)| text).collect();
+            assert_eq!(actual, expected, "{test}");
+        }
+    }
+}
@@ -0,0 +1,774 @@
+//! Kitty terminal graphics protocol.
+//!
+//! Commands are transmitted as `APC G <control data> ; <payload> ST`, where the control data is a
+//! comma separated list of `key=value` pairs.
+//!
+//! See <https://sw.kovidgoyal.net/kitty/graphics-protocol/> for the full specification.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::{env, fmt, str};
+
+use base64::Engine;
+use base64::alphabet::STANDARD;
+use base64::engine::DecodePaddingMode;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use flate2::read::ZlibDecoder;
+
+use crate::graphics::{GraphicRef, Graphics, Image};
+
+/// Maximum width and height of an image in pixels.
+pub const MAX_SIZE: u32 = 10_000;
+
+/// Maximum number of bytes used by the images stored for a single terminal.
+pub const STORAGE_BUDGET: usize = 320 * 1024 * 1024;
+
+/// Maximum number of bytes in a single command.
+///
+/// The protocol limits chunks to 4096 bytes, larger images have to be sent in multiple chunks.
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Base64 engine accepting payloads with and without padding.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Action performed by a graphics command.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Action {
+    /// Transmit image data.
+    #[default]
+    Transmit,
+    /// Transmit image data and display it.
+    TransmitAndDisplay,
+    /// Display a previously transmitted image.
+    Put,
+    /// Delete images and placements.
+    Delete,
+    /// Check if an image could be loaded, without storing it.
+    Query,
+    /// Animation actions, which are not supported.
+    Unsupported,
+}
+
+/// Pixel format of the transmitted data.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Format {
+    Rgb,
+    #[default]
+    Rgba,
+    Png,
+}
+
+/// Location of the image data.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Medium {
+    /// Payload contains the image data.
+    #[default]
+    Direct,
+    /// Payload contains the path of a file.
+    File,
+    /// Payload contains the path of a temporary file, which is deleted after reading.
+    TempFile,
+    /// Payload contains the name of a POSIX shared memory object, which is unlinked after reading.
+    SharedMemory,
+}
+
+/// Error reported back to the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    code: &'static str,
+    message: String,
+}
+
+impl Error {
+    pub fn new<M: Into<String>>(code: &'static str, message: M) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    fn invalid<M: Into<String>>(message: M) -> Self {
+        Self::new("EINVAL", message)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.code, self.message)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        let code = match err.kind() {
+            std::io::ErrorKind::NotFound => "ENOENT",
+            std::io::ErrorKind::PermissionDenied => "EPERM",
+            _ => "EBADF",
+        };
+        Self::new(code, err.to_string())
+    }
+}
+
+/// Graphics command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    /// Action to perform (`a`).
+    pub action: Action,
+    /// Suppression of responses (`q`); `1` suppresses `OK` and `2` also suppresses errors.
+    pub quiet: u8,
+    /// Format of the image data (`f`).
+    pub format: Format,
+    /// Transmission medium (`t`).
+    pub medium: Medium,
+    /// Whether the data is zlib compressed (`o=z`).
+    pub compressed: bool,
+    /// Whether more chunks of data will follow (`m`).
+    pub more: bool,
+    /// Image ID (`i`).
+    pub image_id: u32,
+    /// Image number (`I`).
+    pub image_number: u32,
+    /// Placement ID (`p`).
+    pub placement_id: u32,
+    /// Width and height of raw pixel data (`s`, `v`).
+    pub width: u32,
+    pub height: u32,
+    /// Number of bytes to read from a file and their offset (`S`, `O`).
+    pub size: usize,
+    pub offset: usize,
+    /// Rectangle of the image which is displayed (`x`, `y`, `w`, `h`).
+    pub source_x: u32,
+    pub source_y: u32,
+    pub source_width: u32,
+    pub source_height: u32,
+    /// Pixel offset inside the first cell (`X`, `Y`).
+    pub cell_x_offset: u32,
+    pub cell_y_offset: u32,
+    /// Number of columns and lines the image is scaled to (`c`, `r`).
+    pub columns: u32,
+    pub lines: u32,
+    /// Stacking order relative to the text (`z`).
+    pub z_index: i32,
+    /// Whether the cursor moves after displaying the image (`C=0`).
+    pub move_cursor: bool,
+    /// Which placements are deleted (`d`).
+    pub delete: u8,
+    /// Base64 encoded payload.
+    pub payload: Vec<u8>,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self {
+            move_cursor: true,
+            delete: b'a',
+            action: Default::default(),
+            quiet: Default::default(),
+            format: Default::default(),
+            medium: Default::default(),
+            compressed: Default::default(),
+            more: Default::default(),
+            image_id: Default::default(),
+            image_number: Default::default(),
+            placement_id: Default::default(),
+            width: Default::default(),
+            height: Default::default(),
+            size: Default::default(),
+            offset: Default::default(),
+            source_x: Default::default(),
+            source_y: Default::default(),
+            source_width: Default::default(),
+            source_height: Default::default(),
+            cell_x_offset: Default::default(),
+            cell_y_offset: Default::default(),
+            columns: Default::default(),
+            lines: Default::default(),
+            z_index: Default::default(),
+            payload: Default::default(),
+        }
+    }
+}
+
+impl Command {
+    /// Parse the content of an APC string.
+    ///
+    /// Returns `None` if this isn't a graphics command.
+    pub fn parse(apc: &[u8]) -> Option<Self> {
+        let apc = apc.strip_prefix(b"G")?;
+        let (control, payload) = match apc.iter().position(|&b| b == b';') {
+            Some(index) => (&apc[..index], &apc[index + 1..]),
+            None => (apc, &[][..]),
+        };
+
+        let mut command = Command { payload: payload.to_vec(), ..Default::default() };
+
+        for pair in control.split(|&b| b == b',') {
+            let (key, value) = match pair {
+                [key, b'=', value @ ..] => (*key, value),
+                _ => continue,
+            };
+
+            let number = || str::from_utf8(value).ok()?.parse::<u32>().ok();
+            let number = || number().unwrap_or_default();
+
+            match key {
+                b'a' => {
+                    command.action = match value {
+                        b"t" => Action::Transmit,
+                        b"T" => Action::TransmitAndDisplay,
+                        b"p" => Action::Put,
+                        b"d" => Action::Delete,
+                        b"q" => Action::Query,
+                        _ => Action::Unsupported,
+                    }
+                },
+                b'q' => command.quiet = number() as u8,
+                b'f' => {
+                    command.format = match number() {
+                        24 => Format::Rgb,
+                        100 => Format::Png,
+                        _ => Format::Rgba,
+                    }
+                },
+                b't' => {
+                    command.medium = match value {
+                        b"f" => Medium::File,
+                        b"t" => Medium::TempFile,
+                        b"s" => Medium::SharedMemory,
+                        _ => Medium::Direct,
+                    }
+                },
+                b'o' => command.compressed = value == b"z",
+                b'm' => command.more = value == b"1",
+                b'i' => command.image_id = number(),
+                b'I' => command.image_number = number(),
+                b'p' => command.placement_id = number(),
+                b's' => command.width = number(),
+                b'v' => command.height = number(),
+                b'S' => command.size = number() as usize,
+                b'O' => command.offset = number() as usize,
+                b'x' => command.source_x = number(),
+                b'y' => command.source_y = number(),
+                b'w' => command.source_width = number(),
+                b'h' => command.source_height = number(),
+                b'X' => command.cell_x_offset = number(),
+                b'Y' => command.cell_y_offset = number(),
+                b'c' => command.columns = number(),
+                b'r' => command.lines = number(),
+                b'z' => {
+                    command.z_index =
+                        str::from_utf8(value).ok().and_then(|z| z.parse().ok()).unwrap_or(0)
+                },
+                b'C' => command.move_cursor = value != b"1",
+                b'd' => command.delete = value.first().copied().unwrap_or(b'a'),
+                _ => (),
+            }
+        }
+
+        Some(command)
+    }
+
+    /// Response to the client, if one should be sent.
+    pub fn response(&self, result: &Result<(), Error>) -> Option<String> {
+        // Commands without any ID never get a response.
+        if self.image_id == 0 && self.image_number == 0 {
+            return None;
+        }
+
+        let message = match result {
+            Ok(()) if self.quiet == 0 => String::from("OK"),
+            Err(err) if self.quiet < 2 => err.to_string(),
+            _ => return None,
+        };
+
+        let mut keys = format!("i={}", self.image_id);
+        if self.image_number != 0 {
+            keys.push_str(&format!(",I={}", self.image_number));
+        }
+        if self.placement_id != 0 {
+            keys.push_str(&format!(",p={}", self.placement_id));
+        }
+
+        Some(format!("\x1b_G{keys};{message}\x1b\\"))
+    }
+
+    /// Load the image transmitted with this command.
+    ///
+    /// Images whose decoded pixels would exceed `budget` bytes are rejected before decoding.
+    pub fn load_image(&self, budget: usize) -> Result<Image, Error> {
+        let payload =
+            BASE64.decode(&self.payload).map_err(|_| Error::invalid("invalid base64 payload"))?;
+
+        let mut data = match self.medium {
+            Medium::Direct => payload,
+            Medium::File => self.read_file(&payload, false)?,
+            Medium::TempFile => self.read_file(&payload, true)?,
+            Medium::SharedMemory => self.read_shared_memory(&payload)?,
+        };
+
+        if self.compressed {
+            let mut decompressed = Vec::new();
+            ZlibDecoder::new(data.as_slice())
+                .take(STORAGE_BUDGET as u64)
+                .read_to_end(&mut decompressed)
+                .map_err(|_| Error::invalid("invalid zlib data"))?;
+            data = decompressed;
+        }
+
+        match self.format {
+            Format::Png => decode_png(&data, budget),
+            Format::Rgb | Format::Rgba => self.decode_raw(data, budget),
+        }
+    }
+
+    /// Convert raw pixel data to an RGBA image.
+    fn decode_raw(&self, data: Vec<u8>, budget: usize) -> Result<Image, Error> {
+        if self.width == 0 || self.height == 0 || self.width > MAX_SIZE || self.height > MAX_SIZE {
+            return Err(Error::invalid("invalid image dimensions"));
+        }
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        if width * height * 4 > budget {
+            return Err(Error::invalid("image is too large"));
+        }
+
+        let bytes_per_pixel = if self.format == Format::Rgb { 3 } else { 4 };
+        if data.len() < width * height * bytes_per_pixel {
+            return Err(Error::new("ENODATA", "insufficient image data"));
+        }
+
+        let pixels = if self.format == Format::Rgb {
+            let mut pixels = Vec::with_capacity(width * height * 4);
+            for rgb in data.chunks_exact(3).take(width * height) {
+                pixels.extend_from_slice(&[rgb[0], rgb[1], rgb[2], u8::MAX]);
+            }
+            pixels
+        } else {
+            let mut data = data;
+            data.truncate(width * height * 4);
+            data
+        };
+
+        Ok(Image { width, height, pixels })
+    }
+
+    /// Read the image data from a file.
+    fn read_file(&self, path: &[u8], temporary: bool) -> Result<Vec<u8>, Error> {
+        let path = str::from_utf8(path).map_err(|_| Error::invalid("invalid file path"))?;
+
+        // Resolve symlinks and `..`, so the checks below apply to the file which is read.
+        let path = fs::canonicalize(path)?;
+
+        // Kernel interfaces look like regular files, but aren't images.
+        let kernel_path = ["/proc", "/sys", "/dev"].iter().any(|dir| path.starts_with(dir));
+        if kernel_path && !path.starts_with("/dev/shm") {
+            return Err(Error::invalid("not a regular file"));
+        }
+
+        // Temporary files must be clearly marked, since they are deleted after reading.
+        if temporary {
+            let in_temp_dir = [env::temp_dir(), PathBuf::from("/tmp"), PathBuf::from("/dev/shm")]
+                .iter()
+                .filter_map(|dir| fs::canonicalize(dir).ok())
+                .any(|dir| path.starts_with(dir));
+            let marked = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().contains("tty-graphics-protocol"));
+            if !in_temp_dir || !marked {
+                return Err(Error::invalid("not a temporary graphics file"));
+            }
+        }
+
+        // Only allow regular files, to prevent blocking on devices or pipes.
+        if !fs::metadata(&path)?.is_file() {
+            return Err(Error::invalid("not a regular file"));
+        }
+
+        let mut file = File::open(&path)?;
+        let data = self.read_data(&mut file);
+
+        if temporary {
+            let _ = fs::remove_file(&path);
+        }
+
+        data
+    }
+
+    /// Read the image data from a POSIX shared memory object.
+    #[cfg(unix)]
+    fn read_shared_memory(&self, name: &[u8]) -> Result<Vec<u8>, Error> {
+        use rustix::fs::Mode;
+        use rustix::shm;
+
+        let name = str::from_utf8(name).map_err(|_| Error::invalid("invalid object name"))?;
+        let fd =
+            shm::open(name, shm::OFlags::RDONLY, Mode::empty()).map_err(std::io::Error::from)?;
+        let _ = shm::unlink(name);
+
+        self.read_data(&mut File::from(fd))
+    }
+
+    #[cfg(not(unix))]
+    fn read_shared_memory(&self, _name: &[u8]) -> Result<Vec<u8>, Error> {
+        Err(Error::invalid("shared memory is not supported"))
+    }
+
+    /// Read the requested range of data from a file.
+    fn read_data(&self, file: &mut File) -> Result<Vec<u8>, Error> {
+        let size = if self.size == 0 { STORAGE_BUDGET } else { self.size.min(STORAGE_BUDGET) };
+
+        file.seek(SeekFrom::Start(self.offset as u64))?;
+        let mut data = Vec::new();
+        file.take(size as u64).read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+}
+
+/// Decode a PNG image to RGBA.
+fn decode_png(data: &[u8], budget: usize) -> Result<Image, Error> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|_| Error::invalid("invalid PNG data"))?;
+
+    let info = reader.info();
+    let size = info.width as usize * info.height as usize * 4;
+    if info.width > MAX_SIZE || info.height > MAX_SIZE || size > budget {
+        return Err(Error::invalid("image is too large"));
+    }
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(|_| Error::invalid("invalid PNG data"))?;
+    buffer.truncate(frame.buffer_size());
+
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    let pixels = match frame.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => {
+            buffer.chunks_exact(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX]).collect()
+        },
+        png::ColorType::GrayscaleAlpha => {
+            buffer.chunks_exact(2).flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]]).collect()
+        },
+        png::ColorType::Grayscale => {
+            buffer.iter().flat_map(|&gray| [gray, gray, gray, u8::MAX]).collect()
+        },
+        png::ColorType::Indexed => return Err(Error::invalid("unsupported PNG color type")),
+    };
+
+    Ok(Image { width, height, pixels })
+}
+
+/// Image transmitted by the client.
+#[derive(Debug)]
+pub struct StoredImage {
+    /// Image number, used by clients which let the terminal pick the ID.
+    pub number: u32,
+
+    /// Decoded image, kept around for later placements.
+    pub image: Arc<Image>,
+
+    /// Graphic displaying the entire image.
+    pub graphic: Arc<GraphicRef>,
+
+    /// Age of the image, used to evict the oldest images first.
+    age: u64,
+}
+
+/// Storage of transmitted images.
+#[derive(Debug)]
+pub struct ImageStorage {
+    /// Stored images by their ID.
+    images: HashMap<u32, StoredImage>,
+
+    /// Command with incomplete chunked data.
+    chunked: Option<Command>,
+
+    /// Number of bytes used by all images.
+    used: usize,
+
+    /// Counter for the image ages.
+    age: u64,
+
+    /// Last ID assigned to images without an explicit ID.
+    last_id: u32,
+
+    /// Maximum number of bytes used by all images.
+    budget: usize,
+}
+
+impl Default for ImageStorage {
+    fn default() -> Self {
+        Self {
+            budget: STORAGE_BUDGET,
+            images: Default::default(),
+            chunked: Default::default(),
+            used: Default::default(),
+            age: Default::default(),
+            last_id: Default::default(),
+        }
+    }
+}
+
+impl ImageStorage {
+    /// Collect the chunks of a command's data.
+    ///
+    /// Returns the command once all chunks have been received.
+    pub fn add_chunk(&mut self, mut command: Command) -> Option<Command> {
+        let more = command.more;
+
+        if let Some(mut chunked) = self.chunked.take() {
+            // Only the first chunk contains the full control data.
+            chunked.payload.append(&mut command.payload);
+            chunked.quiet = command.quiet.max(chunked.quiet);
+            command = chunked;
+        }
+
+        if more && command.payload.len() <= STORAGE_BUDGET {
+            self.chunked = Some(command);
+            None
+        } else {
+            command.more = false;
+            Some(command)
+        }
+    }
+
+    /// Pick an ID which is not in use yet.
+    pub fn next_id(&mut self) -> u32 {
+        loop {
+            self.last_id = self.last_id.wrapping_add(1).max(1);
+            if !self.images.contains_key(&self.last_id) {
+                return self.last_id;
+            }
+        }
+    }
+
+    /// Store a new image, replacing any image with the same ID.
+    pub fn insert(&mut self, graphics: &mut Graphics, id: u32, number: u32, image: Image) {
+        self.remove(id);
+
+        let image = Arc::new(image);
+        let graphic = graphics.insert(image.clone());
+
+        self.age += 1;
+        self.used += image.pixels.len();
+        self.images.insert(id, StoredImage { number, image, graphic, age: self.age });
+
+        // Evict the oldest images once the budget is exhausted.
+        while self.used > self.budget && self.images.len() > 1 {
+            let oldest = self.images.iter().min_by_key(|(_, image)| image.age).map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                self.remove(oldest);
+            }
+        }
+    }
+
+    /// Get an image by its ID.
+    pub fn get(&mut self, id: u32) -> Option<&StoredImage> {
+        self.age += 1;
+        let image = self.images.get_mut(&id)?;
+        image.age = self.age;
+        Some(image)
+    }
+
+    /// Find the ID of the newest image with the specified number.
+    pub fn find_number(&self, number: u32) -> Option<u32> {
+        let images = self.images.iter().filter(|(_, image)| image.number == number);
+        images.max_by_key(|(_, image)| image.age).map(|(id, _)| *id)
+    }
+
+    /// IDs of all stored images.
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.images.keys().copied()
+    }
+
+    /// Remove an image.
+    pub fn remove(&mut self, id: u32) {
+        if let Some(image) = self.images.remove(&id) {
+            self.used -= image.image.pixels.len();
+        }
+    }
+
+    /// Remove all images.
+    pub fn clear(&mut self) {
+        self.images.clear();
+        self.chunked = None;
+        self.used = 0;
+    }
+
+    /// Number of bytes used by the stored images.
+    #[inline]
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Maximum number of bytes used by the stored images.
+    #[inline]
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_command() {
+        let command = Command::parse(b"Ga=T,f=24,s=2,v=1,i=7,C=1,z=-5,q=2;AAAA").unwrap();
+
+        assert_eq!(command.action, Action::TransmitAndDisplay);
+        assert_eq!(command.format, Format::Rgb);
+        assert_eq!((command.width, command.height), (2, 1));
+        assert_eq!(command.image_id, 7);
+        assert_eq!(command.z_index, -5);
+        assert_eq!(command.quiet, 2);
+        assert!(!command.move_cursor);
+        assert_eq!(command.payload, b"AAAA");
+
+        assert_eq!(Command::parse(b"Xa=T"), None);
+    }
+
+    #[test]
+    fn load_raw_image() {
+        let command = Command::parse(b"Gf=24,s=2,v=1;/wAAAP8A").unwrap();
+        let image = command.load_image(STORAGE_BUDGET).unwrap();
+
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![255, 0, 0, 255, 0, 255, 0, 255]);
+
+        let command = Command::parse(b"Gf=32,s=2,v=2;/wAAAP8A").unwrap();
+        assert_eq!(command.load_image(STORAGE_BUDGET).unwrap_err().code, "ENODATA");
+
+        let command = Command::parse(b"Gf=24,s=2,v=1;/wAAAP8A").unwrap();
+        assert!(command.load_image(7).is_err());
+    }
+
+    #[test]
+    fn load_png_image() {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 2, 2);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.write_header().unwrap().write_image_data(&[255; 12]).unwrap();
+        let payload = BASE64.encode(&data);
+
+        let command = format!("Gf=100;{payload}");
+        let command = Command::parse(command.as_bytes()).unwrap();
+        assert_eq!(command.load_image(16).unwrap().pixels, vec![255; 16]);
+
+        // Images exceeding the budget are rejected before decoding.
+        assert!(command.load_image(15).is_err());
+    }
+
+    #[test]
+    fn load_compressed_image() {
+        use std::io::Write;
+
+        use flate2::Compression;
+        use flate2::write::ZlibEncoder;
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[1, 2, 3, 4]).unwrap();
+        let payload = BASE64.encode(encoder.finish().unwrap());
+
+        let command = format!("Gs=1,v=1,o=z;{payload}");
+        let image = Command::parse(command.as_bytes()).unwrap().load_image(STORAGE_BUDGET).unwrap();
+        assert_eq!(image.pixels, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn load_temp_file() {
+        let path = env::temp_dir().join(format!("tty-graphics-protocol-{}", std::process::id()));
+        fs::write(&path, [9, 9, 1, 2, 3, 4]).unwrap();
+
+        let payload = BASE64.encode(path.to_string_lossy().as_bytes());
+        let command = format!("Gt=t,s=1,v=1,O=2;{payload}");
+        let image = Command::parse(command.as_bytes()).unwrap().load_image(STORAGE_BUDGET).unwrap();
+
+        assert_eq!(image.pixels, vec![1, 2, 3, 4]);
+        assert!(!path.exists());
+
+        // Files without the protocol marker are rejected.
+        let payload = BASE64.encode(b"/etc/passwd");
+        let command = format!("Gt=t,s=1,v=1;{payload}");
+        assert!(Command::parse(command.as_bytes()).unwrap().load_image(STORAGE_BUDGET).is_err());
+
+        // The marker must be part of the file name.
+        let dir = env::temp_dir().join(format!("tty-graphics-protocol-{}", std::process::id()));
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("image"), [1, 2, 3, 4]).unwrap();
+        let payload = BASE64.encode(dir.join("image").to_string_lossy().as_bytes());
+        let command = format!("Gt=t,s=1,v=1;{payload}");
+        assert!(Command::parse(command.as_bytes()).unwrap().load_image(STORAGE_BUDGET).is_err());
+        assert!(dir.join("image").exists());
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Paths leaving the temporary directory are rejected.
+        let path = env::current_dir()
+            .unwrap()
+            .join(format!("outside-tty-graphics-protocol-{}", std::process::id()));
+        fs::write(&path, [1, 2, 3, 4]).unwrap();
+        let escaped = format!("{}/..{}", env::temp_dir().display(), path.display());
+        let payload = BASE64.encode(escaped.as_bytes());
+        let command = format!("Gt=t,s=1,v=1;{payload}");
+        let result = Command::parse(command.as_bytes()).unwrap().load_image(STORAGE_BUDGET);
+        let exists = path.exists();
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+        assert!(exists);
+    }
+
+    #[test]
+    fn load_kernel_file() {
+        let payload = BASE64.encode(b"/proc/self/../self/status");
+        let command = format!("Gt=f,s=1,v=1;{payload}");
+        assert!(Command::parse(command.as_bytes()).unwrap().load_image(STORAGE_BUDGET).is_err());
+    }
+
+    #[test]
+    fn chunked_transmission() {
+        let mut storage = ImageStorage::default();
+
+        assert_eq!(storage.add_chunk(Command::parse(b"Ga=T,i=3,m=1;AAAA").unwrap()), None);
+        let command = storage.add_chunk(Command::parse(b"Gm=0;BBBB").unwrap()).unwrap();
+
+        assert_eq!(command.action, Action::TransmitAndDisplay);
+        assert_eq!(command.image_id, 3);
+        assert_eq!(command.payload, b"AAAABBBB");
+    }
+
+    #[test]
+    fn responses() {
+        let command = Command::parse(b"Gi=1,p=2").unwrap();
+        assert_eq!(command.response(&Ok(())), Some(String::from("\x1b_Gi=1,p=2;OK\x1b\\")));
+
+        let command = Command::parse(b"Gi=1,q=1").unwrap();
+        assert_eq!(command.response(&Ok(())), None);
+        let err = Err(Error::new("ENOENT", "not found"));
+        assert_eq!(command.response(&err), Some(String::from("\x1b_Gi=1;ENOENT:not found\x1b\\")));
+
+        assert_eq!(Command::parse(b"Ga=T").unwrap().response(&Ok(())), None);
+    }
+
+    #[test]
+    fn storage_budget() {
+        let mut graphics = Graphics::default();
+        let mut storage = ImageStorage { budget: 64, ..Default::default() };
+        let image = |size: usize| Image { width: size / 4, height: 1, pixels: vec![0; size] };
+
+        storage.insert(&mut graphics, 1, 0, image(32));
+        storage.insert(&mut graphics, 2, 0, image(32));
+        assert_eq!(storage.used(), 64);
+
+        // Accessing an image makes it newer.
+        assert!(storage.get(1).is_some());
+
+        storage.insert(&mut graphics, 3, 0, image(4));
+        assert!(storage.get(1).is_some());
+        assert!(storage.get(2).is_none());
+        assert_eq!(storage.used(), 36);
+    }
+}
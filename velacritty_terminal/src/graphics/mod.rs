@@ -0,0 +1,257 @@
+//! Inline images displayed in the terminal grid.
+//!
+//! Images are stored once and referenced by every grid cell they cover. Once the last cell
+//! displaying an image has been cleared or has been rotated out of the scrollback history, the
+//! image is scheduled for removal, allowing the renderer to release its texture.
+
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+pub mod kitty;
+pub mod sixel;
+
+/// Unique identifier of a graphic.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GraphicId(u64);
+
+/// Decoded image with RGBA pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    /// Width in pixels.
+    pub width: usize,
+
+    /// Height in pixels.
+    pub height: usize,
+
+    /// Pixels in RGBA order, row by row.
+    pub pixels: Vec<u8>,
+}
+
+/// Graphic waiting to be uploaded by the renderer.
+#[derive(Debug)]
+pub struct GraphicData {
+    pub id: GraphicId,
+    pub image: Arc<Image>,
+}
+
+/// Shared reference to a graphic.
+///
+/// When the last reference is dropped, the graphic is queued for removal.
+#[derive(Debug)]
+pub struct GraphicRef {
+    id: GraphicId,
+    removed: Arc<Mutex<Vec<GraphicId>>>,
+}
+
+impl GraphicRef {
+    #[inline]
+    pub fn id(&self) -> GraphicId {
+        self.id
+    }
+}
+
+impl Drop for GraphicRef {
+    fn drop(&mut self) {
+        self.removed.lock().push(self.id);
+    }
+}
+
+/// Identifier of a kitty graphics protocol placement.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PlacementId {
+    /// Image ID.
+    pub image: u32,
+
+    /// Placement ID, which is zero for placements without an ID.
+    pub placement: u32,
+}
+
+/// Section of a graphic displayed in a single cell.
+#[derive(Debug, Clone)]
+pub struct GraphicCell {
+    /// Graphic displayed in this cell.
+    pub graphic: Arc<GraphicRef>,
+
+    /// Position of the cell's top-left corner inside the graphic, in pixels.
+    ///
+    /// This is negative when the graphic starts inside the cell.
+    pub offset_x: i32,
+    pub offset_y: i32,
+
+    /// Size of the area covered by the cell inside the graphic, in pixels.
+    pub width: u32,
+    pub height: u32,
+
+    /// Stacking order, graphics with a negative z-index are drawn below the text.
+    pub z_index: i32,
+
+    /// Kitty placement this cell is part of.
+    pub placement: Option<PlacementId>,
+}
+
+impl PartialEq for GraphicCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.graphic.id == other.graphic.id
+            && self.offset_x == other.offset_x
+            && self.offset_y == other.offset_y
+            && self.width == other.width
+            && self.height == other.height
+            && self.z_index == other.z_index
+            && self.placement == other.placement
+    }
+}
+
+impl Eq for GraphicCell {}
+
+/// Changes to the terminal's graphics since they were last retrieved.
+#[derive(Debug, Default)]
+pub struct GraphicUpdates {
+    /// New graphics which need to be uploaded.
+    pub added: Vec<GraphicData>,
+
+    /// Graphics which are no longer referenced by any cell.
+    pub removed: Vec<GraphicId>,
+}
+
+impl GraphicUpdates {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Graphics state of the terminal.
+#[derive(Debug)]
+pub struct Graphics {
+    /// Identifier for the next graphic.
+    next_id: u64,
+
+    /// Graphics which were not uploaded yet.
+    pending: Vec<GraphicData>,
+
+    /// Graphics which are no longer referenced.
+    removed: Arc<Mutex<Vec<GraphicId>>>,
+
+    /// Cell width in pixels.
+    cell_width: usize,
+
+    /// Cell height in pixels.
+    cell_height: usize,
+
+    /// Sixel graphics with their size in bytes, oldest first.
+    sixels: VecDeque<(GraphicId, usize)>,
+
+    /// Maximum number of bytes used by all sixel graphics.
+    sixel_budget: usize,
+}
+
+impl Default for Graphics {
+    fn default() -> Self {
+        Self {
+            sixel_budget: sixel::STORAGE_BUDGET,
+            next_id: Default::default(),
+            pending: Default::default(),
+            removed: Default::default(),
+            cell_width: Default::default(),
+            cell_height: Default::default(),
+            sixels: Default::default(),
+        }
+    }
+}
+
+impl Graphics {
+    /// Update the size of a cell in pixels.
+    ///
+    /// Graphics can only be placed once the cell size is known.
+    #[inline]
+    pub fn set_cell_size(&mut self, width: usize, height: usize) {
+        self.cell_width = width;
+        self.cell_height = height;
+    }
+
+    /// Cell width and height in pixels.
+    #[inline]
+    pub fn cell_size(&self) -> (usize, usize) {
+        (self.cell_width, self.cell_height)
+    }
+
+    /// Retrieve all changes since the last call.
+    pub fn take_updates(&mut self) -> GraphicUpdates {
+        let removed = mem::take(&mut *self.removed.lock());
+        GraphicUpdates { added: mem::take(&mut self.pending), removed }
+    }
+
+    /// Store a new graphic.
+    pub(crate) fn insert(&mut self, image: Arc<Image>) -> Arc<GraphicRef> {
+        let id = GraphicId(self.next_id);
+        self.next_id += 1;
+
+        self.pending.push(GraphicData { id, image });
+
+        Arc::new(GraphicRef { id, removed: self.removed.clone() })
+    }
+
+    /// Store a new sixel graphic.
+    ///
+    /// Once the sixel graphics exceed their budget, the oldest ones are evicted and no longer
+    /// displayed by the cells which still reference them.
+    pub(crate) fn insert_sixel(&mut self, image: Arc<Image>) -> Arc<GraphicRef> {
+        let size = image.pixels.len();
+        let graphic = self.insert(image);
+
+        // Forget about graphics which are no longer referenced by any cell.
+        let removed = self.removed.lock();
+        self.sixels.retain(|(id, _)| !removed.contains(id));
+        drop(removed);
+
+        self.sixels.push_back((graphic.id, size));
+
+        let mut used: usize = self.sixels.iter().map(|(_, size)| size).sum();
+        while used > self.sixel_budget && self.sixels.len() > 1 {
+            let (id, size) = self.sixels.pop_front().unwrap();
+            used -= size;
+
+            self.pending.retain(|data| data.id != id);
+            self.removed.lock().push(id);
+        }
+
+        graphic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sixel_budget() {
+        let mut graphics = Graphics { sixel_budget: 64, ..Default::default() };
+        let image =
+            |size: usize| Arc::new(Image { width: size / 4, height: 1, pixels: vec![0; size] });
+
+        let first = graphics.insert_sixel(image(32));
+        let second = graphics.insert_sixel(image(32));
+        assert_eq!(graphics.take_updates().added.len(), 2);
+
+        // Graphics which were cleared from the grid don't count towards the budget.
+        drop(first);
+        let third = graphics.insert_sixel(image(32));
+        assert_eq!(graphics.take_updates().removed.len(), 1);
+
+        // The oldest graphic is evicted, even while it is still referenced.
+        let fourth = graphics.insert_sixel(image(16));
+        let updates = graphics.take_updates();
+        assert_eq!(updates.added.iter().map(|data| data.id).collect::<Vec<_>>(), vec![fourth.id]);
+        assert_eq!(updates.removed, vec![second.id]);
+
+        // A single graphic is kept even if it exceeds the budget.
+        let fifth = graphics.insert_sixel(image(128));
+        let updates = graphics.take_updates();
+        assert_eq!(updates.added.len(), 1);
+        assert_eq!(updates.removed, vec![third.id, fourth.id]);
+        drop(fifth);
+    }
+}
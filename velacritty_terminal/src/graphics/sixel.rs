@@ -0,0 +1,360 @@
+//! Decoder for the sixel graphics format.
+//!
+//! Sixel images are transmitted as the payload of a `DCS P1 ; P2 ; P3 q ... ST` sequence. Every
+//! data character encodes a column of six vertical pixels, which are drawn with the active color
+//! register.
+
+use crate::graphics::Image;
+
+/// Maximum width and height of a sixel image in pixels.
+pub const MAX_SIZE: usize = 4096;
+
+/// Maximum number of bytes used by the sixel graphics of a single terminal.
+pub const STORAGE_BUDGET: usize = 320 * 1024 * 1024;
+
+/// Number of available color registers.
+const COLOR_REGISTERS: usize = 1024;
+
+/// Default color registers of the VT340, in RGB percentages.
+const VT340_PALETTE: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [20, 20, 80],
+    [80, 13, 13],
+    [20, 80, 20],
+    [80, 20, 80],
+    [20, 80, 80],
+    [80, 80, 20],
+    [53, 53, 53],
+    [26, 26, 26],
+    [33, 33, 60],
+    [60, 26, 26],
+    [33, 60, 33],
+    [60, 33, 60],
+    [33, 60, 60],
+    [60, 60, 33],
+    [80, 80, 80],
+];
+
+/// RGBA color of a single pixel.
+type Rgba = [u8; 4];
+
+/// Control function with numeric parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Command {
+    /// Graphics repeat introducer (`!`).
+    Repeat,
+    /// Raster attributes (`"`).
+    RasterAttributes,
+    /// Color introducer (`#`).
+    Color,
+}
+
+/// Incremental sixel decoder.
+#[derive(Debug)]
+pub struct Parser {
+    /// Control function whose parameters are currently being parsed.
+    command: Option<Command>,
+
+    /// Parameters of the current control function.
+    params: Vec<u16>,
+
+    /// Color registers.
+    palette: Vec<Rgba>,
+
+    /// Index of the active color register.
+    color: usize,
+
+    /// Number of times the next sixel is repeated.
+    repeat: usize,
+
+    /// Whether pixels which have not been drawn remain transparent.
+    transparent: bool,
+
+    /// Image size declared by the raster attributes.
+    raster_size: Option<(usize, usize)>,
+
+    /// Column of the next sixel.
+    x: usize,
+
+    /// Top pixel row of the current sixel band.
+    y: usize,
+
+    /// Drawn pixel rows.
+    rows: Vec<Vec<Rgba>>,
+}
+
+impl Parser {
+    /// Create a new decoder from the parameters of the DCS sequence.
+    pub fn new(params: &[u16]) -> Self {
+        let mut palette = vec![[0, 0, 0, 255]; COLOR_REGISTERS];
+        for (register, [r, g, b]) in palette.iter_mut().zip(VT340_PALETTE) {
+            *register = [percent(r.into()), percent(g.into()), percent(b.into()), 255];
+        }
+
+        // P2 selects how pixels with a value of zero are drawn.
+        let transparent = params.get(1) == Some(&1);
+
+        Self {
+            transparent,
+            palette,
+            command: Default::default(),
+            params: Default::default(),
+            color: Default::default(),
+            repeat: 1,
+            raster_size: Default::default(),
+            x: Default::default(),
+            y: Default::default(),
+            rows: Default::default(),
+        }
+    }
+
+    /// Process the next byte of the sixel data.
+    pub fn put(&mut self, byte: u8) {
+        if self.command.is_some() {
+            match byte {
+                b'0'..=b'9' => {
+                    let param = self.params.last_mut().unwrap();
+                    *param = param.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                    return;
+                },
+                b';' => {
+                    self.params.push(0);
+                    return;
+                },
+                _ => self.finish_command(),
+            }
+        }
+
+        match byte {
+            b'?'..=b'~' => self.draw_sixel(byte - b'?'),
+            b'!' => self.start_command(Command::Repeat),
+            b'"' => self.start_command(Command::RasterAttributes),
+            b'#' => self.start_command(Command::Color),
+            b'$' => self.x = 0,
+            b'-' => {
+                self.x = 0;
+                self.y = self.y.saturating_add(6);
+            },
+            _ => (),
+        }
+    }
+
+    /// Finish decoding and retrieve the image.
+    ///
+    /// Returns `None` if the image is empty.
+    pub fn finish(mut self) -> Option<Image> {
+        if self.command.is_some() {
+            self.finish_command();
+        }
+
+        let drawn_width = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let (raster_width, raster_height) = self.raster_size.unwrap_or_default();
+        let width = drawn_width.max(raster_width).min(MAX_SIZE);
+        let height = self.rows.len().max(raster_height).min(MAX_SIZE);
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let background = if self.transparent { [0; 4] } else { self.palette[0] };
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let row = self.rows.get(y).map(Vec::as_slice).unwrap_or_default();
+            for x in 0..width {
+                let pixel = match row.get(x) {
+                    Some(pixel) if pixel[3] != 0 => *pixel,
+                    _ if x < raster_width && y < raster_height => background,
+                    _ => [0; 4],
+                };
+                pixels.extend_from_slice(&pixel);
+            }
+        }
+
+        Some(Image { width, height, pixels })
+    }
+
+    fn start_command(&mut self, command: Command) {
+        self.command = Some(command);
+        self.params.clear();
+        self.params.push(0);
+    }
+
+    fn finish_command(&mut self) {
+        let param = |index: usize| self.params.get(index).copied().unwrap_or(0) as usize;
+
+        match self.command.take() {
+            Some(Command::Repeat) => self.repeat = param(0).max(1),
+            Some(Command::RasterAttributes) => {
+                // Pixel aspect ratio is ignored, pixels are always square.
+                let size = (param(2).min(MAX_SIZE), param(3).min(MAX_SIZE));
+                self.raster_size = Some(size);
+            },
+            Some(Command::Color) => {
+                let register = param(0) % COLOR_REGISTERS;
+                self.color = register;
+
+                let color = match param(1) {
+                    1 => hls_to_rgb(param(2), param(3), param(4)),
+                    2 => [percent(param(2)), percent(param(3)), percent(param(4))],
+                    _ => return,
+                };
+                self.palette[register] = [color[0], color[1], color[2], 255];
+            },
+            None => (),
+        }
+    }
+
+    /// Draw one sixel at the current position.
+    fn draw_sixel(&mut self, sixel: u8) {
+        let repeat = std::mem::replace(&mut self.repeat, 1);
+        let start = self.x;
+        self.x = self.x.saturating_add(repeat);
+
+        let end = self.x.min(MAX_SIZE);
+        if start >= end || self.y >= MAX_SIZE {
+            return;
+        }
+
+        let color = self.palette[self.color];
+        for bit in 0..6 {
+            if sixel & (1 << bit) == 0 {
+                continue;
+            }
+
+            let y = self.y + bit;
+            if y >= MAX_SIZE {
+                break;
+            }
+
+            if self.rows.len() <= y {
+                self.rows.resize_with(y + 1, Vec::new);
+            }
+
+            let row = &mut self.rows[y];
+            if row.len() < end {
+                row.resize(end, [0; 4]);
+            }
+            row[start..end].fill(color);
+        }
+    }
+}
+
+/// Convert a color percentage to an 8 bit color channel.
+fn percent(value: usize) -> u8 {
+    (value.min(100) * 255 / 100) as u8
+}
+
+/// Convert a DEC HLS color to RGB.
+///
+/// In contrast to the common HSL model, a hue of 0° is blue instead of red.
+fn hls_to_rgb(hue: usize, lightness: usize, saturation: usize) -> [u8; 3] {
+    let hue = ((hue as f32 + 240.) % 360.) / 360.;
+    let lightness = lightness.min(100) as f32 / 100.;
+    let saturation = saturation.min(100) as f32 / 100.;
+
+    if saturation == 0. {
+        let value = (lightness * 255.).round() as u8;
+        return [value; 3];
+    }
+
+    let q = if lightness < 0.5 {
+        lightness * (1. + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2. * lightness - q;
+
+    let channel = |mut t: f32| {
+        t = t.rem_euclid(1.);
+        let value = if t < 1. / 6. {
+            p + (q - p) * 6. * t
+        } else if t < 1. / 2. {
+            q
+        } else if t < 2. / 3. {
+            p + (q - p) * (2. / 3. - t) * 6.
+        } else {
+            p
+        };
+        (value * 255.).round() as u8
+    };
+
+    [channel(hue + 1. / 3.), channel(hue), channel(hue - 1. / 3.)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(params: &[u16], data: &[u8]) -> Option<Image> {
+        let mut parser = Parser::new(params);
+        for byte in data {
+            parser.put(*byte);
+        }
+        parser.finish()
+    }
+
+    fn pixel(image: &Image, x: usize, y: usize) -> [u8; 4] {
+        let start = (y * image.width + x) * 4;
+        image.pixels[start..start + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn single_column() {
+        let image = decode(&[0, 1], b"#0;2;100;0;0~").unwrap();
+
+        assert_eq!((image.width, image.height), (1, 6));
+        for y in 0..6 {
+            assert_eq!(pixel(&image, 0, y), [255, 0, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn repeat_and_bands() {
+        let image = decode(&[0, 1], b"#1;2;0;100;0!3@-#2;2;0;0;100?A").unwrap();
+
+        assert_eq!((image.width, image.height), (3, 8));
+        assert_eq!(pixel(&image, 2, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(&image, 0, 1), [0; 4]);
+        assert_eq!(pixel(&image, 0, 6), [0; 4]);
+        assert_eq!(pixel(&image, 1, 7), [0, 0, 255, 255]);
+        assert_eq!(pixel(&image, 2, 7), [0; 4]);
+    }
+
+    #[test]
+    fn carriage_return_overlays() {
+        let image = decode(&[0, 1], b"#1;2;100;100;100@$#2;2;0;0;100A").unwrap();
+
+        assert_eq!((image.width, image.height), (1, 2));
+        assert_eq!(pixel(&image, 0, 0), [255, 255, 255, 255]);
+        assert_eq!(pixel(&image, 0, 1), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn raster_attributes_fill_background() {
+        let image = decode(&[0, 0], b"\"1;1;4;2#0;2;0;0;100#1;2;100;0;0@").unwrap();
+
+        assert_eq!((image.width, image.height), (4, 2));
+        assert_eq!(pixel(&image, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 3, 1), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn hls_colors() {
+        assert_eq!(hls_to_rgb(0, 50, 100), [0, 0, 255]);
+        assert_eq!(hls_to_rgb(120, 50, 100), [255, 0, 0]);
+        assert_eq!(hls_to_rgb(240, 50, 100), [0, 255, 0]);
+        assert_eq!(hls_to_rgb(0, 100, 0), [255, 255, 255]);
+    }
+
+    #[test]
+    fn empty_image() {
+        assert_eq!(decode(&[], b"#1;2;100;100;100"), None);
+    }
+
+    #[test]
+    fn size_is_capped() {
+        let image = decode(&[0, 1], b"!99999~").unwrap();
+
+        assert_eq!((image.width, image.height), (MAX_SIZE, 6));
+    }
+}
@@ -0,0 +1,198 @@
+//! Compact storage for rows which have scrolled far into the history.
+//!
+//! Most rows only use a handful of different attributes, so instead of storing every cell, a
+//! compressed row stores its text as UTF-8 alongside runs of cells sharing the same attributes.
+//! Trailing cells which are equal to the default cell are not stored at all.
+
+use std::mem;
+use std::sync::OnceLock;
+
+use crate::grid::Row;
+
+/// Cell which can be stored in a compressed row.
+pub trait CompressibleCell: Clone + Default + PartialEq {
+    /// Character stored in the cell.
+    fn character(&self) -> char;
+
+    /// Replace the character stored in the cell.
+    fn set_character(&mut self, c: char);
+}
+
+/// Cells sharing the same attributes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Run {
+    /// Number of cells in the run.
+    len: u16,
+
+    /// Index of the cell attributes in the row's templates.
+    template: u16,
+}
+
+/// Compressed cells of a row.
+#[derive(Debug, Clone)]
+pub(crate) struct CompressedRow<T> {
+    /// Characters of all stored cells.
+    text: Box<str>,
+
+    /// Attribute runs covering all stored cells.
+    runs: Box<[Run]>,
+
+    /// Distinct cell attributes, with the character replaced by a space.
+    templates: Box<[T]>,
+
+    /// Total number of cells in the row.
+    columns: usize,
+
+    /// Decoder for the cells.
+    ///
+    /// This is stored with the row, so decompression is possible without any trait bounds.
+    decompress: fn(&Self) -> Vec<T>,
+
+    /// Decompressed row, for read-only access.
+    cache: OnceLock<Box<Row<T>>>,
+}
+
+impl<T: CompressibleCell> CompressedRow<T> {
+    /// Compress a row's cells.
+    ///
+    /// Returns `None` if the cells cannot be represented in compressed form.
+    pub fn new(cells: &[T]) -> Option<Self> {
+        if cells.len() > u16::MAX as usize {
+            return None;
+        }
+
+        let default = T::default();
+        let stored = cells.iter().rposition(|cell| *cell != default).map_or(0, |i| i + 1);
+
+        let mut text = String::with_capacity(stored);
+        let mut runs: Vec<Run> = Vec::new();
+        let mut templates: Vec<T> = Vec::new();
+
+        for cell in &cells[..stored] {
+            text.push(cell.character());
+
+            let mut template = cell.clone();
+            template.set_character(' ');
+
+            match runs.last_mut() {
+                Some(run) if templates[run.template as usize] == template => run.len += 1,
+                _ => {
+                    let index = match templates.iter().position(|known| *known == template) {
+                        Some(index) => index,
+                        None => {
+                            templates.push(template);
+                            templates.len() - 1
+                        },
+                    };
+                    runs.push(Run { len: 1, template: index as u16 });
+                },
+            }
+        }
+
+        Some(Self {
+            text: text.into_boxed_str(),
+            runs: runs.into_boxed_slice(),
+            templates: templates.into_boxed_slice(),
+            columns: cells.len(),
+            decompress: Self::decompress_cells,
+            cache: OnceLock::new(),
+        })
+    }
+
+    fn decompress_cells(&self) -> Vec<T> {
+        let mut cells = Vec::with_capacity(self.columns);
+
+        let mut chars = self.text.chars();
+        for run in self.runs.iter() {
+            let template = &self.templates[run.template as usize];
+            for c in chars.by_ref().take(run.len as usize) {
+                let mut cell = template.clone();
+                cell.set_character(c);
+                cells.push(cell);
+            }
+        }
+
+        cells.resize_with(self.columns, T::default);
+        cells
+    }
+}
+
+impl<T> CompressedRow<T> {
+    /// Decompress the cells.
+    #[inline]
+    pub fn cells(&self) -> Vec<T> {
+        (self.decompress)(self)
+    }
+
+    /// Decompressed row, created by `init` on first access.
+    #[inline]
+    pub fn row(&self, init: impl FnOnce() -> Row<T>) -> &Row<T> {
+        self.cache.get_or_init(|| Box::new(init()))
+    }
+
+    /// Check if the decompressed row is cached.
+    #[inline]
+    pub fn is_cached(&self) -> bool {
+        self.cache.get().is_some()
+    }
+
+    /// Take the decompressed row out of the cache.
+    #[inline]
+    pub fn take_cache(&mut self) -> Option<Box<Row<T>>> {
+        self.cache.take()
+    }
+
+    /// Approximate number of heap bytes used by the row.
+    pub fn heap_size(&self) -> usize {
+        let cache = self
+            .cache
+            .get()
+            .map_or(0, |row| mem::size_of::<Row<T>>() + row.len() * mem::size_of::<T>());
+
+        mem::size_of::<Self>()
+            + self.text.len()
+            + self.runs.len() * mem::size_of::<Run>()
+            + self.templates.len() * mem::size_of::<T>()
+            + cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::term::cell::{Cell, Flags};
+    use crate::vte::ansi::{Color, NamedColor};
+
+    #[test]
+    fn roundtrip() {
+        let mut cells = vec![Cell::default(); 10];
+        for (cell, c) in cells.iter_mut().zip("ab😀 cd".chars()) {
+            cell.c = c;
+        }
+        cells[1].fg = Color::Named(NamedColor::Red);
+        cells[2].flags = Flags::WIDE_CHAR;
+        cells[3].flags = Flags::WIDE_CHAR_SPACER;
+        cells[4].push_zerowidth('\u{301}');
+        cells[5].bg = Color::Indexed(3);
+        cells[6].bg = Color::Indexed(3);
+
+        let compressed = CompressedRow::new(&cells).unwrap();
+
+        assert_eq!(&*compressed.text, "ab😀 cd ");
+        assert_eq!(compressed.runs.len(), 6);
+        assert_eq!(compressed.templates.len(), 6);
+        assert_eq!(compressed.cells(), cells);
+    }
+
+    #[test]
+    fn blank_row() {
+        let cells = vec![Cell::default(); 80];
+
+        let compressed = CompressedRow::new(&cells).unwrap();
+
+        assert!(compressed.text.is_empty());
+        assert!(compressed.runs.is_empty());
+        assert_eq!(compressed.cells(), cells);
+    }
+}
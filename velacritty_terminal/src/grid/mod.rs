@@ -2,23 +2,42 @@
 
 use std::cmp::{max, min};
 use std::ops::{Bound, Deref, Index, IndexMut, Range, RangeBounds};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::error;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use crate::index::{Column, Line, Point};
 use crate::term::cell::{Flags, ResetDiscriminant};
+use crate::term::shell_integration::SemanticMarkKind;
 use crate::vte::ansi::{CharsetIndex, StandardCharset};
 
+mod compression;
 pub mod resize;
 mod row;
+mod spill;
 mod storage;
 #[cfg(test)]
 mod tests;
 
-pub use self::row::Row;
+pub use self::compression::CompressibleCell;
+pub use self::row::{LineAttribute, Row};
+use self::spill::Spill;
+pub use self::spill::{SpillCell, SpillMode, SpillReader, SpillWriter};
+pub use self::storage::MemoryUsage;
 use self::storage::Storage;
 
+/// Number of history lines directly above the screen which are never compressed.
+const UNCOMPRESSED_HISTORY: usize = 1_000;
+
+/// Number of history lines checked for decompressed rows each time the history is compressed.
+const COMPRESSION_BATCH_SIZE: usize = 32;
+
+/// Number of lines read from the spill file when scrolling past the top of the history.
+const SPILL_PAGE_SIZE: usize = 1_000;
+
 pub trait GridCell: Sized {
     /// Check if the cell contains any content.
     fn is_empty(&self) -> bool;
@@ -140,6 +159,27 @@ pub struct Grid<T> {
     /// When false, viewport stays locked even when at bottom (pure manual control).
     #[cfg_attr(feature = "serde", serde(skip))]
     auto_scroll_enabled: bool,
+
+    /// Lines which scrolled in below the viewport while it was not following the output.
+    ///
+    /// This can exceed the display offset, see [`Self::unread_lines`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    unread_lines: usize,
+
+    /// Next history line checked for rows which need to be compressed again.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    compression_cursor: Line,
+
+    /// Number of compressed rows which were decompressed by indexing since the last release.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    decompressed_rows: DecompressedRows,
+
+    /// Lines which have moved past the history limit.
+    ///
+    /// Lines above the history limit which are still in memory have been paged back in from this
+    /// spill file.
+    #[cfg_attr(feature = "serde", serde(skip, default = "Option::default"))]
+    spill: Option<Spill<T>>,
 }
 
 impl<T: GridCell + Default + PartialEq> Grid<T> {
@@ -151,6 +191,10 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             saved_cursor: Cursor::default(),
             cursor: Cursor::default(),
             auto_scroll_enabled: true,
+            unread_lines: 0,
+            compression_cursor: Line(0),
+            decompressed_rows: Default::default(),
+            spill: None,
             lines,
             columns,
         }
@@ -167,6 +211,21 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
     }
 
     pub fn scroll_display(&mut self, scroll: Scroll) {
+        // Page lines back in from the spill file when scrolling past the top of the history.
+        //
+        // At most one page is read per scroll, to avoid loading an unlimited history at once.
+        let target_offset = match scroll {
+            Scroll::Delta(count) if count > 0 => self.display_offset + count as usize,
+            Scroll::PageUp => self.display_offset + self.lines,
+            Scroll::Top => usize::MAX,
+            _ => 0,
+        };
+        if target_offset > self.history_size() {
+            let max_page = max(self.lines, SPILL_PAGE_SIZE);
+            self.page_in(min(target_offset - self.history_size(), max_page));
+        }
+
+        let unread_lines = self.unread_lines();
         self.display_offset = match scroll {
             Scroll::Delta(count) => {
                 min(max((self.display_offset as i32) + count, 0) as usize, self.history_size())
@@ -176,10 +235,91 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             Scroll::Top => self.history_size(),
             Scroll::Bottom => 0,
         };
+
+        // Lines scrolled into view have been read.
+        self.unread_lines = min(unread_lines, self.display_offset);
+
+        // Release lines paged in from the spill file once the viewport is back at the bottom.
+        if self.display_offset == 0 {
+            self.page_out();
+        }
+    }
+
+    /// Read up to `count` lines above the top of the history from the spill file.
+    ///
+    /// Returns the number of lines added to the history.
+    pub fn page_in(&mut self, count: usize) -> usize {
+        let spill = match &self.spill {
+            Some(spill) => spill,
+            None => return 0,
+        };
+
+        let end = spill.len().saturating_sub(self.paged_lines());
+        let start = end.saturating_sub(max(count, SPILL_PAGE_SIZE));
+        if start == end {
+            return 0;
+        }
+
+        let rows = match spill.read(start..end, self.columns) {
+            Ok(rows) => rows,
+            Err(err) => {
+                error!("Unable to read scrollback history from disk: {err}");
+                return 0;
+            },
+        };
+
+        let count = rows.len();
+        self.raw.initialize(count, self.columns);
+
+        let topmost_line = self.topmost_line();
+        for (i, row) in rows.into_iter().enumerate() {
+            self.raw[topmost_line + i] = row;
+        }
+
+        count
+    }
+
+    /// Insert lines above the top of the history, oldest line first.
+    ///
+    /// Lines are cut off or padded to the current number of columns. Lines which do not fit into
+    /// the history are written to the spill file if it is still empty, otherwise they are dropped.
+    pub fn prepend_history(&mut self, rows: Vec<Row<T>>) {
+        self.page_out();
+
+        let count = min(rows.len(), self.max_scroll_limit.saturating_sub(self.history_size()));
+        let overflow = rows.len() - count;
+        let mut rows = rows.into_iter().map(|mut row| {
+            if row.shrink(self.columns).is_some()
+                && row[Column(self.columns - 1)].flags().contains(Flags::WIDE_CHAR)
+            {
+                row[Column(self.columns - 1)] = T::default();
+            }
+            row.grow(self.columns);
+            row
+        });
+
+        // Older lines can only be added to the spill file before anything else was written to it.
+        let spill_overflow = self.spilled_lines() == 0;
+        for row in rows.by_ref().take(overflow) {
+            if spill_overflow {
+                Self::spill_row(&mut self.spill, &row);
+            }
+        }
+
+        if count == 0 {
+            return;
+        }
+
+        self.raw.initialize(count, self.columns);
+
+        let topmost_line = self.topmost_line();
+        for (i, row) in rows.enumerate() {
+            self.raw[topmost_line + i] = row;
+        }
     }
 
     fn increase_scroll_limit(&mut self, count: usize) {
-        let count = min(count, self.max_scroll_limit - self.history_size());
+        let count = min(count, self.max_scroll_limit.saturating_sub(self.history_size()));
         if count != 0 {
             self.raw.initialize(count, self.columns);
         }
@@ -269,14 +409,34 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             return;
         }
 
+        // Release lines paged in from the spill file once the viewport is at the bottom.
+        if region.start == 0 && self.display_offset == 0 {
+            self.page_out();
+        }
+
+        // Keep lines paged in from the spill file while they might still be in view, but drop the
+        // oldest ones as new lines are written to the spill file, so the history does not grow.
+        let scroll_limit = max(self.max_scroll_limit, self.history_size());
+
         // Update display offset when not pinned to active area.
         // When auto_scroll is disabled, ALWAYS update offset (even at bottom).
         if self.display_offset != 0 || !self.auto_scroll_enabled {
-            self.display_offset = min(self.display_offset + positions, self.max_scroll_limit);
+            self.unread_lines = self.unread_lines() + positions;
+            self.display_offset = min(self.display_offset + positions, scroll_limit);
         }
 
         // Only rotate the entire history if the active region starts at the top.
         if region.start == 0 {
+            self.spill_lines(positions);
+
+            // Clear lines rotated out of the history, releasing resources like graphics right away
+            // instead of once the line is reused.
+            let evicted = (self.history_size() + positions).saturating_sub(scroll_limit);
+            let topmost_line = self.topmost_line();
+            for i in 0..min(evicted, self.history_size()) {
+                self.raw[topmost_line + i].reset(&self.cursor.template);
+            }
+
             // Create scrollback for the new lines.
             self.increase_scroll_limit(positions);
 
@@ -393,6 +553,88 @@ impl<T> Grid<T> {
 
         // Reset display offset.
         self.display_offset = 0;
+
+        if let Some(Err(err)) = self.spill.as_mut().map(|spill| spill.clear()) {
+            error!("Unable to clear scrollback history on disk: {err}");
+            self.spill = None;
+        }
+    }
+
+    /// Release the cells of compressed rows which were cached by read-only access.
+    ///
+    /// This is cheap unless rows were decompressed since the last release.
+    pub fn release_decompressed(&mut self) {
+        let decompressed_rows = self.decompressed_rows.0.get_mut();
+        if *decompressed_rows != 0 {
+            *decompressed_rows = 0;
+            self.raw.release_decompressed();
+        }
+    }
+
+    /// Lines which might hold kitty graphics placements, from the top of the history.
+    ///
+    /// In contrast to indexing, this does not require decompressing any rows.
+    pub(crate) fn placement_lines(&self) -> Vec<Line> {
+        (self.topmost_line().0..self.screen_lines() as i32)
+            .map(Line)
+            .filter(|&line| self.raw.has_placements(line))
+            .collect()
+    }
+
+    /// Mark whether a row might hold kitty graphics placements, without decompressing it.
+    #[inline]
+    pub(crate) fn set_placements(&mut self, line: Line, placements: bool) {
+        self.raw.set_placements(line, placements);
+    }
+
+    /// Access a row without caching its decompressed cells.
+    ///
+    /// This should be preferred over indexing for passes over the entire history.
+    #[inline]
+    pub fn with_row<R>(&self, line: Line, f: impl FnOnce(&Row<T>) -> R) -> R {
+        self.raw.with_row(line, f)
+    }
+
+    /// Number of lines in the spill file.
+    #[inline]
+    pub fn spilled_lines(&self) -> usize {
+        self.spill.as_ref().map_or(0, |spill| spill.len())
+    }
+
+    /// Number of lines above the history limit, which were paged in from the spill file.
+    #[inline]
+    fn paged_lines(&self) -> usize {
+        self.history_size().saturating_sub(self.max_scroll_limit)
+    }
+
+    /// Remove all lines paged in from the spill file.
+    pub fn page_out(&mut self) {
+        let paged_lines = self.paged_lines();
+        if paged_lines != 0 {
+            self.raw.shrink_lines(paged_lines);
+            self.display_offset = min(self.display_offset, self.history_size());
+        }
+    }
+
+    /// Write lines which are about to move past the history limit to the spill file.
+    fn spill_lines(&mut self, positions: usize) {
+        if self.spill.is_none() {
+            return;
+        }
+
+        let limit = Line(-(self.max_scroll_limit as i32));
+        let start = max(limit, self.topmost_line());
+        for line in (start.0..(limit + positions).0).map(Line::from) {
+            Self::spill_row(&mut self.spill, &self.raw[line]);
+        }
+    }
+
+    /// Append a row to the spill file, disabling the spill file if writing fails.
+    fn spill_row(spill: &mut Option<Spill<T>>, row: &Row<T>) {
+        if let Some(Err(err)) = spill.as_mut().map(|spill| spill.push(row)) {
+            error!("Unable to write scrollback history to disk: {err}");
+            *spill = None;
+        }
     }
 
     /// This is used only for initializing after loading ref-tests.
@@ -435,6 +677,36 @@ impl<T> Grid<T> {
         GridIterator { grid: self, point: start, end }
     }
 
+    /// Iterate over all shell integration marks within a range of lines.
+    ///
+    /// Lines outside of the grid are ignored.
+    pub fn semantic_marks<R: RangeBounds<Line>>(
+        &self,
+        lines: R,
+    ) -> impl DoubleEndedIterator<Item = (Point, SemanticMarkKind)> + '_ {
+        let start = match lines.start_bound() {
+            Bound::Included(line) => *line,
+            Bound::Excluded(line) => *line + 1,
+            Bound::Unbounded => self.topmost_line(),
+        };
+
+        let end = match lines.end_bound() {
+            Bound::Included(line) => *line,
+            Bound::Excluded(line) => *line - 1,
+            Bound::Unbounded => self.bottommost_line(),
+        };
+
+        let start = max(start, self.topmost_line());
+        let end = min(end, self.bottommost_line());
+
+        (start.0..=end.0).map(Line).flat_map(move |line| {
+            self.raw
+                .semantic_marks(line)
+                .iter()
+                .map(move |mark| (Point::new(line, mark.column), mark.kind))
+        })
+    }
+
     #[inline]
     pub fn display_offset(&self) -> usize {
         self.display_offset
@@ -450,6 +722,13 @@ impl<T> Grid<T> {
         self.auto_scroll_enabled = enabled;
     }
 
+    /// Number of lines at the bottom which arrived while the viewport was not following the
+    /// output and haven't been scrolled into view since.
+    #[inline]
+    pub fn unread_lines(&self) -> usize {
+        min(self.unread_lines, self.display_offset)
+    }
+
     #[inline]
     pub fn cursor_cell(&mut self) -> &mut T {
         let point = self.cursor.point;
@@ -457,6 +736,82 @@ impl<T> Grid<T> {
     }
 }
 
+impl<T: CompressibleCell> Grid<T> {
+    /// Compress history rows which have scrolled far out of view.
+    ///
+    /// This should be called after moving `positions` lines into the history. Rows which were
+    /// modified since they were first compressed are compressed again incrementally, while the
+    /// cells cached by read-only access are released right away.
+    pub fn compress_history(&mut self, positions: usize) {
+        self.release_decompressed();
+
+        if self.history_size() <= UNCOMPRESSED_HISTORY {
+            return;
+        }
+
+        let boundary = Line(-(UNCOMPRESSED_HISTORY as i32) - 1);
+        let topmost_line = self.topmost_line();
+
+        // Compress the rows which just crossed the boundary.
+        let start = max(boundary - positions + 1, topmost_line);
+        for line in (start.0..=boundary.0).map(Line::from) {
+            self.raw.compress(line);
+        }
+
+        // Walk through the remaining history, to release memory used by decompressed rows.
+        for _ in 0..COMPRESSION_BATCH_SIZE {
+            if self.compression_cursor > boundary || self.compression_cursor < topmost_line {
+                self.compression_cursor = boundary;
+            }
+
+            self.raw.compress(self.compression_cursor);
+            self.compression_cursor -= 1;
+        }
+    }
+}
+
+impl<T: SpillCell> Grid<T> {
+    /// Change where lines moving past the history limit are stored.
+    ///
+    /// Lines already written to disk are discarded when the mode changes.
+    pub fn set_spill_mode(&mut self, mode: SpillMode) {
+        if self.spill.as_ref().map_or(SpillMode::Disabled, |spill| spill.mode()) == mode {
+            return;
+        }
+
+        self.page_out();
+
+        self.spill = match mode {
+            SpillMode::Disabled => None,
+            _ => match Spill::new(mode) {
+                Ok(spill) => Some(spill),
+                Err(err) => {
+                    error!("Unable to create file for scrollback history: {err}");
+                    None
+                },
+            },
+        };
+    }
+}
+
+impl<T> Grid<T> {
+    /// Approximate memory used by the grid's rows.
+    #[inline]
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage { spilled_rows: self.spilled_lines(), ..self.raw.memory_usage() }
+    }
+}
+
+/// Counter for rows decompressed through shared references.
+#[derive(Debug, Default)]
+struct DecompressedRows(AtomicUsize);
+
+impl Clone for DecompressedRows {
+    fn clone(&self) -> Self {
+        Self(AtomicUsize::new(self.0.load(Ordering::Relaxed)))
+    }
+}
+
 impl<T: PartialEq> PartialEq for Grid<T> {
     fn eq(&self, other: &Self) -> bool {
         // Compare struct fields and check result of grid comparison.
@@ -472,6 +827,9 @@ impl<T> Index<Line> for Grid<T> {
 
     #[inline]
     fn index(&self, index: Line) -> &Row<T> {
+        if self.raw.needs_decompression(index) {
+            self.decompressed_rows.0.fetch_add(1, Ordering::Relaxed);
+        }
         &self.raw[index]
     }
 }
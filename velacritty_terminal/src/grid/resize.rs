@@ -16,6 +16,9 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
         T: ResetDiscriminant<D>,
         D: PartialEq,
     {
+        // Lines paged in from the spill file are not reflowed, so they're dropped before resizing.
+        self.page_out();
+
         // Use empty template cell for resetting cells due to resize.
         let template = mem::take(&mut self.cursor.template);
 
@@ -147,10 +150,12 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
             let len = min(row.len(), num_wrapped);
 
             // Insert leading spacer when there's not enough room for reflowing wide char.
+            let marks;
             let mut cells = if row[Column(len - 1)].flags().contains(Flags::WIDE_CHAR) {
                 num_wrapped -= 1;
 
                 let mut cells = row.front_split_off(len - 1);
+                marks = row.front_split_semantic_marks(len - 1);
 
                 let mut spacer = T::default();
                 spacer.flags_mut().insert(Flags::LEADING_WIDE_CHAR_SPACER);
@@ -158,11 +163,16 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
 
                 cells
             } else {
+                marks = row.front_split_semantic_marks(len);
                 row.front_split_off(len)
             };
 
             // Add removed cells to previous row and reflow content.
             last_row.append(&mut cells);
+            last_row.append_semantic_marks(marks, last_len);
+            if row.has_placements() {
+                last_row.set_placements(true);
+            }
 
             let cursor_buffer_line = self.lines - self.cursor.point.line.0 as usize - 1;
 
@@ -182,6 +192,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                 let line_delta = self.cursor.point.line - target.line;
 
                 if line_delta != 0 && row.is_clear() {
+                    last_row.append_semantic_marks(row.split_semantic_marks(0), columns);
                     continue;
                 }
 
@@ -197,6 +208,9 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                     self.cursor.point.line += 1;
                 }
 
+                // Keep marks of the removed line, since it's part of the previous one.
+                last_row.append_semantic_marks(row.split_semantic_marks(0), columns);
+
                 // Don't push line into the new buffer.
                 continue;
             }
@@ -253,6 +267,8 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
 
         let mut new_raw = Vec::with_capacity(self.raw.len());
         let mut buffered: Option<Vec<T>> = None;
+        let mut buffered_marks = Vec::new();
+        let mut buffered_placements = false;
 
         let mut rows = self.raw.take_all();
         for (i, mut row) in rows.drain(..).enumerate().rev() {
@@ -265,10 +281,18 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                     self.cursor.point.column += buffered.len();
                 }
 
+                let buffered_len = buffered.len();
                 row.append_front(buffered);
+                row.prepend_semantic_marks(mem::take(&mut buffered_marks), buffered_len);
+                if mem::take(&mut buffered_placements) {
+                    row.set_placements(true);
+                }
             }
 
             loop {
+                // Rows created from the wrapped cells might hold the same placements.
+                let placements = row.has_placements();
+
                 // Remove all cells which require reflowing.
                 let mut wrapped = match row.shrink(columns) {
                     Some(wrapped) if reflow => wrapped,
@@ -280,6 +304,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                             Vec::new()
                         } else {
                             // Since it fits, just push the existing line without any reflow.
+                            row.clamp_semantic_marks();
                             new_raw.push(row);
                             break;
                         }
@@ -287,6 +312,7 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                 };
 
                 // Insert spacer if a wide char would be wrapped into the last column.
+                let mut wrap_column = columns;
                 if row.len() >= columns
                     && row[Column(columns - 1)].flags().contains(Flags::WIDE_CHAR)
                 {
@@ -295,13 +321,18 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
 
                     let wide_char = mem::replace(&mut row[Column(columns - 1)], spacer);
                     wrapped.insert(0, wide_char);
+                    wrap_column -= 1;
                 }
 
+                // Move marks along with the wrapped cells.
+                let wrapped_marks = row.split_semantic_marks(wrap_column);
+
                 // Remove wide char spacer before shrinking.
                 let len = wrapped.len();
                 if len > 0 && wrapped[len - 1].flags().contains(Flags::LEADING_WIDE_CHAR_SPACER) {
                     if len == 1 {
                         row[Column(columns - 1)].flags_mut().insert(Flags::WRAPLINE);
+                        row.append_semantic_marks(wrapped_marks, wrap_column);
                         new_raw.push(row);
                         break;
                     } else {
@@ -331,6 +362,8 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
 
                     // Add removed cells to start of next row.
                     buffered = Some(wrapped);
+                    buffered_marks = wrapped_marks;
+                    buffered_placements = placements;
                     break;
                 } else {
                     // Reflow cursor if a line below it is deleted.
@@ -354,6 +387,8 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
                         wrapped.resize_with(columns, T::default);
                     }
                     row = Row::from_vec(wrapped, occ);
+                    row.append_semantic_marks(wrapped_marks, 0);
+                    row.set_placements(placements);
 
                     if i < self.display_offset {
                         // Since we added a new line, rotate up the viewport.
@@ -365,6 +400,9 @@ impl<T: GridCell + Default + PartialEq> Grid<T> {
 
         // Reverse iterator and use it as the new grid storage.
         let mut reversed: Vec<Row<T>> = new_raw.drain(..).rev().collect();
+        for row in reversed.iter().skip(self.max_scroll_limit + self.lines).rev() {
+            Self::spill_row(&mut self.spill, row);
+        }
         reversed.truncate(self.max_scroll_limit + self.lines);
         self.raw.replace_inner(reversed);
 
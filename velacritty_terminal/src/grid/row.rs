@@ -8,8 +8,10 @@ use std::{ptr, slice};
 use serde::{Deserialize, Serialize};
 
 use crate::grid::GridCell;
+use crate::grid::compression::{CompressedRow, CompressibleCell};
 use crate::index::Column;
 use crate::term::cell::ResetDiscriminant;
+use crate::term::shell_integration::{SemanticMark, SemanticMarks};
 
 /// A row in the grid.
 #[derive(Default, Clone, Debug)]
@@ -22,6 +24,97 @@ pub struct Row<T> {
     /// This is the upper bound on the number of elements in the row, which have been modified
     /// since the last reset. All cells after this point are guaranteed to be equal.
     pub(crate) occ: usize,
+
+    /// Rarely used row attributes.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "Option::default", skip_serializing_if = "Option::is_none")
+    )]
+    extra: Option<Box<RowExtra<T>>>,
+}
+
+/// Dynamically allocated row attributes.
+///
+/// Like the [`CellExtra`], this keeps the size of every row small, at the cost of an additional
+/// allocation for the few rows which actually carry these attributes.
+///
+/// [`CellExtra`]: crate::term::cell::CellExtra
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct RowExtra<T> {
+    /// Shell integration marks.
+    semantic_marks: SemanticMarks,
+
+    /// Double-width and double-height line attribute.
+    #[cfg_attr(feature = "serde", serde(default))]
+    line_attribute: LineAttribute,
+
+    /// Whether cells might hold kitty graphics placements.
+    ///
+    /// This can be set after the placements were overwritten, but never while a cell holds one.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    placements: bool,
+
+    /// Compressed cells, replacing the row's cells for rows far in the history.
+    #[cfg_attr(feature = "serde", serde(skip, default = "Option::default"))]
+    compressed: Option<CompressedRow<T>>,
+}
+
+impl<T> Default for RowExtra<T> {
+    fn default() -> Self {
+        Self {
+            semantic_marks: Default::default(),
+            line_attribute: Default::default(),
+            placements: Default::default(),
+            compressed: Default::default(),
+        }
+    }
+}
+
+impl<T> RowExtra<T> {
+    /// Copy of the row attributes, without the compressed cells.
+    fn attributes(&self) -> Option<Box<Self>> {
+        let extra = Self {
+            semantic_marks: self.semantic_marks.clone(),
+            line_attribute: self.line_attribute,
+            placements: self.placements,
+            compressed: None,
+        };
+        (!extra.is_empty()).then(|| Box::new(extra))
+    }
+
+    /// Check if the row has no attributes at all.
+    fn is_empty(&self) -> bool {
+        self.semantic_marks.as_slice().is_empty()
+            && self.line_attribute == LineAttribute::Normal
+            && !self.placements
+            && self.compressed.is_none()
+    }
+}
+
+/// Size of the characters in a row.
+///
+/// Rows with a double-width attribute only hold half as many columns as usual.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LineAttribute {
+    /// DECSWL single-width, single-height line.
+    #[default]
+    Normal,
+    /// DECDWL double-width, single-height line.
+    DoubleWidth,
+    /// DECDHL top half of a double-width, double-height line.
+    DoubleHeightTop,
+    /// DECDHL bottom half of a double-width, double-height line.
+    DoubleHeightBottom,
+}
+
+impl LineAttribute {
+    /// Check if characters are drawn across two columns.
+    #[inline]
+    pub fn is_double_width(self) -> bool {
+        self != Self::Normal
+    }
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
@@ -52,7 +145,7 @@ impl<T: Default> Row<T> {
             inner.set_len(columns);
         }
 
-        Row { inner, occ: 0 }
+        Row { inner, occ: 0, extra: None }
     }
 
     /// Increase the number of columns in the row.
@@ -107,6 +200,7 @@ impl<T: Default> Row<T> {
         }
 
         self.occ = 0;
+        self.extra = None;
     }
 }
 
@@ -114,7 +208,7 @@ impl<T: Default> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row { inner: vec, occ, extra: None }
     }
 
     #[inline]
@@ -167,6 +261,205 @@ impl<T> Row<T> {
         std::mem::swap(&mut split, &mut self.inner);
         split
     }
+
+    /// Shell integration marks in this row, ordered by column.
+    #[inline]
+    pub fn semantic_marks(&self) -> &[SemanticMark] {
+        match &self.extra {
+            Some(extra) => extra.semantic_marks.as_slice(),
+            None => &[],
+        }
+    }
+
+    /// Double-width and double-height line attribute.
+    #[inline]
+    pub fn line_attribute(&self) -> LineAttribute {
+        self.extra.as_ref().map_or_else(LineAttribute::default, |extra| extra.line_attribute)
+    }
+
+    /// Change the double-width and double-height line attribute.
+    #[inline]
+    pub fn set_line_attribute(&mut self, attribute: LineAttribute) {
+        if attribute != self.line_attribute() {
+            self.extra.get_or_insert_default().line_attribute = attribute;
+        }
+    }
+
+    /// Add a shell integration mark, replacing any existing mark of the same kind.
+    #[inline]
+    pub fn set_semantic_mark(&mut self, mark: SemanticMark) {
+        self.extra.get_or_insert_default().semantic_marks.insert(mark);
+    }
+
+    /// Remove all shell integration marks at or after `column`.
+    ///
+    /// The removed marks are returned relative to `column`.
+    pub(crate) fn split_semantic_marks(&mut self, column: usize) -> Vec<SemanticMark> {
+        match &mut self.extra {
+            Some(extra) => extra.semantic_marks.split_off(column),
+            None => Vec::new(),
+        }
+    }
+
+    /// Remove all shell integration marks before `column`.
+    ///
+    /// All remaining marks are moved left by `column` columns.
+    pub(crate) fn front_split_semantic_marks(&mut self, column: usize) -> Vec<SemanticMark> {
+        match &mut self.extra {
+            Some(extra) => extra.semantic_marks.front_split_off(column),
+            None => Vec::new(),
+        }
+    }
+
+    /// Add shell integration marks, offset by `offset` columns.
+    ///
+    /// Marks beyond the end of the row are moved to its last column.
+    pub(crate) fn append_semantic_marks(&mut self, marks: Vec<SemanticMark>, offset: usize) {
+        if marks.is_empty() {
+            return;
+        }
+
+        let max_column = self.len().saturating_sub(1);
+        let extra = self.extra.get_or_insert_default();
+        extra.semantic_marks.extend(marks, offset, max_column);
+    }
+
+    /// Insert shell integration marks at the start of the row.
+    ///
+    /// Existing marks are moved right by `columns` columns.
+    pub(crate) fn prepend_semantic_marks(&mut self, marks: Vec<SemanticMark>, columns: usize) {
+        if let Some(extra) = &mut self.extra {
+            extra.semantic_marks.shift_right(columns);
+        }
+        self.append_semantic_marks(marks, 0);
+    }
+
+    /// Move all shell integration marks beyond the end of the row to its last column.
+    pub(crate) fn clamp_semantic_marks(&mut self) {
+        let max_column = self.len().saturating_sub(1);
+        if let Some(extra) = &mut self.extra {
+            extra.semantic_marks.clamp(max_column);
+        }
+    }
+
+    /// Check if cells might hold kitty graphics placements.
+    #[inline]
+    pub(crate) fn has_placements(&self) -> bool {
+        self.extra.as_ref().is_some_and(|extra| extra.placements)
+    }
+
+    /// Mark whether cells might hold kitty graphics placements.
+    ///
+    /// In contrast to changing the cells, this does not decompress the row.
+    pub(crate) fn set_placements(&mut self, placements: bool) {
+        match &mut self.extra {
+            Some(extra) => {
+                extra.placements = placements;
+                if extra.is_empty() {
+                    self.extra = None;
+                }
+            },
+            None if placements => self.extra.get_or_insert_default().placements = true,
+            None => (),
+        }
+    }
+
+    /// Replace the cells by their compressed representation.
+    ///
+    /// If the row is compressed already, only its cached decompressed cells are released.
+    pub(crate) fn compress(&mut self)
+    where
+        T: CompressibleCell,
+    {
+        if let Some(compressed) = self.extra.as_mut().and_then(|extra| extra.compressed.as_mut()) {
+            compressed.take_cache();
+        } else if let Some(compressed) = CompressedRow::new(&self.inner) {
+            self.extra.get_or_insert_default().compressed = Some(compressed);
+            self.inner = Vec::new();
+        }
+    }
+
+    /// Restore the cells of a compressed row.
+    #[inline]
+    pub(crate) fn decompress(&mut self) {
+        let extra = match &mut self.extra {
+            Some(extra) => extra,
+            None => return,
+        };
+
+        if let Some(mut compressed) = extra.compressed.take() {
+            self.inner = match compressed.take_cache() {
+                Some(row) => row.inner,
+                None => compressed.cells(),
+            };
+
+            if extra.is_empty() {
+                self.extra = None;
+            }
+        }
+    }
+
+    /// Access the row with all its cells, decompressing them if necessary.
+    ///
+    /// The decompressed cells are cached until [`Row::release_cache`] is called.
+    #[inline]
+    pub(crate) fn resolve(&self) -> &Row<T> {
+        match self.compressed() {
+            Some(compressed) => compressed.row(|| self.decompressed(compressed)),
+            None => self,
+        }
+    }
+
+    /// Access the row with all its cells, decompressing them into a temporary row if necessary.
+    ///
+    /// In contrast to [`Row::resolve`], this does not cache the decompressed cells.
+    #[inline]
+    pub(crate) fn with_resolved<R>(&self, f: impl FnOnce(&Row<T>) -> R) -> R {
+        match self.compressed() {
+            Some(compressed) if !compressed.is_cached() => f(&self.decompressed(compressed)),
+            _ => f(self.resolve()),
+        }
+    }
+
+    /// Release the cached cells of a compressed row.
+    #[inline]
+    pub(crate) fn release_cache(&mut self) {
+        if let Some(compressed) = self.extra.as_mut().and_then(|extra| extra.compressed.as_mut()) {
+            compressed.take_cache();
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_compressed(&self) -> bool {
+        self.compressed().is_some()
+    }
+
+    /// Check if accessing the row through [`Row::resolve`] has to decompress its cells.
+    #[inline]
+    pub(crate) fn needs_decompression(&self) -> bool {
+        self.compressed().is_some_and(|compressed| !compressed.is_cached())
+    }
+
+    /// Row with the decompressed cells and the attributes of this row.
+    fn decompressed(&self, compressed: &CompressedRow<T>) -> Row<T> {
+        Row {
+            inner: compressed.cells(),
+            occ: self.occ,
+            extra: self.extra.as_ref().and_then(|extra| extra.attributes()),
+        }
+    }
+
+    /// Compressed cells, if the row is compressed.
+    #[inline]
+    fn compressed(&self) -> Option<&CompressedRow<T>> {
+        self.extra.as_ref()?.compressed.as_ref()
+    }
+
+    /// Approximate number of heap bytes used by the row's cells.
+    pub(crate) fn heap_size(&self) -> usize {
+        let compressed = self.compressed().map_or(0, |compressed| compressed.heap_size());
+        self.inner.capacity() * std::mem::size_of::<T>() + compressed
+    }
 }
 
 impl<'a, T> IntoIterator for &'a Row<T> {
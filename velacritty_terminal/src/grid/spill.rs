@@ -0,0 +1,562 @@
+//! Scrollback history written to disk.
+//!
+//! When the scrollback history is unlimited, rows moving past the in-memory history limit are
+//! appended to an anonymous temporary file instead of being discarded. Rows are written in chunks,
+//! which are compressed and optionally encrypted with a random key that only exists in memory.
+//!
+//! Compressing, encrypting and writing the chunks happens on a separate thread, so it never
+//! blocks the terminal. Chunks stay in memory until they have been written.
+
+use std::fmt::{self, Debug, Formatter};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use log::error;
+use parking_lot::Mutex;
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use crate::grid::{CompressibleCell, GridCell, Row};
+use crate::index::Column;
+use crate::term::cell::Flags;
+use crate::term::shell_integration::{SemanticMark, SemanticMarkKind};
+use crate::thread;
+
+/// Number of rows stored in each chunk of the spill file.
+const CHUNK_ROWS: usize = 256;
+
+/// Storage of rows moving past the history limit.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SpillMode {
+    /// Rows moving past the history limit are discarded.
+    #[default]
+    Disabled,
+
+    /// Rows are written to disk as they are.
+    Plaintext,
+
+    /// Rows are encrypted before they are written to disk.
+    Encrypted,
+}
+
+/// Cell which can be written to the spill file.
+pub trait SpillCell: CompressibleCell + GridCell {
+    /// Append the cell to the spill buffer.
+    fn encode(&self, writer: &mut SpillWriter<'_>);
+
+    /// Read a cell written by [`SpillCell::encode`].
+    fn decode(reader: &mut SpillReader<'_>) -> Option<Self>;
+}
+
+/// Binary encoder for spilled rows.
+pub struct SpillWriter<'a>(&'a mut Vec<u8>);
+
+impl SpillWriter<'_> {
+    #[inline]
+    pub fn write_u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    #[inline]
+    pub fn write_u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    #[inline]
+    pub fn write_u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Write a length-prefixed string.
+    #[inline]
+    pub fn write_str(&mut self, value: &str) {
+        self.write_u32(value.len() as u32);
+        self.0.extend_from_slice(value.as_bytes());
+    }
+}
+
+/// Binary decoder for spilled rows.
+pub struct SpillReader<'a>(&'a [u8]);
+
+impl<'a> SpillReader<'a> {
+    #[inline]
+    pub fn read_u8(&mut self) -> Option<u8> {
+        Some(self.read_bytes(1)?[0])
+    }
+
+    #[inline]
+    pub fn read_u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.read_bytes(2)?.try_into().ok()?))
+    }
+
+    #[inline]
+    pub fn read_u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.read_bytes(4)?.try_into().ok()?))
+    }
+
+    /// Read a string written by [`SpillWriter::write_str`].
+    #[inline]
+    pub fn read_str(&mut self) -> Option<&'a str> {
+        let len = self.read_u32()? as usize;
+        std::str::from_utf8(self.read_bytes(len)?).ok()
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+
+        let (bytes, remaining) = self.0.split_at(len);
+        self.0 = remaining;
+        Some(bytes)
+    }
+}
+
+/// Chunk of encoded rows.
+#[derive(Debug, Clone)]
+enum Chunk {
+    /// Uncompressed rows, which have not been written to the file yet.
+    Pending(Arc<Vec<u8>>),
+
+    /// Location of the chunk inside the spill file.
+    Written { offset: u64, size: usize },
+}
+
+/// Chunk which should be written to the file by the writer thread.
+struct WriteJob {
+    file: Arc<Mutex<File>>,
+    cipher: Option<ChaCha20Poly1305>,
+    chunks: Arc<Mutex<Vec<Chunk>>>,
+    failed: Arc<AtomicBool>,
+    index: usize,
+}
+
+/// Rows which have moved past the history limit, oldest first.
+pub(crate) struct Spill<T> {
+    /// Anonymous temporary file, which is removed by the OS once it is closed.
+    file: Arc<Mutex<File>>,
+
+    /// Cipher for encrypting chunks, if encryption is enabled.
+    cipher: Option<ChaCha20Poly1305>,
+
+    /// Chunks which have been completed, shared with the writer thread.
+    chunks: Arc<Mutex<Vec<Chunk>>>,
+
+    /// Whether the writer thread was unable to write a chunk.
+    failed: Arc<AtomicBool>,
+
+    /// Channel to the writer thread, which is shared between clones.
+    writer: Sender<WriteJob>,
+
+    /// Encoded rows which do not fill an entire chunk yet.
+    pending: Vec<u8>,
+
+    /// Total number of rows, including pending ones.
+    len: usize,
+
+    /// Encoder for the rows.
+    ///
+    /// Like for compressed rows, this allows spilling rows without any trait bounds.
+    encode: fn(&Row<T>, &mut Vec<u8>),
+
+    /// Decoder for the rows, resizing them to the requested number of columns.
+    decode: fn(&mut SpillReader<'_>, usize) -> Option<Row<T>>,
+}
+
+impl<T: SpillCell> Spill<T> {
+    pub fn new(mode: SpillMode) -> io::Result<Self> {
+        let (writer, receiver) = mpsc::channel();
+        thread::spawn_named("scrollback writer", move || write_chunks(receiver));
+
+        Ok(Self {
+            file: Arc::new(Mutex::new(tempfile::tempfile()?)),
+            cipher: Self::cipher(mode)?,
+            chunks: Default::default(),
+            failed: Default::default(),
+            writer,
+            pending: Vec::new(),
+            len: 0,
+            encode: Self::encode_row,
+            decode: Self::decode_row,
+        })
+    }
+
+    fn encode_row(row: &Row<T>, buf: &mut Vec<u8>) {
+        row.with_resolved(|row| Self::encode_resolved_row(row, buf));
+    }
+
+    fn encode_resolved_row(row: &Row<T>, buf: &mut Vec<u8>) {
+        let mut writer = SpillWriter(buf);
+
+        writer.write_u32(row.len() as u32);
+        for cell in row {
+            cell.encode(&mut writer);
+        }
+
+        let marks = row.semantic_marks();
+        writer.write_u32(marks.len() as u32);
+        for mark in marks {
+            writer.write_u32(mark.column.0 as u32);
+            match mark.kind {
+                SemanticMarkKind::PromptStart => writer.write_u8(0),
+                SemanticMarkKind::CommandStart => writer.write_u8(1),
+                SemanticMarkKind::OutputStart => writer.write_u8(2),
+                SemanticMarkKind::CommandFinished(None) => writer.write_u8(3),
+                SemanticMarkKind::CommandFinished(Some(code)) => {
+                    writer.write_u8(4);
+                    writer.write_u32(code as u32);
+                },
+            }
+        }
+    }
+
+    fn decode_row(reader: &mut SpillReader<'_>, columns: usize) -> Option<Row<T>> {
+        let len = reader.read_u32()? as usize;
+        let mut cells = Vec::with_capacity(columns);
+        for _ in 0..len {
+            cells.push(T::decode(reader)?);
+        }
+
+        // Rows are not reflowed, so they are cut off or padded to the current width.
+        if cells.len() > columns {
+            cells.truncate(columns);
+            if cells.last().is_some_and(|cell| cell.flags().contains(Flags::WIDE_CHAR)) {
+                cells[columns - 1] = T::default();
+            }
+        }
+        let occ = cells.len();
+        cells.resize_with(columns, T::default);
+
+        let mut row = Row::from_vec(cells, occ);
+
+        for _ in 0..reader.read_u32()? {
+            let column = Column(reader.read_u32()? as usize);
+            let kind = match reader.read_u8()? {
+                0 => SemanticMarkKind::PromptStart,
+                1 => SemanticMarkKind::CommandStart,
+                2 => SemanticMarkKind::OutputStart,
+                3 => SemanticMarkKind::CommandFinished(None),
+                4 => SemanticMarkKind::CommandFinished(Some(reader.read_u32()? as i32)),
+                _ => return None,
+            };
+
+            if column < columns {
+                row.set_semantic_mark(SemanticMark::new(kind, column));
+            }
+        }
+
+        // Spilled rows are always far out of view, so there's no point in expanding them.
+        row.compress();
+
+        Some(row)
+    }
+}
+
+impl<T> Spill<T> {
+    /// Number of rows in the spill file.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Append a row.
+    pub fn push(&mut self, row: &Row<T>) -> io::Result<()> {
+        if self.failed.load(Ordering::Relaxed) {
+            return Err(io::Error::other("writing a previous chunk failed"));
+        }
+
+        (self.encode)(row, &mut self.pending);
+        self.len += 1;
+
+        if self.len % CHUNK_ROWS == 0 { self.write_chunk() } else { Ok(()) }
+    }
+
+    /// Read a range of rows, resized to `columns`.
+    pub fn read(&self, range: Range<usize>, columns: usize) -> io::Result<Vec<Row<T>>> {
+        let mut rows = Vec::with_capacity(range.len());
+
+        let mut chunk_start = range.start - range.start % CHUNK_ROWS;
+        while chunk_start < range.end {
+            let chunk_data;
+            let data = match self.chunks.lock().get(chunk_start / CHUNK_ROWS).cloned() {
+                Some(Chunk::Written { offset, size }) => {
+                    chunk_data = Arc::new(self.read_chunk(offset, size)?);
+                    chunk_data.as_slice()
+                },
+                Some(Chunk::Pending(rows)) => {
+                    chunk_data = rows;
+                    chunk_data.as_slice()
+                },
+                None => self.pending.as_slice(),
+            };
+
+            let mut reader = SpillReader(data);
+            for index in chunk_start..range.end.min(chunk_start + CHUNK_ROWS) {
+                let row = (self.decode)(&mut reader, columns).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "corrupted scrollback row")
+                })?;
+
+                if index >= range.start {
+                    rows.push(row);
+                }
+            }
+
+            chunk_start += CHUNK_ROWS;
+        }
+
+        Ok(rows)
+    }
+
+    /// Remove all rows.
+    pub fn clear(&mut self) -> io::Result<()> {
+        // Start over with a new file and key, so no nonce is ever used twice.
+        self.file = Arc::new(Mutex::new(tempfile::tempfile()?));
+        self.cipher = Self::cipher(self.mode())?;
+        self.chunks = Default::default();
+        self.failed = Default::default();
+        self.pending.clear();
+        self.len = 0;
+
+        Ok(())
+    }
+
+    /// Mode the spill file was created with.
+    #[inline]
+    pub fn mode(&self) -> SpillMode {
+        if self.cipher.is_some() { SpillMode::Encrypted } else { SpillMode::Plaintext }
+    }
+
+    /// Create a cipher with a random key.
+    fn cipher(mode: SpillMode) -> io::Result<Option<ChaCha20Poly1305>> {
+        if mode != SpillMode::Encrypted {
+            return Ok(None);
+        }
+
+        let mut key = Key::default();
+        getrandom::getrandom(key.as_mut_slice())?;
+
+        Ok(Some(ChaCha20Poly1305::new(&key)))
+    }
+
+    /// Hand all pending rows to the writer thread.
+    fn write_chunk(&mut self) -> io::Result<()> {
+        let rows = Arc::new(mem::take(&mut self.pending));
+
+        let mut chunks = self.chunks.lock();
+        let index = chunks.len();
+        chunks.push(Chunk::Pending(rows));
+        drop(chunks);
+
+        let job = WriteJob {
+            file: self.file.clone(),
+            cipher: self.cipher.clone(),
+            chunks: self.chunks.clone(),
+            failed: self.failed.clone(),
+            index,
+        };
+        self.writer.send(job).map_err(|_| io::Error::other("scrollback writer has stopped"))
+    }
+
+    /// Read and decode a chunk from the file.
+    fn read_chunk(&self, offset: u64, size: usize) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; size];
+        let mut file = self.file.lock();
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut data)?;
+        drop(file);
+
+        if let Some(cipher) = &self.cipher {
+            data = cipher.decrypt(&nonce(offset), data.as_slice()).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "scrollback decryption failed")
+            })?;
+        }
+
+        let mut rows = Vec::new();
+        ZlibDecoder::new(data.as_slice()).read_to_end(&mut rows)?;
+
+        Ok(rows)
+    }
+
+    /// Wait until the writer thread has written all chunks.
+    #[cfg(test)]
+    fn sync(&self) {
+        while self.chunks.lock().iter().any(|chunk| matches!(chunk, Chunk::Pending(_))) {
+            std::thread::yield_now();
+        }
+    }
+}
+
+impl<T> Clone for Spill<T> {
+    fn clone(&self) -> Self {
+        // Chunks which are still pending are never marked as written in the clone, so they stay
+        // in memory for its entire lifetime.
+        Self {
+            file: self.file.clone(),
+            cipher: self.cipher.clone(),
+            chunks: Arc::new(Mutex::new(self.chunks.lock().clone())),
+            failed: Arc::new(AtomicBool::new(self.failed.load(Ordering::Relaxed))),
+            writer: self.writer.clone(),
+            pending: self.pending.clone(),
+            len: self.len,
+            encode: self.encode,
+            decode: self.decode,
+        }
+    }
+}
+
+impl<T> Debug for Spill<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spill")
+            .field("len", &self.len)
+            .field("chunks", &self.chunks.lock().len())
+            .field("mode", &self.mode())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Write chunks to their spill file, until all spill files using this thread are dropped.
+fn write_chunks(receiver: Receiver<WriteJob>) {
+    for job in receiver {
+        if let Err(err) = write_chunk(&job) {
+            error!("Unable to write scrollback history to disk: {err}");
+            job.failed.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Compress, encrypt and write a pending chunk, keeping it in memory if that fails.
+fn write_chunk(job: &WriteJob) -> io::Result<()> {
+    let rows = match job.chunks.lock().get(job.index) {
+        Some(Chunk::Pending(rows)) => rows.clone(),
+        _ => return Ok(()),
+    };
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+    encoder.write_all(&rows)?;
+    let mut data = encoder.finish()?;
+
+    let mut file = job.file.lock();
+    let offset = file.seek(SeekFrom::End(0))?;
+
+    // The offset is unique for every chunk, even when the file is shared between clones.
+    if let Some(cipher) = &job.cipher {
+        data = cipher
+            .encrypt(&nonce(offset), data.as_slice())
+            .map_err(|_| io::Error::other("scrollback encryption failed"))?;
+    }
+
+    file.write_all(&data)?;
+    drop(file);
+
+    job.chunks.lock()[job.index] = Chunk::Written { offset, size: data.len() };
+
+    Ok(())
+}
+
+fn nonce(offset: u64) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[..8].copy_from_slice(&offset.to_le_bytes());
+    nonce
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::term::cell::Cell;
+    use crate::vte::ansi::{Color, NamedColor, Rgb};
+
+    fn row(text: &str, columns: usize) -> Row<Cell> {
+        let mut cells = vec![Cell::default(); columns];
+        for (cell, c) in cells.iter_mut().zip(text.chars()) {
+            cell.c = c;
+        }
+        Row::from_vec(cells, text.len())
+    }
+
+    fn roundtrip(mode: SpillMode) {
+        let mut spill = Spill::new(mode).unwrap();
+
+        let mut styled = row("styled", 10);
+        styled[Column(0)].fg = Color::Named(NamedColor::DimRed);
+        styled[Column(1)].bg = Color::Spec(Rgb { r: 1, g: 2, b: 3 });
+        styled[Column(2)].flags = Flags::BOLD | Flags::UNDERLINE;
+        styled[Column(3)].push_zerowidth('\u{301}');
+        styled[Column(4)].set_underline_color(Some(Color::Indexed(42)));
+        styled.set_semantic_mark(SemanticMark::new(
+            SemanticMarkKind::CommandFinished(Some(-1)),
+            Column(5),
+        ));
+
+        for i in 0..CHUNK_ROWS * 2 + 10 {
+            spill.push(&row(&i.to_string(), 10)).unwrap();
+        }
+        spill.push(&styled).unwrap();
+
+        assert_eq!(spill.len(), CHUNK_ROWS * 2 + 11);
+        assert_eq!(spill.chunks.lock().len(), 2);
+
+        // Chunks are read from memory until they have been written.
+        let pending = spill.read(0..spill.len(), 10).unwrap();
+        spill.sync();
+
+        let rows = spill.read(0..spill.len(), 10).unwrap();
+        assert_eq!(rows.len(), pending.len());
+        assert!(rows.iter().zip(&pending).all(|(row, pending)| row.resolve() == pending.resolve()));
+
+        let rows = spill.read(CHUNK_ROWS - 1..spill.len(), 10).unwrap();
+        assert_eq!(rows.len(), CHUNK_ROWS + 12);
+        assert_eq!(rows[0].resolve(), &row(&(CHUNK_ROWS - 1).to_string(), 10));
+        assert_eq!(rows[1].resolve(), &row(&CHUNK_ROWS.to_string(), 10));
+        assert_eq!(rows.last().unwrap().resolve(), &styled);
+        assert_eq!(rows.last().unwrap().semantic_marks(), styled.semantic_marks());
+    }
+
+    #[test]
+    fn roundtrip_plaintext() {
+        roundtrip(SpillMode::Plaintext);
+    }
+
+    #[test]
+    fn roundtrip_encrypted() {
+        roundtrip(SpillMode::Encrypted);
+    }
+
+    #[test]
+    fn resize_rows() {
+        let mut spill = Spill::new(SpillMode::Plaintext).unwrap();
+        spill.push(&row("abcdef", 6)).unwrap();
+
+        let rows = spill.read(0..1, 3).unwrap();
+        assert_eq!(rows[0].resolve(), &row("abc", 3));
+
+        let rows = spill.read(0..1, 8).unwrap();
+        assert_eq!(rows[0].resolve(), &row("abcdef", 8));
+    }
+
+    #[test]
+    fn encrypted_on_disk() {
+        let mut spill = Spill::new(SpillMode::Encrypted).unwrap();
+        for _ in 0..CHUNK_ROWS {
+            spill.push(&row("secret", 10)).unwrap();
+        }
+        spill.sync();
+
+        let mut file = spill.file.lock();
+        let mut data = Vec::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_end(&mut data).unwrap();
+
+        let mut plaintext = Vec::new();
+        assert!(ZlibDecoder::new(data.as_slice()).read_to_end(&mut plaintext).is_err());
+    }
+}
@@ -1,17 +1,53 @@
 use std::cmp::max;
 use std::mem;
 use std::mem::MaybeUninit;
-use std::ops::{Index, IndexMut};
+use std::ops::{Add, Index, IndexMut};
 
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeSeq;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize, Serializer};
 
 use super::Row;
+use crate::grid::compression::CompressibleCell;
 use crate::index::Line;
+use crate::term::shell_integration::SemanticMark;
 
 /// Maximum number of buffered lines outside of the grid for performance optimization.
 const MAX_CACHE_SIZE: usize = 1_000;
 
+/// Size of a [`Row`] in qwords.
+const ROW_QWORDS: usize = 5;
+
+/// Approximate memory used by the rows of a grid.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Number of uncompressed rows, including rows allocated ahead of time.
+    pub rows: usize,
+
+    /// Number of compressed rows.
+    pub compressed_rows: usize,
+
+    /// Total size in bytes.
+    pub bytes: usize,
+
+    /// Number of rows written to disk.
+    pub spilled_rows: usize,
+}
+
+impl Add for MemoryUsage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            rows: self.rows + other.rows,
+            compressed_rows: self.compressed_rows + other.compressed_rows,
+            bytes: self.bytes + other.bytes,
+            spilled_rows: self.spilled_rows + other.spilled_rows,
+        }
+    }
+}
+
 /// A ring buffer for optimizing indexing and rotation.
 ///
 /// The [`Storage::rotate`] and [`Storage::rotate_down`] functions are fast modular additions on
@@ -25,12 +61,18 @@ const MAX_CACHE_SIZE: usize = 1_000;
 /// implementation is provided. Anything from [`Vec`] that should be exposed must be done so
 /// manually.
 ///
+/// Rows far in the history can be compressed, in which case they are transparently decompressed
+/// when accessed. Indexing caches the decompressed row until its cache is released, while
+/// [`Storage::with_row`] only decompresses it temporarily.
+///
 /// [`slice::rotate_left`]: https://doc.rust-lang.org/std/primitive.slice.html#method.rotate_left
 /// [`Deref`]: std::ops::Deref
 /// [`zero`]: #structfield.zero
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "T: Serialize")))]
 pub struct Storage<T> {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_rows"))]
     inner: Vec<Row<T>>,
 
     /// Starting point for the storage of rows.
@@ -58,10 +100,30 @@ impl<T: PartialEq> PartialEq for Storage<T> {
         assert_eq!(self.zero, 0);
         assert_eq!(other.zero, 0);
 
-        self.inner == other.inner && self.len == other.len
+        self.inner.len() == other.inner.len()
+            && self
+                .inner
+                .iter()
+                .zip(&other.inner)
+                .all(|(a, b)| a.with_resolved(|a| b.with_resolved(|b| a == b)))
+            && self.len == other.len
     }
 }
 
+/// Serialize rows with all their cells, decompressing them if necessary.
+#[cfg(feature = "serde")]
+fn serialize_rows<T, S>(rows: &[Row<T>], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(rows.len()))?;
+    for row in rows {
+        row.with_resolved(|row| seq.serialize_element(row))?;
+    }
+    seq.end()
+}
+
 impl<T> Storage<T> {
     #[inline]
     pub fn with_capacity(visible_lines: usize, columns: usize) -> Storage<T>
@@ -147,11 +209,10 @@ impl<T> Storage<T> {
     /// Exploits the known size of Row<T> to produce a slightly more efficient
     /// swap than going through slice::swap.
     ///
-    /// The default implementation from swap generates 8 movups and 4 movaps
-    /// instructions. This implementation achieves the swap in only 8 movups
-    /// instructions.
+    /// Instead of the generic byte-wise swap, this swaps the row one qword at a
+    /// time, which allows the optimizer to unroll and vectorize the loop.
     pub fn swap(&mut self, a: Line, b: Line) {
-        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * 4);
+        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * ROW_QWORDS);
 
         let a = self.compute_index(a);
         let b = self.compute_index(b);
@@ -167,7 +228,7 @@ impl<T> Storage<T> {
             //
             // The optimizer unrolls this loop and vectorizes it.
             let mut tmp: MaybeUninit<usize>;
-            for i in 0..4 {
+            for i in 0..ROW_QWORDS as isize {
                 tmp = *a_ptr.offset(i);
                 *a_ptr.offset(i) = *b_ptr.offset(i);
                 *b_ptr.offset(i) = tmp;
@@ -212,9 +273,77 @@ impl<T> Storage<T> {
         mem::swap(&mut buffer, &mut self.inner);
         self.len = 0;
 
+        for row in &mut buffer {
+            row.decompress();
+        }
+
         buffer
     }
 
+    /// Compress a row, or release its cached cells if it is compressed already.
+    #[inline]
+    pub fn compress(&mut self, line: Line)
+    where
+        T: CompressibleCell,
+    {
+        let index = self.compute_index(line);
+        self.inner[index].compress();
+    }
+
+    /// Release the cached cells of all compressed rows.
+    pub fn release_decompressed(&mut self) {
+        for row in &mut self.inner {
+            row.release_cache();
+        }
+    }
+
+    /// Access a row without caching its decompressed cells.
+    #[inline]
+    pub fn with_row<R>(&self, line: Line, f: impl FnOnce(&Row<T>) -> R) -> R {
+        self.inner[self.compute_index(line)].with_resolved(f)
+    }
+
+    /// Check if indexing a row has to decompress its cells.
+    #[inline]
+    pub fn needs_decompression(&self, line: Line) -> bool {
+        self.inner[self.compute_index(line)].needs_decompression()
+    }
+
+    /// Mark whether a row might hold kitty graphics placements, without decompressing it.
+    #[inline]
+    pub fn set_placements(&mut self, line: Line, placements: bool) {
+        let index = self.compute_index(line);
+        self.inner[index].set_placements(placements);
+    }
+
+    /// Check if a row might hold kitty graphics placements, without decompressing it.
+    #[inline]
+    pub fn has_placements(&self, line: Line) -> bool {
+        self.inner[self.compute_index(line)].has_placements()
+    }
+
+    /// Shell integration marks of a row.
+    ///
+    /// In contrast to indexing, this does not require decompressing the row.
+    #[inline]
+    pub fn semantic_marks(&self, line: Line) -> &[SemanticMark] {
+        self.inner[self.compute_index(line)].semantic_marks()
+    }
+
+    /// Approximate memory used by all rows, including rows allocated ahead of time.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage::default();
+        for row in &self.inner {
+            if row.is_compressed() {
+                usage.compressed_rows += 1;
+            } else {
+                usage.rows += 1;
+            }
+            usage.bytes += mem::size_of::<Row<T>>() + row.heap_size();
+        }
+        usage
+    }
+
     /// Compute actual index in underlying storage given the requested index.
     #[inline]
     fn compute_index(&self, requested: Line) -> usize {
@@ -251,7 +380,7 @@ impl<T> Index<Line> for Storage<T> {
     #[inline]
     fn index(&self, index: Line) -> &Self::Output {
         let index = self.compute_index(index);
-        &self.inner[index]
+        self.inner[index].resolve()
     }
 }
 
@@ -259,7 +388,9 @@ impl<T> IndexMut<Line> for Storage<T> {
     #[inline]
     fn index_mut(&mut self, index: Line) -> &mut Self::Output {
         let index = self.compute_index(index);
-        &mut self.inner[index]
+        let row = &mut self.inner[index];
+        row.decompress();
+        row
     }
 }
 
@@ -3,6 +3,8 @@
 use super::*;
 
 use crate::term::cell::Cell;
+use crate::term::shell_integration::SemanticMark;
+use crate::vte::ansi::Color;
 
 impl GridCell for usize {
     fn is_empty(&self) -> bool {
@@ -367,6 +369,191 @@ fn accurate_size_hint() {
     size_hint_matches_count(grid.display_iter());
 }
 
+#[test]
+fn reflow_semantic_marks() {
+    let mut grid = Grid::<Cell>::new(2, 5, 2);
+    for (i, c) in "$ abc".chars().enumerate() {
+        grid[Line(0)][Column(i)] = cell(c);
+    }
+    grid[Line(0)].set_semantic_mark(SemanticMark::new(SemanticMarkKind::PromptStart, Column(0)));
+    grid[Line(0)].set_semantic_mark(SemanticMark::new(SemanticMarkKind::CommandStart, Column(2)));
+    grid[Line(1)].set_semantic_mark(SemanticMark::new(SemanticMarkKind::OutputStart, Column(0)));
+
+    grid.resize(true, 2, 2);
+
+    let marks: Vec<_> = grid.semantic_marks(..).collect();
+    assert_eq!(marks, vec![
+        (Point::new(Line(-2), Column(0)), SemanticMarkKind::PromptStart),
+        (Point::new(Line(-1), Column(0)), SemanticMarkKind::CommandStart),
+        (Point::new(Line(1), Column(0)), SemanticMarkKind::OutputStart),
+    ]);
+
+    grid.resize(true, 2, 5);
+
+    let marks: Vec<_> = grid.semantic_marks(..).collect();
+    assert_eq!(marks, vec![
+        (Point::new(Line(-1), Column(0)), SemanticMarkKind::PromptStart),
+        (Point::new(Line(-1), Column(2)), SemanticMarkKind::CommandStart),
+        (Point::new(Line(1), Column(0)), SemanticMarkKind::OutputStart),
+    ]);
+}
+
+#[test]
+fn compressed_history() {
+    let lines = UNCOMPRESSED_HISTORY + 100;
+    let mut grid = Grid::<Cell>::new(1, 5, lines);
+
+    for i in 0..lines {
+        let c = char::from_digit(i as u32 % 10, 10).unwrap();
+        grid[Line(0)][Column(0)] = cell(c);
+        grid[Line(0)][Column(4)] = wrap_cell('x');
+        if i == 0 {
+            grid[Line(0)]
+                .set_semantic_mark(SemanticMark::new(SemanticMarkKind::PromptStart, Column(1)));
+        } else if i == 1 {
+            grid[Line(0)].set_line_attribute(LineAttribute::DoubleWidth);
+        }
+
+        grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+        grid.compress_history(1);
+    }
+
+    let usage = grid.memory_usage();
+    assert_eq!(usage.compressed_rows, 100);
+
+    // Compressed rows are accessible without any changes to their content.
+    let topmost_line = grid.topmost_line();
+    let second_line = Line(topmost_line.0 + 1);
+    assert_eq!(grid[topmost_line][Column(0)], cell('0'));
+    assert_eq!(grid[second_line][Column(0)], cell('1'));
+    assert_eq!(grid[second_line][Column(4)], wrap_cell('x'));
+    assert_eq!(grid[second_line].line_attribute(), LineAttribute::DoubleWidth);
+    assert_eq!(
+        grid.semantic_marks(..).next(),
+        Some((Point::new(topmost_line, Column(1)), SemanticMarkKind::PromptStart))
+    );
+    assert!(grid.memory_usage().bytes > usage.bytes);
+
+    // Modifying a row decompresses it, keeping its attributes.
+    grid[topmost_line][Column(1)] = cell('y');
+    grid[second_line][Column(1)] = cell('z');
+    assert_eq!(grid.memory_usage().compressed_rows, 98);
+    assert_eq!(grid[second_line].line_attribute(), LineAttribute::DoubleWidth);
+    assert_eq!(
+        grid.semantic_marks(..).next(),
+        Some((Point::new(topmost_line, Column(1)), SemanticMarkKind::PromptStart))
+    );
+
+    // Decompressed and cached rows are compressed again incrementally.
+    for _ in 0..=100 / COMPRESSION_BATCH_SIZE {
+        grid.compress_history(0);
+    }
+    assert_eq!(grid.memory_usage(), usage);
+    assert_eq!(grid[topmost_line][Column(1)], cell('y'));
+    assert_eq!(grid[second_line][Column(1)], cell('z'));
+}
+
+#[test]
+fn release_decompressed_history() {
+    let lines = UNCOMPRESSED_HISTORY + 100;
+    let mut grid = Grid::<Cell>::new(1, 5, lines);
+    for _ in 0..lines {
+        grid[Line(0)][Column(0)] = cell('x');
+        grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+        grid.compress_history(1);
+    }
+    let usage = grid.memory_usage();
+
+    // Temporary access does not keep the decompressed cells around.
+    for line in grid.topmost_line().0..0 {
+        assert_eq!(grid.with_row(Line(line), |row| row[Column(0)].c), 'x');
+    }
+    assert_eq!(grid.memory_usage(), usage);
+
+    // Cells cached by a full scan through indexing are released at once.
+    for line in grid.topmost_line().0..0 {
+        assert_eq!(grid[Line(line)][Column(0)], cell('x'));
+    }
+    assert!(grid.memory_usage().bytes > usage.bytes);
+
+    grid.release_decompressed();
+    assert_eq!(grid.memory_usage(), usage);
+
+    // Writing output releases them too.
+    for line in grid.topmost_line().0..0 {
+        assert_eq!(grid[Line(line)][Column(0)], cell('x'));
+    }
+    grid.compress_history(0);
+    assert_eq!(grid.memory_usage(), usage);
+}
+
+#[test]
+fn spilled_history() {
+    let mut grid = Grid::<Cell>::new(1, 5, 2);
+    grid.set_spill_mode(SpillMode::Encrypted);
+
+    for i in 0..10 {
+        grid[Line(0)][Column(0)] = cell(char::from_digit(i, 10).unwrap());
+        grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+    }
+
+    assert_eq!(grid.history_size(), 2);
+    assert_eq!(grid.spilled_lines(), 8);
+    assert_eq!(grid[Line(-2)][Column(0)], cell('8'));
+
+    // Scrolling past the top of the history pages lines back in.
+    grid.scroll_display(Scroll::Delta(3));
+    assert_eq!(grid.history_size(), 10);
+    assert_eq!(grid.display_offset(), 3);
+    assert_eq!(grid[Line(-10)][Column(0)], cell('0'));
+    assert_eq!(grid[Line(-3)][Column(0)], cell('7'));
+
+    // Paged lines are kept while they might be in view, without growing the history.
+    grid[Line(0)][Column(0)] = cell('a');
+    grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+    assert_eq!(grid.history_size(), 10);
+    assert_eq!(grid.display_offset(), 4);
+    assert_eq!(grid.spilled_lines(), 9);
+    assert_eq!(grid[Line(-10)][Column(0)], cell('1'));
+    assert_eq!(grid[Line(-3)][Column(0)], cell('8'));
+
+    // Returning to the bottom releases them again.
+    grid.scroll_display(Scroll::Bottom);
+    assert_eq!(grid.history_size(), 2);
+    assert_eq!(grid[Line(-2)][Column(0)], cell('9'));
+    assert_eq!(grid[Line(-1)][Column(0)], cell('a'));
+
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid.history_size(), 11);
+    assert_eq!(grid[Line(-3)][Column(0)], cell('8'));
+
+    grid.clear_history();
+    assert_eq!(grid.spilled_lines(), 0);
+}
+
+#[test]
+fn spilled_history_pages() {
+    let mut grid = Grid::<Cell>::new(1, 5, 2);
+    grid.set_spill_mode(SpillMode::Plaintext);
+
+    for _ in 0..2_502 {
+        grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+    }
+    assert_eq!(grid.spilled_lines(), 2_500);
+
+    // Scrolling to the top only reads one page at a time.
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid.history_size(), 1_002);
+    assert_eq!(grid.display_offset(), 1_002);
+
+    grid.scroll_display(Scroll::Delta(5_000));
+    assert_eq!(grid.history_size(), 2_002);
+
+    grid.scroll_display(Scroll::Top);
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid.history_size(), 2_502);
+}
+
 fn size_hint_matches_count<T>(iter: impl Iterator<Item = T>) {
     let iterator = iter.into_iter();
     let (lower, upper) = iterator.size_hint();
@@ -388,3 +575,68 @@ fn wrap_cell(c: char) -> Cell {
     cell.flags.insert(Flags::WRAPLINE);
     cell
 }
+
+#[test]
+fn prepend_history() {
+    let mut grid = Grid::<Cell>::new(1, 3, 2);
+    grid[Line(0)][Column(0)] = cell('x');
+    grid.scroll_up::<Color>(&(Line(0)..Line(1)), 1);
+
+    let mut wide = Row::new(5);
+    wide[Column(2)] = cell('b');
+    wide[Column(2)].flags = Flags::WIDE_CHAR;
+    wide[Column(3)].flags = Flags::WIDE_CHAR_SPACER;
+    let mut narrow = Row::new(1);
+    narrow[Column(0)] = cell('c');
+
+    grid.prepend_history(vec![Row::new(3), wide, narrow]);
+
+    // Lines which don't fit into the history are dropped.
+    assert_eq!(grid.history_size(), 2);
+    assert_eq!(grid[Line(-2)][Column(0)], cell('c'));
+    assert_eq!(grid[Line(-2)].len(), 3);
+    assert_eq!(grid[Line(-1)][Column(0)], cell('x'));
+
+    // Oldest lines are written to the spill file, wide characters are not cut in half.
+    let mut grid = Grid::<Cell>::new(1, 3, 1);
+    grid.set_spill_mode(SpillMode::Plaintext);
+    let mut wide = Row::new(5);
+    wide[Column(2)] = cell('b');
+    wide[Column(2)].flags = Flags::WIDE_CHAR;
+    wide[Column(3)].flags = Flags::WIDE_CHAR_SPACER;
+
+    grid.prepend_history(vec![Row::new(3), wide]);
+
+    assert_eq!(grid.history_size(), 1);
+    assert_eq!(grid.spilled_lines(), 1);
+    assert_eq!(grid[Line(-1)][Column(2)], Cell::default());
+}
+
+#[test]
+fn unread_lines() {
+    let mut grid = Grid::<usize>::new(5, 1, 100);
+    let region = Line(0)..Line(5);
+
+    // Following the output leaves nothing unread.
+    grid.scroll_up::<usize>(&region, 3);
+    assert_eq!(grid.unread_lines(), 0);
+
+    grid.set_auto_scroll_enabled(false);
+    grid.scroll_up::<usize>(&region, 4);
+    assert_eq!(grid.unread_lines(), 4);
+
+    grid.scroll_display(Scroll::Delta(2));
+    grid.scroll_up::<usize>(&region, 1);
+    assert_eq!(grid.display_offset(), 7);
+    assert_eq!(grid.unread_lines(), 5);
+
+    grid.scroll_display(Scroll::Delta(-4));
+    assert_eq!(grid.unread_lines(), 3);
+
+    // Scrolling back up doesn't mark lines as unread again.
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid.unread_lines(), 3);
+
+    grid.scroll_display(Scroll::Bottom);
+    assert_eq!(grid.unread_lines(), 0);
+}
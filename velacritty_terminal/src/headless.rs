@@ -0,0 +1,198 @@
+//! Terminal emulation without a window.
+//!
+//! [`HeadlessTerm`] combines a [`Term`] with its escape sequence parser, which is all that is
+//! needed to replay recorded output and inspect the result, for example in tests.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use velacritty_terminal::headless::{HeadlessTerm, Region};
+//!
+//! let mut term = HeadlessTerm::new(20, 2);
+//! term.feed(b"plain \x1b[1;31mred\x1b[m\r\n");
+//!
+//! assert_eq!(term.text(Region::Screen), "plain red\n\n");
+//! assert_eq!(term.ansi(Region::Screen), "plain \x1b[0;1;31mred\x1b[0m\n\n");
+//! ```
+
+use crate::event::{EventListener, VoidListener};
+use crate::grid::Dimensions;
+use crate::index::Line;
+use crate::parser::Processor;
+use crate::term::color::Colors;
+use crate::term::export::Format;
+use crate::term::{Config, Term};
+
+/// Lines included in a snapshot.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Region {
+    /// Visible screen, ignoring the scroll position.
+    Screen,
+
+    /// Lines which have been scrolled out of the screen.
+    Scrollback,
+
+    /// Scrollback followed by the screen.
+    All,
+}
+
+/// Terminal driven directly by bytes, without a PTY or window.
+pub struct HeadlessTerm<T = VoidListener> {
+    term: Term<T>,
+    parser: Processor,
+    palette: Colors,
+}
+
+impl HeadlessTerm<VoidListener> {
+    /// Create a terminal with the default configuration, which ignores all events.
+    pub fn new(columns: usize, screen_lines: usize) -> Self {
+        Self::with_config(Config::default(), columns, screen_lines, VoidListener)
+    }
+}
+
+impl<T: EventListener> HeadlessTerm<T> {
+    /// Create a terminal, sending its events to `event_proxy`.
+    pub fn with_config(
+        config: Config,
+        columns: usize,
+        screen_lines: usize,
+        event_proxy: T,
+    ) -> Self {
+        let size = Size { columns, screen_lines };
+        let term = Term::new(config, &size, event_proxy);
+        Self { term, parser: Processor::new(), palette: Colors::default() }
+    }
+
+    /// Set the colors of the color scheme, which HTML uses for indexed colors.
+    ///
+    /// Colors missing from the palette are left to the page's style sheet, like the default colors.
+    pub fn set_palette(&mut self, palette: Colors) {
+        self.palette = palette;
+    }
+
+    /// Process bytes as if they were written by the application.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.parser.advance(&mut self.term, bytes);
+    }
+
+    /// Apply a synchronized update which hasn't been finished yet.
+    pub fn flush(&mut self) {
+        self.parser.stop_sync(&mut self.term);
+    }
+
+    /// Resize the terminal.
+    pub fn resize(&mut self, columns: usize, screen_lines: usize) {
+        self.term.resize(Size { columns, screen_lines });
+    }
+
+    /// Emulated terminal.
+    pub fn term(&self) -> &Term<T> {
+        &self.term
+    }
+
+    /// Mutable emulated terminal.
+    pub fn term_mut(&mut self) -> &mut Term<T> {
+        &mut self.term
+    }
+
+    /// Text of a region, with one line per row and trailing whitespace removed.
+    pub fn text(&self, region: Region) -> String {
+        self.export(region, Format::Text)
+    }
+
+    /// Text of a region, with SGR escapes reproducing the character attributes.
+    ///
+    /// Every line which changes attributes ends with a reset, so lines can be used separately.
+    pub fn ansi(&self, region: Region) -> String {
+        self.export(region, Format::Ansi)
+    }
+
+    /// HTML `<pre>` element with the text of a region, using inline styles for its attributes.
+    ///
+    /// Default colors, and indexed colors which are not part of the palette, are left to the
+    /// page's style sheet.
+    pub fn html(&self, region: Region) -> String {
+        self.export(region, Format::Html)
+    }
+
+    fn export(&self, region: Region, format: Format) -> String {
+        let grid = self.term.grid();
+        let screen_lines = Line(grid.screen_lines() as i32);
+        let lines = match region {
+            Region::Screen => Line(0)..screen_lines,
+            Region::Scrollback => grid.topmost_line()..Line(0),
+            Region::All => grid.topmost_line()..screen_lines,
+        };
+
+        self.term.export_lines(lines, format, &self.palette)
+    }
+}
+
+/// Dimensions of a headless terminal.
+struct Size {
+    columns: usize,
+    screen_lines: usize,
+}
+
+impl Dimensions for Size {
+    fn total_lines(&self) -> usize {
+        self.screen_lines
+    }
+
+    fn screen_lines(&self) -> usize {
+        self.screen_lines
+    }
+
+    fn columns(&self) -> usize {
+        self.columns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::vte::ansi::{NamedColor, Rgb};
+
+    #[test]
+    fn text() {
+        let mut term = HeadlessTerm::new(5, 2);
+        term.feed("a\r\nb\r\nc  \r\n漢字x".as_bytes());
+
+        assert_eq!(term.text(Region::Scrollback), "a\nb\n");
+        assert_eq!(term.text(Region::Screen), "c\n漢字x\n");
+        assert_eq!(term.text(Region::All), "a\nb\nc\n漢字x\n");
+
+        term.resize(5, 4);
+        assert_eq!(term.text(Region::All), term.text(Region::Screen));
+    }
+
+    #[test]
+    fn ansi() {
+        let mut term = HeadlessTerm::new(10, 2);
+        term.feed(b"\x1b[4mab\x1b[24;38;5;100mc\x1b[m d\r\n\x1b[41m \x1b[m");
+
+        assert_eq!(
+            term.ansi(Region::Screen),
+            "\x1b[0;4mab\x1b[0;38:5:100mc\x1b[0m d\n\x1b[0;41m \x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn html() {
+        let mut palette = Colors::default();
+        palette[NamedColor::Black] = Some(Rgb { r: 0x18, g: 0x18, b: 0x18 });
+        palette[NamedColor::Red] = Some(Rgb { r: 0xac, g: 0x42, b: 0x42 });
+
+        let mut term = HeadlessTerm::new(10, 2);
+        term.set_palette(palette);
+        term.feed(b"<\x1b[1;31ma\x1b[7mb\x1b[m\r\n\x1b[38;2;1;2;3m&");
+
+        assert_eq!(
+            term.html(Region::Screen),
+            "<pre>&lt;<span style=\"color:#ac4242;font-weight:bold;\">a</span><span \
+             style=\"color:#181818;background-color:#ac4242;font-weight:bold;\">b</span>\n<span \
+             style=\"color:#010203;\">&amp;</span>\n</pre>"
+        );
+    }
+}
@@ -6,8 +6,13 @@
 
 pub mod event;
 pub mod event_loop;
+pub mod grapheme;
+pub mod graphics;
 pub mod grid;
+pub mod headless;
 pub mod index;
+pub mod parser;
+pub mod recording;
 pub mod selection;
 pub mod sync;
 pub mod term;
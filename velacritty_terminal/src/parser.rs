@@ -0,0 +1,742 @@
+//! Escape sequence parser with support for sequences unknown to the VTE parser.
+//!
+//! The [`ansi::Processor`] silently drops all escape sequences it doesn't know about. To still
+//! handle these sequences, a second parser runs alongside it which only looks at the sequences
+//! missing from [`ansi::Handler`]. Whenever one of them is encountered, both parsers are stopped
+//! at the same byte, so the sequence is dispatched in order with the rest of the stream.
+//!
+//! Since the VTE parser doesn't expose the content of APC strings at all, these are collected by
+//! a separate [`ApcScanner`].
+//!
+//! Synchronized updates are buffered here instead of in the VTE parser, so extended sequences
+//! within them are dispatched in order once the update ends.
+
+use std::mem;
+use std::time::Duration;
+
+use vte::ansi::{self, NamedPrivateMode, StdSyncHandler, Timeout};
+use vte::{Params, Perform};
+
+use crate::graphics::{Image, kitty, sixel};
+use crate::grid::LineAttribute;
+use crate::term::notification::{Notification, NotificationChunk};
+use crate::term::progress::Progress;
+use crate::term::rectangle::{AttributeChange, Rectangle};
+use crate::term::shell_integration::{SemanticMarkKind, WorkingDirectory};
+
+/// Type that handles actions from the parser.
+///
+/// This extends [`ansi::Handler`] with all escape sequences which are handled outside of the VTE
+/// crate.
+pub trait Handler: ansi::Handler {
+    /// OSC 133 semantic prompt mark at the cursor position.
+    fn semantic_mark(&mut self, _kind: SemanticMarkKind) {}
+
+    /// OSC 7 working directory report.
+    fn set_working_directory(&mut self, _working_directory: WorkingDirectory) {}
+
+    /// DCS q sixel image at the cursor position.
+    fn sixel_graphic(&mut self, _image: Image) {}
+
+    /// APC G kitty graphics protocol command.
+    fn kitty_graphics(&mut self, _command: kitty::Command) {}
+
+    /// DECCRA copy of a rectangle, with `top` and `left` as its new top-left corner.
+    fn copy_rectangle(&mut self, _source: Rectangle, _top: usize, _left: usize) {}
+
+    /// DECFRA fill of a rectangle with a character.
+    fn fill_rectangle(&mut self, _area: Rectangle, _c: char) {}
+
+    /// DECERA erase of a rectangle.
+    fn erase_rectangle(&mut self, _area: Rectangle) {}
+
+    /// DECSERA erase of all unprotected characters in a rectangle.
+    fn selective_erase_rectangle(&mut self, _area: Rectangle) {}
+
+    /// DECCARA and DECRARA change of the attributes in a rectangle.
+    fn change_rectangle_attributes(&mut self, _area: Rectangle, _change: AttributeChange) {}
+
+    /// DECSACE selection between rectangle and stream extent for attribute changes.
+    fn set_attribute_change_extent(&mut self, _rectangle: bool) {}
+
+    /// DECSCA protection of new characters against selective erase.
+    fn set_character_protection(&mut self, _protected: bool) {}
+
+    /// Check if DECLRMM is enabled, turning `CSI s` into DECSLRM.
+    fn left_right_margin_mode(&self) -> bool {
+        false
+    }
+
+    /// DECSLRM left and right margins.
+    fn set_horizontal_margins(&mut self, _left: usize, _right: Option<usize>) {}
+
+    /// DECSWL, DECDWL and DECDHL attribute for the cursor line.
+    fn set_line_attribute(&mut self, _attribute: LineAttribute) {}
+
+    /// XTGETTCAP request for hex-encoded terminfo capability names, separated by semicolons.
+    fn report_terminfo_capabilities(&mut self, _names: String) {}
+
+    /// DECRQSS request for the value of a setting.
+    fn report_setting(&mut self, _setting: String) {}
+
+    /// OSC 9 and OSC 777 desktop notification.
+    fn desktop_notification(&mut self, _notification: Notification) {}
+
+    /// OSC 99 chunk of a kitty desktop notification.
+    fn desktop_notification_chunk(&mut self, _chunk: NotificationChunk) {}
+
+    /// OSC 9 ; 4 progress report, `None` to remove it.
+    fn set_progress(&mut self, _progress: Option<Progress>) {}
+}
+
+/// Maximum number of bytes in an APC string.
+const MAX_APC_SIZE: usize = kitty::MAX_CHUNK_SIZE;
+
+/// Maximum number of bytes in a DCS query.
+const MAX_QUERY_SIZE: usize = 1024;
+
+/// Maximum time before a synchronized update is aborted.
+const SYNC_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Maximum number of bytes read in one synchronized update (2MiB).
+const SYNC_BUFFER_SIZE: usize = 0x20_0000;
+
+/// Number of bytes in the BSU/ESU CSI sequences.
+const SYNC_ESCAPE_LEN: usize = 8;
+
+/// BSU CSI sequence for beginning or extending synchronized updates.
+const BSU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026h";
+
+/// ESU CSI sequence for terminating synchronized updates.
+const ESU_CSI: [u8; SYNC_ESCAPE_LEN] = *b"\x1b[?2026l";
+
+/// Escape sequence handled by this module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Sequence {
+    SemanticMark(SemanticMarkKind),
+    WorkingDirectory(WorkingDirectory),
+    Sixel(Image),
+    KittyGraphics(kitty::Command),
+    CopyRectangle(Rectangle, usize, usize),
+    FillRectangle(Rectangle, char),
+    EraseRectangle(Rectangle),
+    SelectiveEraseRectangle(Rectangle),
+    ChangeRectangleAttributes(Rectangle, AttributeChange),
+    AttributeChangeExtent(bool),
+    CharacterProtection(bool),
+    HorizontalMargins(usize, Option<usize>),
+    BeginSync(Vec<u16>),
+    LineAttribute(LineAttribute),
+    TerminfoRequest(String),
+    SettingRequest(String),
+    Notification(Notification),
+    NotificationChunk(NotificationChunk),
+    Progress(Option<Progress>),
+}
+
+/// The processor wraps an [`ansi::Processor`] to additionally dispatch extended sequences.
+#[derive(Default)]
+pub struct Processor<T: Timeout = StdSyncHandler> {
+    inner: ansi::Processor<T>,
+    scanner: vte::Parser,
+    performer: Performer,
+    apc: ApcScanner,
+    sync_state: SyncState<T>,
+}
+
+/// State for synchronized terminal updates.
+#[derive(Default)]
+struct SyncState<T: Timeout> {
+    /// Handler for synchronized updates.
+    timeout: T,
+
+    /// Bytes read during the synchronized update.
+    buffer: Vec<u8>,
+}
+
+impl<T: Timeout> Processor<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Synchronized update timeout.
+    #[inline]
+    pub fn sync_timeout(&self) -> &T {
+        &self.sync_state.timeout
+    }
+
+    /// Process new bytes from the PTY.
+    pub fn advance<H: Handler>(&mut self, handler: &mut H, bytes: &[u8]) {
+        let mut processed = 0;
+        while processed != bytes.len() {
+            if self.sync_state.timeout.pending_timeout() {
+                processed += self.advance_sync(handler, &bytes[processed..]);
+            } else {
+                processed += self.advance_parsers(handler, &bytes[processed..], false);
+            }
+        }
+    }
+
+    /// Process bytes outside of a synchronized update.
+    ///
+    /// Returns the number of bytes processed, which is less than `bytes` only if a synchronized
+    /// update was started. While `replay` is set, no new synchronized update is started.
+    fn advance_parsers<H: Handler>(
+        &mut self,
+        handler: &mut H,
+        bytes: &[u8],
+        replay: bool,
+    ) -> usize {
+        let mut processed = 0;
+        let mut apc_end = 0;
+        while processed != bytes.len() {
+            // Stop the parsers at the end of the next APC string.
+            if apc_end <= processed {
+                let remaining = &bytes[processed..];
+                apc_end = processed + self.apc.terminator(remaining).unwrap_or(remaining.len());
+            }
+
+            let consumed = self
+                .scanner
+                .advance_until_terminated(&mut self.performer, &bytes[processed..apc_end]);
+            let consumed_bytes = &bytes[processed..processed + consumed];
+            if let Some(apc) = self.apc.advance(consumed_bytes) {
+                self.performer.pending = kitty::Command::parse(&apc).map(Sequence::KittyGraphics);
+            }
+            match self.performer.pending {
+                Some(Sequence::HorizontalMargins(..)) => {
+                    self.advance_margins(handler, consumed_bytes)
+                },
+                Some(Sequence::BeginSync(_)) => self.cancel_final_byte(handler, consumed_bytes),
+                _ => self.inner.advance(handler, consumed_bytes),
+            }
+            processed += consumed;
+
+            if let Some(sequence) = self.performer.pending.take() {
+                match sequence {
+                    Sequence::SemanticMark(kind) => handler.semantic_mark(kind),
+                    Sequence::WorkingDirectory(cwd) => handler.set_working_directory(cwd),
+                    Sequence::Sixel(image) => handler.sixel_graphic(image),
+                    Sequence::KittyGraphics(command) => handler.kitty_graphics(command),
+                    Sequence::CopyRectangle(source, top, left) => {
+                        handler.copy_rectangle(source, top, left)
+                    },
+                    Sequence::FillRectangle(area, c) => handler.fill_rectangle(area, c),
+                    Sequence::EraseRectangle(area) => handler.erase_rectangle(area),
+                    Sequence::SelectiveEraseRectangle(area) => {
+                        handler.selective_erase_rectangle(area)
+                    },
+                    Sequence::ChangeRectangleAttributes(area, change) => {
+                        handler.change_rectangle_attributes(area, change)
+                    },
+                    Sequence::AttributeChangeExtent(rectangle) => {
+                        handler.set_attribute_change_extent(rectangle)
+                    },
+                    Sequence::CharacterProtection(protected) => {
+                        handler.set_character_protection(protected)
+                    },
+                    Sequence::HorizontalMargins(left, right) => {
+                        handler.set_horizontal_margins(left, right)
+                    },
+                    Sequence::BeginSync(modes) => {
+                        // Other modes set by the same sequence are passed on to the VTE parser.
+                        for mode in modes {
+                            if mode == NamedPrivateMode::SyncUpdate as u16 {
+                                handler.set_private_mode(NamedPrivateMode::SyncUpdate.into());
+                            } else {
+                                self.inner.advance(handler, format!("\x1b[?{mode}h").as_bytes());
+                            }
+                        }
+
+                        if !replay {
+                            self.sync_state.timeout.set_timeout(SYNC_UPDATE_TIMEOUT);
+                            return processed;
+                        }
+                    },
+                    Sequence::LineAttribute(attribute) => handler.set_line_attribute(attribute),
+                    Sequence::TerminfoRequest(names) => handler.report_terminfo_capabilities(names),
+                    Sequence::SettingRequest(setting) => handler.report_setting(setting),
+                    Sequence::Notification(notification) => {
+                        handler.desktop_notification(notification)
+                    },
+                    Sequence::NotificationChunk(chunk) => handler.desktop_notification_chunk(chunk),
+                    Sequence::Progress(progress) => handler.set_progress(progress),
+                }
+            }
+        }
+
+        processed
+    }
+
+    /// Process bytes during a synchronized update.
+    ///
+    /// Returns the number of bytes processed.
+    #[cold]
+    fn advance_sync<H: Handler>(&mut self, handler: &mut H, bytes: &[u8]) -> usize {
+        // Stop sync if we'd exceed the maximum buffer size, parsing the bytes normally.
+        if self.sync_state.buffer.len() + bytes.len() >= SYNC_BUFFER_SIZE - 1 {
+            self.stop_sync(handler);
+            return 0;
+        }
+
+        self.sync_state.buffer.extend(bytes);
+
+        // Get constraints within which a new escape character might be relevant.
+        let buffer_len = self.sync_state.buffer.len();
+        let start_offset = (buffer_len - bytes.len()).saturating_sub(SYNC_ESCAPE_LEN - 1);
+        let end_offset = buffer_len.saturating_sub(SYNC_ESCAPE_LEN - 1);
+        let search_buffer = &self.sync_state.buffer[start_offset..end_offset];
+
+        // Search for termination/extension escapes in the added bytes.
+        //
+        // NOTE: Like the VTE parser, only EXACTLY `\e[?2026h`/`\e[?2026l` are recognized here.
+        let mut bsu_offset = None;
+        let escapes = (0..search_buffer.len()).rev().filter(|&index| search_buffer[index] == 0x1b);
+        for index in escapes {
+            let offset = start_offset + index;
+            let escape = &self.sync_state.buffer[offset..offset + SYNC_ESCAPE_LEN];
+
+            if escape == BSU_CSI {
+                self.sync_state.timeout.set_timeout(SYNC_UPDATE_TIMEOUT);
+                bsu_offset = Some(offset);
+            } else if escape == ESU_CSI {
+                self.stop_sync_internal(handler, bsu_offset);
+                break;
+            }
+        }
+
+        bytes.len()
+    }
+
+    /// End a synchronized update.
+    ///
+    /// The `bsu_offset` is the start of a new BSU escape in the sync buffer, which begins the
+    /// next synchronized update.
+    fn stop_sync_internal<H: Handler>(&mut self, handler: &mut H, bsu_offset: Option<usize>) {
+        // Process all synchronized bytes.
+        let buffer = mem::take(&mut self.sync_state.buffer);
+        let offset = bsu_offset.unwrap_or(buffer.len());
+        self.advance_parsers(handler, &buffer[..offset], true);
+        self.sync_state.buffer = buffer;
+
+        match bsu_offset {
+            // Just clear processed bytes if there is a new BSU.
+            Some(bsu_offset) => {
+                let new_len = self.sync_state.buffer.len() - bsu_offset;
+                self.sync_state.buffer.copy_within(bsu_offset.., 0);
+                self.sync_state.buffer.truncate(new_len);
+            },
+            // Report mode and clear state if no new BSU is present.
+            None => {
+                handler.unset_private_mode(NamedPrivateMode::SyncUpdate.into());
+                self.sync_state.timeout.clear_timeout();
+                self.sync_state.buffer.clear();
+            },
+        }
+    }
+
+    /// Advance the VTE parser over a sequence, cancelling it before it is dispatched.
+    fn cancel_final_byte<H: Handler>(&mut self, handler: &mut H, bytes: &[u8]) {
+        self.inner.advance(handler, &bytes[..bytes.len() - 1]);
+        self.inner.advance(handler, &[0x18]);
+    }
+
+    /// Advance the VTE parser over a `CSI s` sequence.
+    ///
+    /// Depending on DECLRMM, this is either DECSLRM or the VTE parser's save cursor. When it
+    /// sets the margins, the sequence is cancelled in the VTE parser before it is dispatched.
+    ///
+    /// Since synchronized updates are only replayed through the parsers once they end, all
+    /// previous bytes have been processed by the time the mode is checked.
+    fn advance_margins<H: Handler>(&mut self, handler: &mut H, bytes: &[u8]) {
+        let (sequence, final_byte) = bytes.split_at(bytes.len() - 1);
+        self.inner.advance(handler, sequence);
+
+        if handler.left_right_margin_mode() {
+            self.inner.advance(handler, &[0x18]);
+        } else {
+            self.inner.advance(handler, final_byte);
+            self.performer.pending = None;
+        }
+    }
+
+    /// End a synchronized update.
+    #[inline]
+    pub fn stop_sync<H: Handler>(&mut self, handler: &mut H) {
+        self.stop_sync_internal(handler, None);
+    }
+
+    /// Number of bytes in the synchronization buffer.
+    #[inline]
+    pub fn sync_bytes_count(&self) -> usize {
+        self.sync_state.buffer.len()
+    }
+}
+
+/// Performer for the scanner, which records extended sequences.
+#[derive(Default)]
+struct Performer {
+    /// Sequence waiting to be dispatched to the handler.
+    pending: Option<Sequence>,
+
+    /// Decoder for the active sixel DCS sequence.
+    sixel: Option<Box<sixel::Parser>>,
+
+    /// Active DCS query, collecting its content.
+    query: Option<Sequence>,
+}
+
+impl Perform for Performer {
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        if action != 'q' || ignore {
+            return;
+        }
+
+        match intermediates {
+            [] => {
+                let params: Vec<u16> = params.iter().map(|param| param[0]).collect();
+                self.sixel = Some(Box::new(sixel::Parser::new(&params)));
+            },
+            [b'+'] => self.query = Some(Sequence::TerminfoRequest(String::new())),
+            [b'$'] => self.query = Some(Sequence::SettingRequest(String::new())),
+            _ => (),
+        }
+    }
+
+    fn put(&mut self, byte: u8) {
+        if let Some(sixel) = &mut self.sixel {
+            sixel.put(byte);
+        } else if let Some(Sequence::TerminfoRequest(query) | Sequence::SettingRequest(query)) =
+            &mut self.query
+        {
+            if query.len() < MAX_QUERY_SIZE {
+                query.push(byte as char);
+            }
+        }
+    }
+
+    fn unhook(&mut self) {
+        if let Some(sixel) = self.sixel.take() {
+            self.pending = sixel.finish().map(Sequence::Sixel);
+        } else if let Some(query) = self.query.take() {
+            self.pending = Some(query);
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        if ignore {
+            return;
+        }
+
+        let params: Vec<u16> = params.iter().map(|param| param[0]).collect();
+        let param = |index: usize| params.get(index).copied().unwrap_or(0);
+        let params_from = |index: usize| params.get(index..).unwrap_or_default();
+
+        self.pending = match (action, intermediates) {
+            ('v', [b'$']) => {
+                let (top, left) = (param(5) as usize, param(6) as usize);
+                Some(Sequence::CopyRectangle(Rectangle::from_params(&params), top, left))
+            },
+            ('x', [b'$']) => char::from_u32(param(0) as u32)
+                .map(|c| Sequence::FillRectangle(Rectangle::from_params(params_from(1)), c)),
+            ('z', [b'$']) => Some(Sequence::EraseRectangle(Rectangle::from_params(&params))),
+            ('{', [b'$']) => {
+                Some(Sequence::SelectiveEraseRectangle(Rectangle::from_params(&params)))
+            },
+            ('r', [b'$']) => Some(Sequence::ChangeRectangleAttributes(
+                Rectangle::from_params(&params),
+                AttributeChange::change(params_from(4)),
+            )),
+            ('t', [b'$']) => Some(Sequence::ChangeRectangleAttributes(
+                Rectangle::from_params(&params),
+                AttributeChange::reverse(params_from(4)),
+            )),
+            ('x', [b'*']) => Some(Sequence::AttributeChangeExtent(param(0) == 2)),
+            ('q', [b'"']) => Some(Sequence::CharacterProtection(param(0) == 1)),
+            // Synchronized updates are handled by the processor, not the VTE parser.
+            ('h', [b'?']) if params.contains(&(NamedPrivateMode::SyncUpdate as u16)) => {
+                Some(Sequence::BeginSync(params))
+            },
+            ('s', []) => {
+                let right = params.get(1).filter(|right| **right != 0);
+                Some(Sequence::HorizontalMargins(param(0) as usize, right.map(|r| *r as usize)))
+            },
+            _ => return,
+        };
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], ignore: bool, byte: u8) {
+        if ignore {
+            return;
+        }
+
+        self.pending = match (byte, intermediates) {
+            (b'3', [b'#']) => Some(Sequence::LineAttribute(LineAttribute::DoubleHeightTop)),
+            (b'4', [b'#']) => Some(Sequence::LineAttribute(LineAttribute::DoubleHeightBottom)),
+            (b'5', [b'#']) => Some(Sequence::LineAttribute(LineAttribute::Normal)),
+            (b'6', [b'#']) => Some(Sequence::LineAttribute(LineAttribute::DoubleWidth)),
+            _ => return,
+        };
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        let (kind, params) = match params.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+
+        match *kind {
+            b"133" => self.pending = SemanticMarkKind::parse(params).map(Sequence::SemanticMark),
+            // Semicolons are valid inside the URI, so the parameters need to be joined again.
+            b"7" => {
+                let uri = params.join(&b';');
+                self.pending = WorkingDirectory::parse(&uri).map(Sequence::WorkingDirectory);
+            },
+            // A lone `4` is a notification, not a progress report.
+            b"9" => match params {
+                [b"4", progress @ ..] if !progress.is_empty() => {
+                    self.pending = Progress::parse(progress).map(Sequence::Progress);
+                },
+                _ => self.pending = Notification::parse_osc9(params).map(Sequence::Notification),
+            },
+            b"777" => {
+                self.pending = Notification::parse_osc777(params).map(Sequence::Notification);
+            },
+            b"99" => {
+                self.pending = NotificationChunk::parse(params).map(Sequence::NotificationChunk);
+            },
+            _ => (),
+        }
+    }
+
+    #[inline(always)]
+    fn terminated(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+/// State of the [`ApcScanner`].
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum ApcState {
+    #[default]
+    Ground,
+    Escape,
+    String,
+    StringEscape,
+}
+
+impl ApcState {
+    /// Transition to the next state.
+    ///
+    /// Returns `true` if the byte terminated an APC string.
+    #[inline]
+    fn advance(&mut self, byte: u8) -> bool {
+        *self = match (*self, byte) {
+            (Self::StringEscape, b'\\') => {
+                *self = Self::Ground;
+                return true;
+            },
+            (Self::String, 0x1b) => Self::StringEscape,
+            (Self::String, 0x18 | 0x1a) => Self::Ground,
+            (Self::String, _) => Self::String,
+            (Self::Escape | Self::StringEscape, b'_') => Self::String,
+            (_, 0x1b) => Self::Escape,
+            _ => Self::Ground,
+        };
+
+        false
+    }
+}
+
+/// Scanner collecting the content of APC strings.
+#[derive(Default)]
+struct ApcScanner {
+    state: ApcState,
+    buffer: Vec<u8>,
+}
+
+impl ApcScanner {
+    /// Find the end of the next APC string, without advancing the scanner.
+    fn terminator(&self, bytes: &[u8]) -> Option<usize> {
+        let mut state = self.state;
+        bytes.iter().position(|&byte| state.advance(byte)).map(|index| index + 1)
+    }
+
+    /// Advance the scanner, returning the content of a terminated APC string.
+    fn advance(&mut self, bytes: &[u8]) -> Option<Vec<u8>> {
+        let mut apc = None;
+
+        for &byte in bytes {
+            let previous = self.state;
+            if self.state.advance(byte) {
+                apc = Some(std::mem::take(&mut self.buffer));
+            } else if self.state == ApcState::String {
+                if previous != ApcState::String {
+                    self.buffer.clear();
+                } else if self.buffer.len() < MAX_APC_SIZE {
+                    self.buffer.push(byte);
+                }
+            }
+        }
+
+        apc.filter(|apc| apc.len() < MAX_APC_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockHandler {
+        text: String,
+        marks: Vec<(usize, SemanticMarkKind)>,
+        images: Vec<(usize, Image)>,
+        kitty: Vec<(usize, kitty::Command)>,
+        margin_mode: bool,
+        margins: Vec<(usize, Option<usize>)>,
+        saved_cursor: usize,
+    }
+
+    impl ansi::Handler for MockHandler {
+        fn input(&mut self, c: char) {
+            self.text.push(c);
+        }
+
+        fn save_cursor_position(&mut self) {
+            self.saved_cursor += 1;
+        }
+
+        fn set_private_mode(&mut self, mode: ansi::PrivateMode) {
+            self.margin_mode |= mode.raw() == 69;
+        }
+    }
+
+    impl Handler for MockHandler {
+        fn semantic_mark(&mut self, kind: SemanticMarkKind) {
+            self.marks.push((self.text.len(), kind));
+        }
+
+        fn sixel_graphic(&mut self, image: Image) {
+            self.images.push((self.text.len(), image));
+        }
+
+        fn kitty_graphics(&mut self, command: kitty::Command) {
+            self.kitty.push((self.text.len(), command));
+        }
+
+        fn left_right_margin_mode(&self) -> bool {
+            self.margin_mode
+        }
+
+        fn set_horizontal_margins(&mut self, left: usize, right: Option<usize>) {
+            self.margins.push((left, right));
+        }
+    }
+
+    #[test]
+    fn save_cursor_or_margins() {
+        let mut processor: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        processor.advance(&mut handler, b"a\x1b[s\x1b[?69h\x1b[2;");
+        processor.advance(&mut handler, b"5sb\x1b[s\x1b[?2026h\x1b[?69hc\x1b[3s\x1b[?2026l");
+
+        assert_eq!(handler.text, "abc");
+        assert_eq!(handler.saved_cursor, 1);
+        assert_eq!(handler.margins, vec![(2, Some(5)), (0, None), (3, None)]);
+    }
+
+    #[test]
+    fn save_cursor_in_sync_update() {
+        let mut processor: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        processor.advance(&mut handler, b"\x1b[?2026ha\x1b[sb\x1b7");
+
+        assert_eq!(handler.text, "");
+        assert_eq!(handler.saved_cursor, 0);
+        assert!(processor.sync_timeout().pending_timeout());
+
+        processor.advance(&mut handler, b"\x1b[?2026l");
+
+        assert_eq!(handler.text, "ab");
+        assert_eq!(handler.saved_cursor, 2);
+        assert!(handler.margins.is_empty());
+    }
+
+    #[test]
+    fn semantic_marks_in_order() {
+        let mut processor: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        processor.advance(&mut handler, b"\x1b]133;A\x07$ \x1b]133;B\x1b\\ls\r\n\x1b]133;C\x07");
+        processor.advance(&mut handler, b"out\x1b]133;D;");
+        processor.advance(&mut handler, b"1\x07");
+
+        assert_eq!(handler.text, "$ lsout");
+        assert_eq!(handler.marks, vec![
+            (0, SemanticMarkKind::PromptStart),
+            (2, SemanticMarkKind::CommandStart),
+            (4, SemanticMarkKind::OutputStart),
+            (7, SemanticMarkKind::CommandFinished(Some(1))),
+        ]);
+    }
+
+    #[test]
+    fn semantic_marks_in_sync_update() {
+        let mut processor: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        processor.advance(&mut handler, b"a\x1b[?25;2026h\x1b]133;A\x07$ \x1b]133;B\x07ls");
+
+        assert_eq!(handler.text, "a");
+        assert!(handler.marks.is_empty());
+        assert!(processor.sync_timeout().pending_timeout());
+
+        processor.advance(&mut handler, b"\x1b[?2026l");
+
+        assert_eq!(handler.text, "a$ ls");
+        assert_eq!(handler.marks, vec![
+            (1, SemanticMarkKind::PromptStart),
+            (3, SemanticMarkKind::CommandStart),
+        ]);
+        assert!(!processor.sync_timeout().pending_timeout());
+        assert_eq!(processor.sync_bytes_count(), 0);
+    }
+
+    #[test]
+    fn sixel_in_order() {
+        let mut processor: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        processor.advance(&mut handler, b"a\x1bPq#0;2;100;0;0~~");
+        processor.advance(&mut handler, b"\x1b\\b\x1bP0;1q\x1b\\c");
+
+        assert_eq!(handler.text, "abc");
+        assert_eq!(handler.images.len(), 1);
+        assert_eq!(handler.images[0].0, 1);
+        assert_eq!((handler.images[0].1.width, handler.images[0].1.height), (2, 6));
+    }
+
+    #[test]
+    fn apc_in_order() {
+        let mut processor: Processor = Processor::new();
+        let mut handler = MockHandler::default();
+
+        processor.advance(&mut handler, b"a\x1b_Gi=1;AA\x1b\\b\x1b_Gi=2;");
+        processor.advance(&mut handler, b"BB\x1b");
+        processor.advance(&mut handler, b"\\\x1b]133;A\x07c\x1b_X\x1b\\\x1b_Gi=3\x18d");
+
+        assert_eq!(handler.text, "abcd");
+        assert_eq!(handler.marks, vec![(2, SemanticMarkKind::PromptStart)]);
+
+        let kitty: Vec<_> = handler
+            .kitty
+            .iter()
+            .map(|(position, command)| (*position, command.image_id, command.payload.clone()))
+            .collect();
+        assert_eq!(kitty, vec![(1, 1, b"AA".to_vec()), (2, 2, b"BB".to_vec())]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Recording of PTY output in the asciicast v2 format.
+//!
+//! Recordings can be played back with `asciinema play` or any other asciicast v2 player. See
+//! <https://docs.asciinema.org/manual/asciicast/v2/> for the format specification.
+
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::event::WindowSize;
+
+/// Writer of an asciicast v2 recording.
+#[derive(Debug)]
+pub struct Recorder<W: Write = File> {
+    writer: W,
+    start: Instant,
+
+    /// Trailing bytes of an incomplete UTF-8 sequence.
+    pending: Vec<u8>,
+}
+
+impl Recorder {
+    /// Create a recording file, replacing any existing file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P, size: WindowSize) -> io::Result<Self> {
+        Self::new(File::create(path)?, size)
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    /// Start a recording by writing its header.
+    pub fn new(mut writer: W, size: WindowSize) -> io::Result<Self> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |t| t.as_secs());
+        writeln!(
+            writer,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {timestamp}}}",
+            size.num_cols, size.num_lines,
+        )?;
+        writer.flush()?;
+
+        Ok(Self { writer, start: Instant::now(), pending: Vec::new() })
+    }
+
+    /// Record bytes written by the application.
+    ///
+    /// UTF-8 sequences split across calls are kept until they're complete, invalid UTF-8 is
+    /// replaced with `U+FFFD`.
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(bytes);
+
+        let complete = self.pending.len() - incomplete_utf8_len(&self.pending);
+        if complete == 0 {
+            return Ok(());
+        }
+
+        let data = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+
+        self.write_event("o", &data)
+    }
+
+    /// Record a change of the terminal dimensions.
+    pub fn resize(&mut self, size: WindowSize) -> io::Result<()> {
+        self.write_event("r", &format!("{}x{}", size.num_cols, size.num_lines))
+    }
+
+    fn write_event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        let mut line = format!("[{:.6}, \"{code}\", \"", self.start.elapsed().as_secs_f64());
+        push_json_escaped(&mut line, data);
+        line.push_str("\"]\n");
+
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.flush()
+    }
+}
+
+/// Length of an incomplete UTF-8 sequence at the end of `bytes`.
+fn incomplete_utf8_len(bytes: &[u8]) -> usize {
+    for len in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - len];
+
+        // Skip continuation bytes until the start of the last sequence.
+        if byte & 0b1100_0000 == 0b1000_0000 {
+            continue;
+        }
+
+        let expected = match byte {
+            0b1100_0000..=0b1101_1111 => 2,
+            0b1110_0000..=0b1110_1111 => 3,
+            0b1111_0000..=0b1111_0111 => 4,
+            _ => 1,
+        };
+        return if expected > len { len } else { 0 };
+    }
+
+    0
+}
+
+fn push_json_escaped(json: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() && (c as u32) < 0x80 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            },
+            c => json.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asciicast() {
+        let size =
+            |num_cols, num_lines| WindowSize { num_cols, num_lines, cell_width: 1, cell_height: 1 };
+
+        let mut recorder = Recorder::new(Vec::new(), size(80, 24)).unwrap();
+        recorder.output(b"a\"\\\x1b[m\r\n\xe6\xbc").unwrap();
+        recorder.output(b"\xa2\xff").unwrap();
+        recorder.resize(size(100, 30)).unwrap();
+
+        let recording = String::from_utf8(recorder.writer).unwrap();
+        let lines: Vec<_> = recording.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert!(
+            lines[0].starts_with("{\"version\": 2, \"width\": 80, \"height\": 24, \"timestamp\": ")
+        );
+        assert!(lines[1].ends_with(", \"o\", \"a\\\"\\\\\\u001b[m\\r\\n\"]"));
+        assert!(lines[2].ends_with(", \"o\", \"漢\u{fffd}\"]"));
+        assert!(lines[3].ends_with(", \"r\", \"100x30\"]"));
+    }
+}
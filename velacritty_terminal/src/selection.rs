@@ -95,6 +95,7 @@ pub enum SelectionType {
     Block,
     Semantic,
     Lines,
+    CommandOutput,
 }
 
 /// Describes a region of a 2-dimensional area.
@@ -103,7 +104,8 @@ pub enum SelectionType {
 /// [`simple`], [`block`], [`semantic`], and [`lines`]. The [`simple`] mode precisely tracks which
 /// cells are selected without any expansion. [`block`] will select rectangular regions.
 /// [`semantic`] mode expands the initial selection to the nearest semantic escape char in either
-/// direction. [`lines`] will always select entire lines.
+/// direction. [`lines`] will always select entire lines. [`command_output`] selects the output of
+/// all shell commands between its points, using the OSC 133 shell integration marks.
 ///
 /// Calls to [`update`] operate different based on the selection kind. The [`simple`] and [`block`]
 /// mode do nothing special, simply track points and sides. [`semantic`] will continue to expand
@@ -114,6 +116,7 @@ pub enum SelectionType {
 /// [`block`]: enum.Selection.html#method.block
 /// [`semantic`]: enum.Selection.html#method.semantic
 /// [`lines`]: enum.Selection.html#method.lines
+/// [`command_output`]: enum.Selection.html#method.command_output
 /// [`update`]: enum.Selection.html#method.update
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Selection {
@@ -220,7 +223,7 @@ impl Selection {
                         && start.side == Side::Left
                         && end.side == Side::Right)
             },
-            SelectionType::Semantic | SelectionType::Lines => false,
+            SelectionType::Semantic | SelectionType::Lines | SelectionType::CommandOutput => false,
         }
     }
 
@@ -292,9 +295,19 @@ impl Selection {
             SelectionType::Block => self.range_block(start, end),
             SelectionType::Semantic => Some(Self::range_semantic(term, start.point, end.point)),
             SelectionType::Lines => Some(Self::range_lines(term, start.point, end.point)),
+            SelectionType::CommandOutput => {
+                Self::range_command_output(term, start.point, end.point)
+            },
         }
     }
 
+    fn range_command_output<T>(term: &Term<T>, start: Point, end: Point) -> Option<SelectionRange> {
+        let start = *term.command_output(start)?.start();
+        let end = *term.command_output(end)?.end();
+
+        Some(SelectionRange { start, end, is_block: false })
+    }
+
     fn range_semantic<T>(term: &Term<T>, mut start: Point, mut end: Point) -> SelectionRange {
         if start == end {
             if let Some(matching) = term.bracket_search(start) {
@@ -397,6 +410,7 @@ mod tests {
     use super::*;
 
     use crate::index::{Column, Point, Side};
+    use crate::term::shell_integration::{SemanticMark, SemanticMarkKind};
     use crate::term::test::TermSize;
     use crate::term::{Config, Term};
 
@@ -668,6 +682,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn command_output_selection() {
+        let mut term = term(10, 5);
+        let mark = |kind, column| SemanticMark::new(kind, Column(column));
+        term.grid_mut()[Line(0)].set_semantic_mark(mark(SemanticMarkKind::PromptStart, 0));
+        term.grid_mut()[Line(0)].set_semantic_mark(mark(SemanticMarkKind::CommandStart, 2));
+        term.grid_mut()[Line(1)].set_semantic_mark(mark(SemanticMarkKind::OutputStart, 0));
+        term.grid_mut()[Line(4)]
+            .set_semantic_mark(mark(SemanticMarkKind::CommandFinished(None), 0));
+        term.grid_mut()[Line(4)].set_semantic_mark(mark(SemanticMarkKind::PromptStart, 0));
+
+        let point = Point::new(Line(2), Column(3));
+        let selection = Selection::new(SelectionType::CommandOutput, point, Side::Left);
+        assert_eq!(
+            selection.to_range(&term).unwrap(),
+            SelectionRange {
+                start: Point::new(Line(1), Column(0)),
+                end: Point::new(Line(3), Column(4)),
+                is_block: false,
+            }
+        );
+
+        // Prompts are not part of any output.
+        let point = Point::new(Line(0), Column(3));
+        let selection = Selection::new(SelectionType::CommandOutput, point, Side::Left);
+        assert_eq!(selection.to_range(&term), None);
+
+        assert_eq!(
+            term.last_command_output(),
+            Some(Point::new(Line(1), Column(0))..=Point::new(Line(3), Column(4)))
+        );
+    }
+
     #[test]
     fn range_intersection() {
         let mut selection =
@@ -1,3 +1,4 @@
+use std::fmt::Write;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU32, Ordering};
 
@@ -5,9 +6,10 @@ use bitflags::bitflags;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::grid::{self, GridCell};
+use crate::graphics::GraphicCell;
+use crate::grid::{self, CompressibleCell, GridCell, SpillCell, SpillReader, SpillWriter};
 use crate::index::Column;
-use crate::vte::ansi::{Color, Hyperlink as VteHyperlink, NamedColor};
+use crate::vte::ansi::{Color, Hyperlink as VteHyperlink, NamedColor, Rgb};
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -30,6 +32,7 @@ bitflags! {
         const UNDERCURL                 = 0b0001_0000_0000_0000;
         const DOTTED_UNDERLINE          = 0b0010_0000_0000_0000;
         const DASHED_UNDERLINE          = 0b0100_0000_0000_0000;
+        const PROTECTED                 = 0b1000_0000_0000_0000;
         const ALL_UNDERLINES            = Self::UNDERLINE.bits() | Self::DOUBLE_UNDERLINE.bits()
                                         | Self::UNDERCURL.bits() | Self::DOTTED_UNDERLINE.bits()
                                         | Self::DASHED_UNDERLINE.bits();
@@ -126,6 +129,8 @@ pub struct CellExtra {
     zerowidth: Vec<char>,
     underline_color: Option<Color>,
     hyperlink: Option<Hyperlink>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    graphic: Option<GraphicCell>,
 }
 
 /// Content and attributes of a single cell in the terminal grid.
@@ -180,10 +185,9 @@ impl Cell {
     pub fn set_underline_color(&mut self, color: Option<Color>) {
         // If we reset color and we don't have zerowidth we should drop extra storage.
         if color.is_none()
-            && self
-                .extra
-                .as_ref()
-                .is_none_or(|extra| extra.zerowidth.is_empty() && extra.hyperlink.is_none())
+            && self.extra.as_ref().is_none_or(|extra| {
+                extra.zerowidth.is_empty() && extra.hyperlink.is_none() && extra.graphic.is_none()
+            })
         {
             self.extra = None;
         } else {
@@ -201,10 +205,11 @@ impl Cell {
     /// Set hyperlink.
     pub fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
         let should_drop = hyperlink.is_none()
-            && self
-                .extra
-                .as_ref()
-                .is_none_or(|extra| extra.zerowidth.is_empty() && extra.underline_color.is_none());
+            && self.extra.as_ref().is_none_or(|extra| {
+                extra.zerowidth.is_empty()
+                    && extra.underline_color.is_none()
+                    && extra.graphic.is_none()
+            });
 
         if should_drop {
             self.extra = None;
@@ -219,6 +224,77 @@ impl Cell {
     pub fn hyperlink(&self) -> Option<Hyperlink> {
         self.extra.as_ref()?.hyperlink.clone()
     }
+
+    /// Set the section of a graphic displayed in this cell.
+    pub fn set_graphic(&mut self, graphic: Option<GraphicCell>) {
+        let should_drop = graphic.is_none()
+            && self.extra.as_ref().is_none_or(|extra| {
+                extra.zerowidth.is_empty()
+                    && extra.underline_color.is_none()
+                    && extra.hyperlink.is_none()
+            });
+
+        if should_drop {
+            self.extra = None;
+        } else {
+            let extra = self.extra.get_or_insert(Default::default());
+            Arc::make_mut(extra).graphic = graphic;
+        }
+    }
+
+    /// Section of a graphic displayed in this cell.
+    #[inline]
+    pub fn graphic(&self) -> Option<&GraphicCell> {
+        self.extra.as_ref()?.graphic.as_ref()
+    }
+
+    /// SGR parameters which reproduce the cell's attributes after a reset.
+    pub(crate) fn sgr_parameters(&self) -> String {
+        let mut sgr = String::from("0");
+
+        let attributes = [
+            (Flags::BOLD, "1"),
+            (Flags::DIM, "2"),
+            (Flags::ITALIC, "3"),
+            (Flags::UNDERLINE, "4"),
+            (Flags::DOUBLE_UNDERLINE, "4:2"),
+            (Flags::UNDERCURL, "4:3"),
+            (Flags::DOTTED_UNDERLINE, "4:4"),
+            (Flags::DASHED_UNDERLINE, "4:5"),
+            (Flags::INVERSE, "7"),
+            (Flags::HIDDEN, "8"),
+            (Flags::STRIKEOUT, "9"),
+        ];
+        for (flag, param) in attributes {
+            if self.flags.contains(flag) {
+                sgr.push(';');
+                sgr.push_str(param);
+            }
+        }
+
+        push_color_param(&mut sgr, self.fg, 30, 90, 38);
+        push_color_param(&mut sgr, self.bg, 40, 100, 48);
+        if let Some(color) = self.underline_color() {
+            push_color_param(&mut sgr, color, 0, 0, 58);
+        }
+
+        sgr
+    }
+}
+
+/// Add the SGR parameter for a color, unless it is the default color.
+fn push_color_param(sgr: &mut String, color: Color, normal: u8, bright: u8, extended: u8) {
+    let _ = match color {
+        Color::Named(color) if normal != 0 && (color as usize) < 8 => {
+            write!(sgr, ";{}", normal + color as u8)
+        },
+        Color::Named(color) if normal != 0 && (color as usize) < 16 => {
+            write!(sgr, ";{}", bright + color as u8 - NamedColor::BrightBlack as u8)
+        },
+        Color::Named(_) => Ok(()),
+        Color::Indexed(index) => write!(sgr, ";{extended}:5:{index}"),
+        Color::Spec(rgb) => write!(sgr, ";{extended}:2::{}:{}:{}", rgb.r, rgb.g, rgb.b),
+    };
 }
 
 impl GridCell for Cell {
@@ -235,7 +311,10 @@ impl GridCell for Cell {
                     | Flags::WIDE_CHAR_SPACER
                     | Flags::LEADING_WIDE_CHAR_SPACER,
             )
-            && self.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) != Some(false)
+            && self
+                .extra
+                .as_ref()
+                .is_none_or(|extra| extra.zerowidth.is_empty() && extra.graphic.is_none())
     }
 
     #[inline]
@@ -254,6 +333,147 @@ impl GridCell for Cell {
     }
 }
 
+impl CompressibleCell for Cell {
+    #[inline]
+    fn character(&self) -> char {
+        self.c
+    }
+
+    #[inline]
+    fn set_character(&mut self, c: char) {
+        self.c = c;
+    }
+}
+
+impl SpillCell for Cell {
+    fn encode(&self, writer: &mut SpillWriter<'_>) {
+        writer.write_u32(self.c as u32);
+        encode_color(writer, self.fg);
+        encode_color(writer, self.bg);
+        writer.write_u16(self.flags.bits());
+
+        // Graphics are not written to disk, since their images are released with the row.
+        let extra = match &self.extra {
+            Some(extra) => extra,
+            None => return writer.write_u8(0),
+        };
+        writer.write_u8(1);
+
+        writer.write_u32(extra.zerowidth.len() as u32);
+        for c in &extra.zerowidth {
+            writer.write_u32(*c as u32);
+        }
+
+        match extra.underline_color {
+            Some(color) => {
+                writer.write_u8(1);
+                encode_color(writer, color);
+            },
+            None => writer.write_u8(0),
+        }
+
+        match &extra.hyperlink {
+            Some(hyperlink) => {
+                writer.write_u8(1);
+                writer.write_str(hyperlink.id());
+                writer.write_str(hyperlink.uri());
+            },
+            None => writer.write_u8(0),
+        }
+    }
+
+    fn decode(reader: &mut SpillReader<'_>) -> Option<Self> {
+        let mut cell = Cell {
+            c: char::from_u32(reader.read_u32()?)?,
+            fg: decode_color(reader)?,
+            bg: decode_color(reader)?,
+            flags: Flags::from_bits_retain(reader.read_u16()?),
+            extra: None,
+        };
+
+        if reader.read_u8()? == 0 {
+            return Some(cell);
+        }
+
+        for _ in 0..reader.read_u32()? {
+            cell.push_zerowidth(char::from_u32(reader.read_u32()?)?);
+        }
+
+        if reader.read_u8()? != 0 {
+            cell.set_underline_color(Some(decode_color(reader)?));
+        }
+
+        if reader.read_u8()? != 0 {
+            let id = reader.read_str()?;
+            let uri = reader.read_str()?;
+            cell.set_hyperlink(Some(Hyperlink::new(Some(id), uri.to_owned())));
+        }
+
+        Some(cell)
+    }
+}
+
+fn encode_color(writer: &mut SpillWriter<'_>, color: Color) {
+    match color {
+        Color::Named(named) => {
+            writer.write_u8(0);
+            writer.write_u16(named as u16);
+        },
+        Color::Spec(rgb) => {
+            writer.write_u8(1);
+            writer.write_u8(rgb.r);
+            writer.write_u8(rgb.g);
+            writer.write_u8(rgb.b);
+        },
+        Color::Indexed(index) => {
+            writer.write_u8(2);
+            writer.write_u8(index);
+        },
+    }
+}
+
+fn decode_color(reader: &mut SpillReader<'_>) -> Option<Color> {
+    let color = match reader.read_u8()? {
+        0 => Color::Named(match reader.read_u16()? {
+            0 => NamedColor::Black,
+            1 => NamedColor::Red,
+            2 => NamedColor::Green,
+            3 => NamedColor::Yellow,
+            4 => NamedColor::Blue,
+            5 => NamedColor::Magenta,
+            6 => NamedColor::Cyan,
+            7 => NamedColor::White,
+            8 => NamedColor::BrightBlack,
+            9 => NamedColor::BrightRed,
+            10 => NamedColor::BrightGreen,
+            11 => NamedColor::BrightYellow,
+            12 => NamedColor::BrightBlue,
+            13 => NamedColor::BrightMagenta,
+            14 => NamedColor::BrightCyan,
+            15 => NamedColor::BrightWhite,
+            256 => NamedColor::Foreground,
+            257 => NamedColor::Background,
+            258 => NamedColor::Cursor,
+            259 => NamedColor::DimBlack,
+            260 => NamedColor::DimRed,
+            261 => NamedColor::DimGreen,
+            262 => NamedColor::DimYellow,
+            263 => NamedColor::DimBlue,
+            264 => NamedColor::DimMagenta,
+            265 => NamedColor::DimCyan,
+            266 => NamedColor::DimWhite,
+            267 => NamedColor::BrightForeground,
+            268 => NamedColor::DimForeground,
+            _ => return None,
+        }),
+        1 => Color::Spec(Rgb { r: reader.read_u8()?, g: reader.read_u8()?, b: reader.read_u8()? }),
+        2 => Color::Indexed(reader.read_u8()?),
+        _ => return None,
+    };
+
+    Some(color)
+}
+
 impl From<Color> for Cell {
     #[inline]
     fn from(color: Color) -> Self {
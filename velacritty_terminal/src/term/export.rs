@@ -0,0 +1,403 @@
+//! Export of the terminal content as plain text, ANSI escapes or HTML.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::grid::Dimensions;
+use crate::index::{Column, Line};
+use crate::selection::SelectionRange;
+use crate::term::Term;
+use crate::term::cell::{Cell, Flags, Hyperlink};
+use crate::term::color::Colors;
+use crate::vte::ansi::{Color, NamedColor, Rgb};
+
+/// Foreground color of inverse text when no background color is known.
+const INVERSE_FOREGROUND: Rgb = Rgb { r: 0x18, g: 0x18, b: 0x18 };
+
+/// Background color of inverse text when no foreground color is known.
+const INVERSE_BACKGROUND: Rgb = Rgb { r: 0xd8, g: 0xd8, b: 0xd8 };
+
+/// URI schemes which are exported as HTML links.
+const LINK_SCHEMES: [&str; 5] = ["http", "https", "ftp", "file", "mailto"];
+
+/// Format of exported text.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// Plain text, with trailing whitespace removed.
+    Text,
+
+    /// Text with SGR escapes reproducing the character attributes.
+    ///
+    /// Every line which changes attributes ends with a reset, so lines can be used separately.
+    Ansi,
+
+    /// HTML `<pre>` element, using inline styles for the attributes and links for hyperlinks.
+    #[default]
+    Html,
+}
+
+impl<T> Term<T> {
+    /// Export the text inside a range, with one line per row.
+    ///
+    /// Like selections, rows which wrap onto the next row are joined into a single line.
+    ///
+    /// Colors are resolved through the terminal's dynamic colors first, then through `palette`.
+    /// Colors missing from both are left to the reader of the export.
+    pub fn export(&self, range: &SelectionRange, format: Format, palette: &Colors) -> String {
+        let columns = |line: Line| {
+            let start = if range.is_block || line == range.start.line {
+                range.start.column
+            } else {
+                Column(0)
+            };
+            let end = if range.is_block || line == range.end.line {
+                range.end.column
+            } else {
+                self.last_column()
+            };
+            start..end + 1
+        };
+
+        let lines = range.start.line.0..range.end.line.0 + 1;
+        self.export_rows(lines.map(Line).map(|line| (line, columns(line))), format, palette)
+    }
+
+    /// Export complete lines.
+    pub(crate) fn export_lines(
+        &self,
+        lines: Range<Line>,
+        format: Format,
+        palette: &Colors,
+    ) -> String {
+        let columns = Column(0)..Column(self.columns());
+        let rows = (lines.start.0..lines.end.0).map(|line| (Line(line), columns.clone()));
+        self.export_rows(rows, format, palette)
+    }
+
+    fn export_rows(
+        &self,
+        rows: impl Iterator<Item = (Line, Range<Column>)>,
+        format: Format,
+        palette: &Colors,
+    ) -> String {
+        let mut exporter = Exporter { term: self, palette, text: String::new() };
+
+        if format == Format::Html {
+            let style = exporter.default_style();
+            if style.is_empty() {
+                exporter.text.push_str("<pre>");
+            } else {
+                let _ = write!(exporter.text, "<pre style=\"{style}\">");
+            }
+        }
+
+        // Rows are only decompressed temporarily, to avoid caching the entire history.
+        let mut rows = rows.peekable();
+        while let Some((line, columns)) = rows.next() {
+            let last = rows.peek().is_none();
+            self.grid.with_row(line, |row| {
+                let wrapped = !last
+                    && columns.end.0 == self.columns()
+                    && row[self.last_column()].flags.contains(Flags::WRAPLINE);
+
+                let cells = visible_cells(&row[columns], wrapped);
+                match format {
+                    Format::Text => exporter.text_line(cells, wrapped),
+                    Format::Ansi => exporter.ansi_line(cells),
+                    Format::Html => exporter.html_line(cells),
+                }
+
+                if !wrapped {
+                    exporter.text.push('\n');
+                }
+            });
+        }
+
+        if format == Format::Html {
+            exporter.text.push_str("</pre>");
+        }
+
+        exporter.text
+    }
+}
+
+/// Exported text which is being built.
+struct Exporter<'a, T> {
+    term: &'a Term<T>,
+    palette: &'a Colors,
+    text: String,
+}
+
+impl<T> Exporter<'_, T> {
+    fn text_line<'a>(&mut self, cells: impl Iterator<Item = &'a Cell>, wrapped: bool) {
+        let start = self.text.len();
+        for cell in cells {
+            push_cell_text(&mut self.text, cell);
+        }
+
+        if !wrapped {
+            let len = self.text[start..].trim_end_matches(' ').len();
+            self.text.truncate(start + len);
+        }
+    }
+
+    fn ansi_line<'a>(&mut self, cells: impl Iterator<Item = &'a Cell>) {
+        let mut sgr = String::from("0");
+        for cell in cells {
+            let cell_sgr = cell.sgr_parameters();
+            if cell_sgr != sgr {
+                let _ = write!(self.text, "\x1b[{cell_sgr}m");
+                sgr = cell_sgr;
+            }
+
+            push_cell_text(&mut self.text, cell);
+        }
+
+        if sgr != "0" {
+            self.text.push_str("\x1b[0m");
+        }
+    }
+
+    fn html_line<'a>(&mut self, cells: impl Iterator<Item = &'a Cell>) {
+        let mut run: (String, Option<Hyperlink>) = Default::default();
+        let mut cell_text = String::new();
+        for cell in cells {
+            // Hyperlinks with other schemes, like `javascript:`, are exported as plain text.
+            let hyperlink = cell.hyperlink().filter(|hyperlink| is_link_uri(hyperlink.uri()));
+            let cell_run = (self.css(cell), hyperlink);
+            if cell_run != run {
+                self.close_run(&run);
+
+                if let Some(hyperlink) = &cell_run.1 {
+                    self.text.push_str("<a href=\"");
+                    push_html_escaped(&mut self.text, hyperlink.uri());
+                    self.text.push_str("\">");
+                }
+                if !cell_run.0.is_empty() {
+                    let _ = write!(self.text, "<span style=\"{}\">", cell_run.0);
+                }
+
+                run = cell_run;
+            }
+
+            cell_text.clear();
+            push_cell_text(&mut cell_text, cell);
+            push_html_escaped(&mut self.text, &cell_text);
+        }
+
+        self.close_run(&run);
+    }
+
+    /// Close the HTML elements of a run of cells with the same attributes.
+    fn close_run(&mut self, (style, hyperlink): &(String, Option<Hyperlink>)) {
+        if !style.is_empty() {
+            self.text.push_str("</span>");
+        }
+        if hyperlink.is_some() {
+            self.text.push_str("</a>");
+        }
+    }
+
+    /// Inline CSS for the default colors.
+    fn default_style(&self) -> String {
+        let mut css = String::new();
+        if let Some(fg) = self.color(NamedColor::Foreground as usize) {
+            push_css_color(&mut css, "color", fg);
+        }
+        if let Some(bg) = self.color(NamedColor::Background as usize) {
+            push_css_color(&mut css, "background-color", bg);
+        }
+        css
+    }
+
+    /// Inline CSS for a cell's attributes.
+    fn css(&self, cell: &Cell) -> String {
+        let mut fg = self.rgb(cell.fg);
+        let mut bg = self.rgb(cell.bg);
+        if cell.flags.contains(Flags::INVERSE) {
+            let default_fg = self.color(NamedColor::Foreground as usize);
+            let default_bg = self.color(NamedColor::Background as usize);
+            (fg, bg) = (
+                bg.or(default_bg).or(Some(INVERSE_FOREGROUND)),
+                fg.or(default_fg).or(Some(INVERSE_BACKGROUND)),
+            );
+        }
+
+        let mut css = String::new();
+        if let Some(fg) = fg {
+            push_css_color(&mut css, "color", fg);
+        }
+        if let Some(bg) = bg {
+            push_css_color(&mut css, "background-color", bg);
+        }
+        if cell.flags.contains(Flags::BOLD) {
+            css.push_str("font-weight:bold;");
+        }
+        if cell.flags.contains(Flags::ITALIC) {
+            css.push_str("font-style:italic;");
+        }
+        if cell.flags.contains(Flags::DIM) {
+            css.push_str("opacity:0.66;");
+        }
+        if cell.flags.contains(Flags::HIDDEN) {
+            css.push_str("visibility:hidden;");
+        }
+
+        match (cell.flags.intersects(Flags::ALL_UNDERLINES), cell.flags.contains(Flags::STRIKEOUT))
+        {
+            (true, true) => css.push_str("text-decoration:underline line-through;"),
+            (true, false) => css.push_str("text-decoration:underline;"),
+            (false, true) => css.push_str("text-decoration:line-through;"),
+            (false, false) => (),
+        }
+
+        css
+    }
+
+    /// Resolve a cell color, returning `None` for the default foreground and background.
+    fn rgb(&self, color: Color) -> Option<Rgb> {
+        let index = match color {
+            Color::Spec(rgb) => return Some(rgb),
+            Color::Indexed(index) => index as usize,
+            Color::Named(
+                NamedColor::Foreground
+                | NamedColor::BrightForeground
+                | NamedColor::DimForeground
+                | NamedColor::Background,
+            ) => return None,
+            Color::Named(color) if (color as usize) < 16 => color as usize,
+            // Dim colors use the same color as their normal variant.
+            Color::Named(color) => color as usize - NamedColor::DimBlack as usize,
+        };
+
+        self.color(index)
+    }
+
+    /// Color at an index of the terminal's color table.
+    fn color(&self, index: usize) -> Option<Rgb> {
+        self.term.colors()[index].or(self.palette[index])
+    }
+}
+
+/// Cells which are drawn, without spacers.
+///
+/// Trailing blank cells are only kept when the row wraps, since they separate its text from the
+/// next row.
+fn visible_cells(cells: &[Cell], wrapped: bool) -> impl Iterator<Item = &Cell> {
+    let len = if wrapped {
+        cells.len()
+    } else {
+        cells
+            .iter()
+            .rposition(|cell| {
+                cell.c != ' '
+                    || cell.bg != Color::Named(NamedColor::Background)
+                    || cell.zerowidth().is_some_and(|zerowidth| !zerowidth.is_empty())
+                    || cell
+                        .flags
+                        .intersects(Flags::INVERSE | Flags::ALL_UNDERLINES | Flags::STRIKEOUT)
+            })
+            .map_or(0, |column| column + 1)
+    };
+
+    cells[..len].iter().filter(|cell| {
+        !cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+    })
+}
+
+/// Add the characters of a cell to a string.
+fn push_cell_text(text: &mut String, cell: &Cell) {
+    text.push(if cell.c == '\t' { ' ' } else { cell.c });
+    text.extend(cell.zerowidth().into_iter().flatten());
+}
+
+/// Check if a URI uses one of the [`LINK_SCHEMES`].
+fn is_link_uri(uri: &str) -> bool {
+    uri.split_once(':').is_some_and(|(scheme, _)| {
+        LINK_SCHEMES.iter().any(|link_scheme| scheme.eq_ignore_ascii_case(link_scheme))
+    })
+}
+
+fn push_css_color(css: &mut String, property: &str, color: Rgb) {
+    let _ = write!(css, "{property}:#{:02x}{:02x}{:02x};", color.r, color.g, color.b);
+}
+
+fn push_html_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::event::VoidListener;
+    use crate::index::Point;
+    use crate::parser::Processor;
+    use crate::term::Config;
+    use crate::term::test::TermSize;
+
+    #[test]
+    fn export_range() {
+        let size = TermSize::new(6, 2);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, b"abcdef\x1b]8;;https://a.b/?x&y\x07gh\x1b]8;;\x07ij");
+
+        let mut palette = Colors::default();
+        palette[NamedColor::Foreground] = Some(Rgb { r: 255, g: 255, b: 255 });
+
+        let start = Point::new(Line(0), Column(2));
+        let end = Point::new(Line(1), Column(2));
+
+        let range = SelectionRange::new(start, end, false);
+        assert_eq!(term.export(&range, Format::Text, &palette), "cdefghi\n");
+
+        let range = SelectionRange::new(start, end, true);
+        assert_eq!(term.export(&range, Format::Text, &palette), "c\ni\n");
+
+        let range = SelectionRange::new(Point::new(Line(1), Column(0)), end, false);
+        assert_eq!(
+            term.export(&range, Format::Html, &palette),
+            "<pre style=\"color:#ffffff;\"><a href=\"https://a.b/?x&amp;y\">gh</a>i\n</pre>"
+        );
+    }
+
+    #[test]
+    fn export_unsafe_link() {
+        let size = TermSize::new(6, 1);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, b"\x1b]8;;javascript:alert(1)\x07ab\x1b]8;;\x07");
+        parser.advance(&mut term, b"\x1b]8;;HTTPS://a.b\x07cd\x1b]8;;\x07");
+
+        let palette = Colors::default();
+        assert_eq!(
+            term.export_lines(Line(0)..Line(1), Format::Html, &palette),
+            "<pre>ab<a href=\"HTTPS://a.b\">cd</a>\n</pre>"
+        );
+    }
+
+    #[test]
+    fn export_wrapped_lines() {
+        let size = TermSize::new(5, 4);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: Processor = Processor::new();
+        parser.advance(&mut term, b"abcdefghij\r\nab cd ef");
+
+        let palette = Colors::default();
+        let lines = Line(0)..Line(4);
+        assert_eq!(
+            term.export_lines(lines.clone(), Format::Text, &palette),
+            "abcdefghij\nab cd ef\n"
+        );
+        assert_eq!(term.export_lines(lines, Format::Ansi, &palette), "abcdefghij\nab cd ef\n");
+    }
+}
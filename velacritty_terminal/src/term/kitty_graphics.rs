@@ -0,0 +1,505 @@
+//! Placement and deletion of kitty graphics protocol images.
+
+use std::cmp;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use log::{debug, trace};
+
+use crate::event::{Event, EventListener};
+use crate::graphics::kitty::{Action, Command, Error};
+use crate::graphics::{GraphicCell, Image, PlacementId};
+use crate::grid::Dimensions;
+use crate::index::{Column, Line, Point};
+use crate::term::Term;
+use crate::vte::ansi::Handler;
+
+impl<T: EventListener> Term<T> {
+    /// Handle a kitty graphics protocol command.
+    pub(super) fn kitty_graphics_command(&mut self, command: Command) {
+        let mut command = match self.kitty_images.add_chunk(command) {
+            Some(command) => command,
+            None => return,
+        };
+
+        trace!("Kitty graphics command: {:?}", command.action);
+
+        // Clients which don't pass any ID don't expect a response.
+        let anonymous = command.image_id == 0 && command.image_number == 0;
+
+        let result = match command.action {
+            Action::Transmit | Action::TransmitAndDisplay | Action::Query => {
+                self.kitty_transmit(&mut command)
+            },
+            Action::Put => self.kitty_put(&mut command),
+            Action::Delete => {
+                self.kitty_delete(&command);
+                return;
+            },
+            Action::Unsupported => Err(Error::new("EINVAL", "unsupported action")),
+        };
+
+        if let Err(err) = &result {
+            debug!("Kitty graphics command failed: {err}");
+        }
+
+        if let Some(response) = command.response(&result).filter(|_| !anonymous) {
+            self.event_proxy.send_event(Event::PtyWrite(response));
+        }
+    }
+
+    /// Load and store a transmitted image.
+    fn kitty_transmit(&mut self, command: &mut Command) -> Result<(), Error> {
+        // Older images are evicted to make space, so the entire budget is available.
+        let image = command.load_image(self.kitty_images.budget())?;
+
+        if command.action == Action::Query {
+            return Ok(());
+        }
+
+        if command.image_id == 0 {
+            command.image_id = self.kitty_images.next_id();
+        }
+
+        let (id, number) = (command.image_id, command.image_number);
+        self.kitty_images.insert(&mut self.graphics, id, number, image);
+
+        if command.action == Action::TransmitAndDisplay {
+            self.kitty_place(command)?;
+        }
+
+        Ok(())
+    }
+
+    /// Display a previously transmitted image.
+    fn kitty_put(&mut self, command: &mut Command) -> Result<(), Error> {
+        if command.image_id == 0 {
+            command.image_id = self
+                .kitty_images
+                .find_number(command.image_number)
+                .ok_or_else(|| Error::new("ENOENT", "image not found"))?;
+        }
+
+        self.kitty_place(command)
+    }
+
+    /// Place an image at the cursor position.
+    fn kitty_place(&mut self, command: &Command) -> Result<(), Error> {
+        let (cell_width, cell_height) = self.graphics.cell_size();
+        if cell_width == 0 || cell_height == 0 {
+            return Err(Error::new("EINVAL", "unknown cell size"));
+        }
+
+        let stored = self
+            .kitty_images
+            .get(command.image_id)
+            .ok_or_else(|| Error::new("ENOENT", "image not found"))?;
+        let image = stored.image.clone();
+        let full_graphic = stored.graphic.clone();
+
+        // Clamp the source rectangle to the image.
+        let x = cmp::min(command.source_x as usize, image.width);
+        let y = cmp::min(command.source_y as usize, image.height);
+        let width = match command.source_width as usize {
+            0 => image.width - x,
+            width => cmp::min(width, image.width - x),
+        };
+        let height = match command.source_height as usize {
+            0 => image.height - y,
+            height => cmp::min(height, image.height - y),
+        };
+        if width == 0 || height == 0 {
+            return Err(Error::new("EINVAL", "empty source rectangle"));
+        }
+
+        let graphic = if (x, y, width, height) == (0, 0, image.width, image.height) {
+            full_graphic
+        } else {
+            self.graphics.insert(Arc::new(crop(&image, x, y, width, height)))
+        };
+
+        // Size of the image on screen in pixels, scaling it to the requested cells.
+        //
+        // The requested cells are limited to the screen, since every line might scroll it.
+        let offset_x = cmp::min(command.cell_x_offset as usize, cell_width - 1);
+        let offset_y = cmp::min(command.cell_y_offset as usize, cell_height - 1);
+        let columns = cmp::min(command.columns as usize, self.columns());
+        let lines = cmp::min(command.lines as usize, self.screen_lines());
+        let (display_width, display_height) = match (columns, lines) {
+            (0, 0) => (width, height),
+            (columns, 0) => {
+                let display_width = columns * cell_width - offset_x;
+                (display_width, display_width * height / width)
+            },
+            (0, lines) => {
+                let display_height = lines * cell_height - offset_y;
+                (display_height * width / height, display_height)
+            },
+            (columns, lines) => (columns * cell_width - offset_x, lines * cell_height - offset_y),
+        };
+        let (display_width, display_height) = (display_width.max(1), display_height.max(1));
+
+        let columns = (offset_x + display_width).div_ceil(cell_width);
+        let lines = (offset_y + display_height).div_ceil(cell_height);
+        let scale_x = width as f64 / display_width as f64;
+        let scale_y = height as f64 / display_height as f64;
+
+        // Placements with an ID replace their previous location.
+        let placement = PlacementId { image: command.image_id, placement: command.placement_id };
+        if placement.placement != 0 {
+            self.kitty_delete_cells(|cell| cell.placement == Some(placement));
+        }
+
+        let start = self.grid.cursor.point;
+        let end_column = cmp::min(start.column.0 + columns, self.columns());
+        let mut line = start.line;
+        for image_line in 0..lines {
+            if image_line != 0 {
+                if command.move_cursor {
+                    // Scroll the screen to make space for the entire image.
+                    self.linefeed();
+                    line = self.grid.cursor.point.line;
+                } else if line + 1 >= self.screen_lines() {
+                    break;
+                } else {
+                    line += 1;
+                }
+            }
+
+            for column in start.column.0..end_column {
+                let cell_x = ((column - start.column.0) * cell_width) as f64 - offset_x as f64;
+                let cell_y = (image_line * cell_height) as f64 - offset_y as f64;
+                let graphic = GraphicCell {
+                    graphic: graphic.clone(),
+                    offset_x: (cell_x * scale_x).round() as i32,
+                    offset_y: (cell_y * scale_y).round() as i32,
+                    width: (cell_width as f64 * scale_x).round().max(1.) as u32,
+                    height: (cell_height as f64 * scale_y).round().max(1.) as u32,
+                    z_index: command.z_index,
+                    placement: Some(placement),
+                };
+                self.grid[line][Column(column)].set_graphic(Some(graphic));
+            }
+            self.grid[line].set_placements(true);
+        }
+
+        // Move the cursor behind the last line of the image.
+        if command.move_cursor {
+            let column = start.column.0 + columns;
+            self.grid.cursor.point.column = Column(cmp::min(column, self.columns() - 1));
+            self.grid.cursor.input_needs_wrap = column >= self.columns();
+        }
+
+        self.mark_fully_damaged();
+
+        Ok(())
+    }
+
+    /// Delete placements and images.
+    fn kitty_delete(&mut self, command: &Command) {
+        let free = command.delete.is_ascii_uppercase();
+        let (x, y) = (command.source_x as usize, command.source_y as usize);
+        let cursor = self.grid.cursor.point;
+
+        let mut image_ids = match command.delete.to_ascii_lowercase() {
+            b'a' => self.kitty_delete_visible(|_, _| true),
+            b'c' => self.kitty_delete_visible(|point, _| point == cursor),
+            b'p' => self.kitty_delete_visible(|point, _| is_cell(point, x, y)),
+            b'q' => self.kitty_delete_visible(|point, cell| {
+                is_cell(point, x, y) && cell.z_index == command.z_index
+            }),
+            b'x' => self.kitty_delete_visible(|point, _| point.column.0 + 1 == x),
+            b'y' => self.kitty_delete_visible(|point, _| point.line.0 + 1 == y as i32),
+            b'z' => self.kitty_delete_cells(|cell| cell.z_index == command.z_index),
+            b'i' | b'n' => {
+                let id = match command.image_id {
+                    0 => match self.kitty_images.find_number(command.image_number) {
+                        Some(id) => id,
+                        None => return,
+                    },
+                    id => id,
+                };
+
+                let placement_id = command.placement_id;
+                let mut image_ids = self.kitty_delete_cells(|cell| {
+                    cell.placement.is_some_and(|placement| {
+                        placement.image == id
+                            && (placement_id == 0 || placement.placement == placement_id)
+                    })
+                });
+                image_ids.insert(id);
+                image_ids
+            },
+            b'r' => {
+                let range = command.source_x..=command.source_y;
+                let mut image_ids = self.kitty_delete_cells(|cell| {
+                    cell.placement.is_some_and(|placement| range.contains(&placement.image))
+                });
+                if free {
+                    image_ids.extend(self.kitty_images.ids().filter(|id| range.contains(id)));
+                }
+                image_ids
+            },
+            _ => {
+                debug!("Unsupported kitty graphics delete: {}", command.delete as char);
+                return;
+            },
+        };
+
+        if free {
+            for id in image_ids.drain() {
+                self.kitty_images.remove(id);
+            }
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    /// Delete all placements which have a cell on the screen that matches the `filter`.
+    ///
+    /// Returns the IDs of all images with deleted placements.
+    fn kitty_delete_visible<F>(&mut self, filter: F) -> HashSet<u32>
+    where
+        F: Fn(Point, &GraphicCell) -> bool,
+    {
+        let mut placements = HashSet::new();
+        for line in (0..self.screen_lines() as i32).map(Line) {
+            for column in (0..self.columns()).map(Column) {
+                let point = Point::new(line, column);
+                let graphic = match self.grid[point].graphic() {
+                    Some(graphic) if filter(point, graphic) => graphic,
+                    _ => continue,
+                };
+
+                if let Some(placement) = graphic.placement {
+                    placements.insert(placement);
+                }
+            }
+        }
+
+        if placements.is_empty() {
+            return HashSet::new();
+        }
+
+        self.kitty_delete_cells(|cell| cell.placement.is_some_and(|p| placements.contains(&p)))
+    }
+
+    /// Remove kitty graphics matching the `predicate` from all cells, including the history.
+    ///
+    /// Only rows marked as holding placements are checked, without caching compressed rows.
+    ///
+    /// Returns the IDs of all images with deleted placements.
+    fn kitty_delete_cells<F>(&mut self, predicate: F) -> HashSet<u32>
+    where
+        F: Fn(&GraphicCell) -> bool,
+    {
+        let mut image_ids = HashSet::new();
+
+        for line in self.grid.placement_lines() {
+            let mut deleted = Vec::new();
+            let mut remaining = false;
+            self.grid.with_row(line, |row| {
+                for (column, cell) in row.into_iter().enumerate() {
+                    match cell.graphic().and_then(|graphic| Some((graphic, graphic.placement?))) {
+                        Some((graphic, placement)) if predicate(graphic) => {
+                            deleted.push((Column(column), placement));
+                        },
+                        Some(_) => remaining = true,
+                        None => (),
+                    }
+                }
+            });
+
+            for &(column, placement) in &deleted {
+                image_ids.insert(placement.image);
+                self.grid[line][column].set_graphic(None);
+            }
+
+            if !remaining {
+                self.grid.set_placements(line, false);
+            }
+        }
+
+        image_ids
+    }
+}
+
+/// Check if a point is at the 1-based cell coordinates.
+fn is_cell(point: Point, x: usize, y: usize) -> bool {
+    point.column.0 + 1 == x && point.line.0 + 1 == y as i32
+}
+
+/// Copy a rectangle from an image.
+fn crop(image: &Image, x: usize, y: usize, width: usize, height: usize) -> Image {
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for row in y..y + height {
+        let start = (row * image.width + x) * 4;
+        pixels.extend_from_slice(&image.pixels[start..start + width * 4]);
+    }
+
+    Image { width, height, pixels }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD as Base64;
+
+    use crate::parser::Processor;
+    use crate::term::Config;
+    use crate::term::test::{EventCollector, TermSize};
+
+    fn placement(term: &Term<EventCollector>, line: i32, column: usize) -> Option<PlacementId> {
+        term.grid[Line(line)][Column(column)].graphic().and_then(|graphic| graphic.placement)
+    }
+
+    #[test]
+    fn place_and_delete() {
+        let size = TermSize::new(10, 5);
+        let writes = EventCollector::default();
+        let mut term = Term::new(Config::default(), &size, writes.clone());
+        term.graphics_mut().set_cell_size(2, 2);
+        let mut parser: Processor = Processor::new();
+
+        // Transmit and display a 3x3 image.
+        let payload = Base64.encode([255; 27]);
+        let transmit = format!("\x1b_Ga=T,f=24,s=3,v=3,i=1;{payload}\x1b\\");
+        parser.advance(&mut term, transmit.as_bytes());
+
+        assert_eq!(writes.take_pty_writes(), ["\x1b_Gi=1;OK\x1b\\"]);
+        let id = PlacementId { image: 1, placement: 0 };
+        assert_eq!(placement(&term, 1, 1), Some(id));
+        assert_eq!(placement(&term, 1, 2), None);
+        assert_eq!(term.grid.cursor.point, Point::new(Line(1), Column(2)));
+
+        let graphic = term.grid[Line(1)][Column(1)].graphic().unwrap();
+        assert_eq!((graphic.offset_x, graphic.offset_y, graphic.width), (2, 2, 2));
+
+        // Scale the image to a single cell without moving the cursor.
+        parser.advance(&mut term, b"\x1b[4;1H\x1b_Ga=p,i=1,p=5,c=1,r=1,C=1,q=1\x1b\\");
+
+        let id = PlacementId { image: 1, placement: 5 };
+        assert_eq!(placement(&term, 3, 0), Some(id));
+        assert_eq!(term.grid[Line(3)][Column(0)].graphic().unwrap().width, 3);
+        assert_eq!(term.grid.cursor.point, Point::new(Line(3), Column(0)));
+
+        // Placing it again replaces the old placement.
+        parser.advance(&mut term, b"\x1b[5;6H\x1b_Ga=p,i=1,p=5,c=1,r=1,C=1,q=1\x1b\\");
+        assert_eq!(placement(&term, 3, 0), None);
+        assert_eq!(placement(&term, 4, 5), Some(id));
+
+        // Delete image and its data.
+        parser.advance(&mut term, b"\x1b_Ga=d,d=I,i=1\x1b\\");
+        assert_eq!(placement(&term, 0, 0), None);
+        assert_eq!(placement(&term, 4, 5), None);
+
+        parser.advance(&mut term, b"\x1b_Ga=p,i=1\x1b\\");
+        let responses = writes.take_pty_writes();
+        assert_eq!(responses.len(), 1);
+        assert!(responses[0].starts_with("\x1b_Gi=1;ENOENT:"));
+    }
+
+    #[test]
+    fn delete_at_cursor() {
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, EventCollector::default());
+        term.graphics_mut().set_cell_size(1, 1);
+        let mut parser: Processor = Processor::new();
+
+        let payload = Base64.encode([255; 8]);
+        let transmit = format!("\x1b_Ga=T,s=2,v=1,i=1,p=1,q=2;{payload}\x1b\\");
+        parser.advance(&mut term, transmit.as_bytes());
+        let transmit = format!("\x1b[3;1H\x1b_Ga=T,s=2,v=1,i=2,q=2;{payload}\x1b\\");
+        parser.advance(&mut term, transmit.as_bytes());
+
+        parser.advance(&mut term, b"\x1b[1;2H\x1b_Ga=d,d=c\x1b\\");
+        assert_eq!(placement(&term, 0, 0), None);
+        assert_eq!(placement(&term, 2, 0), Some(PlacementId { image: 2, placement: 0 }));
+
+        // Image data is kept for lowercase deletes.
+        assert_eq!(term.kitty_images.ids().count(), 2);
+    }
+
+    #[test]
+    fn delete_in_compressed_history() {
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, EventCollector::default());
+        term.graphics_mut().set_cell_size(1, 1);
+        let mut parser: Processor = Processor::new();
+
+        // Scroll the image far enough into the history for it to be compressed.
+        let payload = Base64.encode([255; 12]);
+        let transmit = format!("\x1b_Ga=T,f=24,s=2,v=2,i=1,q=2;{payload}\x1b\\");
+        parser.advance(&mut term, transmit.as_bytes());
+        parser.advance(&mut term, "x\r\n".repeat(1_100).as_bytes());
+        let usage = term.grid.memory_usage();
+        assert!(usage.compressed_rows > 0);
+
+        // Only the rows with the placement are decompressed.
+        parser.advance(&mut term, b"\x1b_Ga=d,d=i,i=1\x1b\\");
+        assert_eq!(term.grid.memory_usage().compressed_rows, usage.compressed_rows - 2);
+        assert!(term.grid.placement_lines().is_empty());
+
+        let topmost_line = term.grid.topmost_line().0;
+        assert_eq!(placement(&term, topmost_line, 0), None);
+        assert_eq!(placement(&term, topmost_line + 1, 1), None);
+    }
+
+    #[test]
+    fn delete_after_reflow() {
+        let size = TermSize::new(4, 5);
+        let mut term = Term::new(Config::default(), &size, EventCollector::default());
+        term.graphics_mut().set_cell_size(1, 1);
+        let mut parser: Processor = Processor::new();
+
+        let payload = Base64.encode([255; 12]);
+        let transmit = format!("\x1b_Ga=T,s=3,v=1,i=1,q=2;{payload}\x1b\\");
+        parser.advance(&mut term, transmit.as_bytes());
+
+        // Cells wrapped into a new row keep their placement.
+        term.resize(TermSize::new(2, 5));
+        let id = PlacementId { image: 1, placement: 0 };
+        assert_eq!(placement(&term, -1, 1), Some(id));
+        assert_eq!(placement(&term, 0, 0), Some(id));
+
+        parser.advance(&mut term, b"\x1b_Ga=d,d=i,i=1\x1b\\");
+        assert_eq!(placement(&term, -1, 1), None);
+        assert_eq!(placement(&term, 0, 0), None);
+    }
+
+    #[test]
+    fn scale_beyond_screen() {
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, EventCollector::default());
+        term.graphics_mut().set_cell_size(1, 1);
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b_Ga=T,f=24,s=1,v=1,r=100000000;AAAA\x1b\\");
+
+        let id = PlacementId { image: 1, placement: 0 };
+        assert_eq!(placement(&term, 0, 0), Some(id));
+        assert_eq!(placement(&term, 4, 4), Some(id));
+        assert_eq!(term.grid.cursor.point, Point::new(Line(4), Column(5)));
+        assert_eq!(term.grid.history_size(), 0);
+    }
+
+    #[test]
+    fn source_rectangle() {
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, EventCollector::default());
+        term.graphics_mut().set_cell_size(1, 1);
+        let mut parser: Processor = Processor::new();
+
+        let payload = Base64.encode([255; 64]);
+        let transmit = format!("\x1b_Ga=T,s=4,v=4,x=1,y=1,w=2,h=1,q=2;{payload}\x1b\\");
+        parser.advance(&mut term, transmit.as_bytes());
+
+        let updates = term.graphics_mut().take_updates();
+        assert_eq!(updates.added.len(), 2);
+        assert_eq!((updates.added[1].image.width, updates.added[1].image.height), (2, 1));
+        assert!(term.grid[Line(0)][Column(1)].graphic().is_some());
+        assert!(term.grid[Line(0)][Column(2)].graphic().is_none());
+    }
+}
@@ -1,6 +1,6 @@
 //! Exports the `Term` type which is a high-level API for the Grid.
 
-use std::ops::{Index, IndexMut, Range};
+use std::ops::{Index, IndexMut, Range, RangeBounds, RangeInclusive};
 use std::sync::Arc;
 use std::{cmp, mem, ptr, slice, str};
 
@@ -14,11 +14,19 @@ use log::{debug, trace};
 use unicode_width::UnicodeWidthChar;
 
 use crate::event::{Event, EventListener};
-use crate::grid::{Dimensions, Grid, GridIterator, Scroll};
+use crate::grapheme::GraphemeCluster;
+use crate::graphics::kitty::{self, ImageStorage};
+use crate::graphics::{GraphicCell, Graphics, Image};
+use crate::grid::{Dimensions, Grid, GridIterator, LineAttribute, MemoryUsage, Scroll, SpillMode};
 use crate::index::{self, Boundary, Column, Direction, Line, Point, Side};
+use crate::parser;
 use crate::selection::{Selection, SelectionRange, SelectionType};
 use crate::term::cell::{Cell, Flags, LineLength};
 use crate::term::color::Colors;
+use crate::term::notification::{Notification, NotificationChunk};
+use crate::term::progress::{Progress, ProgressState};
+use crate::term::rectangle::{AttributeChange, Rectangle};
+use crate::term::shell_integration::{SemanticMark, SemanticMarkKind, WorkingDirectory};
 use crate::vi_mode::{ViModeCursor, ViMotion};
 use crate::vte::ansi::{
     self, Attr, CharsetIndex, Color, CursorShape, CursorStyle, Handler, Hyperlink, KeyboardModes,
@@ -28,7 +36,14 @@ use crate::vte::ansi::{
 
 pub mod cell;
 pub mod color;
+pub mod export;
+mod kitty_graphics;
+pub mod notification;
+pub mod progress;
+mod query;
+pub mod rectangle;
 pub mod search;
+pub mod shell_integration;
 
 /// Minimum number of columns.
 ///
@@ -38,9 +53,18 @@ pub const MIN_COLUMNS: usize = 2;
 /// Minimum number of visible lines.
 pub const MIN_SCREEN_LINES: usize = 1;
 
+/// Private mode for grapheme cluster segmentation.
+const GRAPHEME_CLUSTERING_MODE: u16 = 2027;
+
+/// Private mode enabling left and right margins (DECLRMM).
+const LEFT_RIGHT_MARGIN_MODE: u16 = 69;
+
 /// Max size of the window title stack.
 const TITLE_STACK_MAX_DEPTH: usize = 4096;
 
+/// Max combined size of a notification's title and body, in bytes.
+const NOTIFICATION_MAX_SIZE: usize = 64 * 1024;
+
 /// Default semantic escape characters.
 pub const SEMANTIC_ESCAPE_CHARS: &str = ",│`|:\"' ()[]{}<>\t";
 
@@ -77,6 +101,9 @@ bitflags! {
         const REPORT_ALTERNATE_KEYS   = 1 << 20;
         const REPORT_ALL_KEYS_AS_ESC  = 1 << 21;
         const REPORT_ASSOCIATED_TEXT  = 1 << 22;
+        const GRAPHEME_CLUSTERING     = 1 << 23;
+        const RECTANGULAR_ATTRIBUTE_CHANGE = 1 << 24;
+        const LEFT_RIGHT_MARGIN       = 1 << 25;
         const MOUSE_MODE              = Self::MOUSE_REPORT_CLICK.bits() | Self::MOUSE_MOTION.bits() | Self::MOUSE_DRAG.bits();
         const KITTY_KEYBOARD_PROTOCOL = Self::DISAMBIGUATE_ESC_CODES.bits()
                                       | Self::REPORT_EVENT_TYPES.bits()
@@ -300,6 +327,11 @@ pub struct Term<T> {
     /// Range going from top to bottom of the terminal, indexed from the top of the viewport.
     scroll_region: Range<Line>,
 
+    /// Horizontal margins.
+    ///
+    /// Range going from the left to the right margin, covering all columns unless DECLRMM is set.
+    horizontal_margins: Range<Column>,
+
     /// Modified terminal colors.
     colors: Colors,
 
@@ -316,6 +348,17 @@ pub struct Term<T> {
     /// term is set.
     title_stack: Vec<Option<String>>,
 
+    /// Working directory reported by the shell.
+    working_directory: Option<WorkingDirectory>,
+
+    /// Kitty notification which is still receiving chunks, with its identifier.
+    ///
+    /// The notification is `None` once it has been dropped for exceeding the size limit.
+    pending_notification: Option<(String, Option<Notification>)>,
+
+    /// Progress reported through `OSC 9 ; 4`.
+    progress: Option<Progress>,
+
     /// The stack for the keyboard modes.
     keyboard_mode_stack: Vec<KeyboardModes>,
 
@@ -325,6 +368,15 @@ pub struct Term<T> {
     /// Information about damaged cells.
     damage: TermDamageState,
 
+    /// Inline images.
+    graphics: Graphics,
+
+    /// Images transmitted with the kitty graphics protocol.
+    kitty_images: ImageStorage,
+
+    /// Cursor position after the last printed character, grapheme clusters only continue there.
+    last_input: Option<(Point, bool)>,
+
     /// Config directly for the terminal.
     config: Config,
 }
@@ -335,6 +387,9 @@ pub struct Config {
     /// The maximum amount of scrolling history.
     pub scrolling_history: usize,
 
+    /// Storage of lines moving past the scrolling history limit.
+    pub history_spill: SpillMode,
+
     /// Default cursor style to reset the cursor to.
     pub default_cursor_style: CursorStyle,
 
@@ -364,6 +419,7 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             scrolling_history: 10000,
+            history_spill: Default::default(),
             semantic_escape_chars: SEMANTIC_ESCAPE_CHARS.to_owned(),
             default_cursor_style: Default::default(),
             vi_mode_cursor_style: Default::default(),
@@ -420,12 +476,14 @@ impl<T> Term<T> {
         let num_lines = dimensions.screen_lines();
 
         let history_size = config.scrolling_history;
-        let grid = Grid::new(num_lines, num_cols, history_size);
+        let mut grid = Grid::new(num_lines, num_cols, history_size);
+        grid.set_spill_mode(config.history_spill);
         let inactive_grid = Grid::new(num_lines, num_cols, 0);
 
         let tabs = TabStops::new(grid.columns());
 
         let scroll_region = Line(0)..Line(grid.screen_lines() as i32);
+        let horizontal_margins = Column(0)..Column(grid.columns());
 
         // Initialize terminal damage, covering the entire terminal upon launch.
         let damage = TermDamageState::new(num_cols, num_lines);
@@ -433,6 +491,7 @@ impl<T> Term<T> {
         Term {
             inactive_grid,
             scroll_region,
+            horizontal_margins,
             event_proxy,
             damage,
             config,
@@ -448,7 +507,13 @@ impl<T> Term<T> {
             is_focused: Default::default(),
             selection: Default::default(),
             title: Default::default(),
+            working_directory: Default::default(),
+            pending_notification: Default::default(),
+            progress: Default::default(),
             mode: Default::default(),
+            graphics: Default::default(),
+            kitty_images: Default::default(),
+            last_input: None,
         }
     }
 
@@ -519,8 +584,10 @@ impl<T> Term<T> {
 
         if self.mode.contains(TermMode::ALT_SCREEN) {
             self.inactive_grid.update_history(self.config.scrolling_history);
+            self.inactive_grid.set_spill_mode(self.config.history_spill);
         } else {
             self.grid.update_history(self.config.scrolling_history);
+            self.grid.set_spill_mode(self.config.history_spill);
         }
 
         if self.config.kitty_keyboard != old_config.kitty_keyboard {
@@ -659,6 +726,46 @@ impl<T> Term<T> {
         &mut self.grid
     }
 
+    /// Grid of the primary screen, even while the alternate screen is active.
+    pub fn primary_grid(&self) -> &Grid<Cell> {
+        if self.mode.contains(TermMode::ALT_SCREEN) { &self.inactive_grid } else { &self.grid }
+    }
+
+    /// Inline images.
+    #[inline]
+    pub fn graphics(&self) -> &Graphics {
+        &self.graphics
+    }
+
+    /// Mutable access to the inline images.
+    #[inline]
+    pub fn graphics_mut(&mut self) -> &mut Graphics {
+        &mut self.graphics
+    }
+
+    /// Approximate memory used by the terminal's grids.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.grid.memory_usage() + self.inactive_grid.memory_usage()
+    }
+
+    /// Release the cells of compressed history rows which were decompressed for reading.
+    pub fn release_decompressed(&mut self) {
+        self.grid.release_decompressed();
+        self.inactive_grid.release_decompressed();
+    }
+
+    /// Working directory reported by the shell through `OSC 7`.
+    #[inline]
+    pub fn working_directory(&self) -> Option<&WorkingDirectory> {
+        self.working_directory.as_ref()
+    }
+
+    /// Progress reported by the application through `OSC 9 ; 4`.
+    #[inline]
+    pub fn progress(&self) -> Option<Progress> {
+        self.progress
+    }
+
     /// Resize terminal to new dimensions.
     pub fn resize<S: Dimensions>(&mut self, size: S) {
         let old_cols = self.columns();
@@ -672,6 +779,8 @@ impl<T> Term<T> {
             return;
         }
 
+        self.last_input = None;
+
         debug!("New num_cols is {num_cols} and num_lines is {num_lines}");
 
         // Move vi mode cursor with the content.
@@ -689,6 +798,10 @@ impl<T> Term<T> {
         if old_cols != num_cols {
             self.selection = None;
 
+            // Reflow decompresses all rows, so the history needs to be compressed again.
+            self.grid.compress_history(self.grid.history_size());
+            self.inactive_grid.compress_history(self.inactive_grid.history_size());
+
             // Recreate tabs list.
             self.tabs.resize(num_cols);
         } else if let Some(selection) = self.selection.take() {
@@ -705,8 +818,9 @@ impl<T> Term<T> {
             cmp::max(cmp::min(vi_point.line, viewport_bottom), viewport_top);
         self.vi_mode_cursor.point.column = cmp::min(vi_point.column, self.last_column());
 
-        // Reset scrolling region.
+        // Reset scrolling region and margins.
         self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(self.columns());
 
         // Resize damage information.
         self.damage.resize(num_cols, num_lines);
@@ -739,6 +853,7 @@ impl<T> Term<T> {
         mem::swap(&mut self.grid, &mut self.inactive_grid);
         self.mode ^= TermMode::ALT_SCREEN;
         self.selection = None;
+        self.last_input = None;
         self.mark_fully_damaged();
     }
 
@@ -750,11 +865,18 @@ impl<T> Term<T> {
     fn scroll_down_relative(&mut self, origin: Line, mut lines: usize) {
         trace!("Scrolling down relative: origin={origin}, lines={lines}");
 
+        self.last_input = None;
+
         lines = cmp::min(lines, (self.scroll_region.end - self.scroll_region.start).0 as usize);
         lines = cmp::min(lines, (self.scroll_region.end - origin).0 as usize);
 
         let region = origin..self.scroll_region.end;
 
+        if self.has_horizontal_margins() {
+            self.scroll_margins(region, -(lines as i32));
+            return;
+        }
+
         // Scroll selection.
         self.selection =
             self.selection.take().and_then(|s| s.rotate(self, &region, -(lines as i32)));
@@ -778,14 +900,24 @@ impl<T> Term<T> {
     fn scroll_up_relative(&mut self, origin: Line, mut lines: usize) {
         trace!("Scrolling up relative: origin={origin}, lines={lines}");
 
+        self.last_input = None;
+
         lines = cmp::min(lines, (self.scroll_region.end - self.scroll_region.start).0 as usize);
 
         let region = origin..self.scroll_region.end;
 
+        if self.has_horizontal_margins() {
+            self.scroll_margins(region, lines as i32);
+            return;
+        }
+
         // Scroll selection.
         self.selection = self.selection.take().and_then(|s| s.rotate(self, &region, lines as i32));
 
         self.grid.scroll_up(&region, lines);
+        if region.start == 0 {
+            self.grid.compress_history(lines);
+        }
 
         // Scroll vi mode cursor.
         let viewport_top = Line(-(self.grid.display_offset() as i32));
@@ -797,6 +929,70 @@ impl<T> Term<T> {
         self.mark_fully_damaged();
     }
 
+    /// Scroll the cells between the horizontal margins, leaving the rest of the lines untouched.
+    ///
+    /// Positive `lines` move the text up. Since the lines are only scrolled partially, nothing is
+    /// added to the scrollback history.
+    fn scroll_margins(&mut self, region: Range<Line>, lines: i32) {
+        let columns = self.horizontal_margins.clone();
+        let height = (region.end - region.start).0;
+        let bg = self.grid.cursor.template.bg;
+
+        for offset in 0..height {
+            // Start at the edge text is moving towards, to avoid overwriting the source.
+            let line = if lines > 0 { region.start + offset } else { region.end - 1 - offset };
+            let source = line + lines;
+
+            if region.contains(&source) {
+                let cells = self.grid[source][columns.clone()].to_vec();
+                self.grid[line][columns.clone()].clone_from_slice(&cells);
+            } else {
+                for cell in &mut self.grid[line][columns.clone()] {
+                    *cell = bg.into();
+                }
+            }
+        }
+
+        let range = region.start..=region.end - 1;
+        self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
+        self.mark_fully_damaged();
+    }
+
+    /// Check if horizontal margins are restricting the scrolling region.
+    fn has_horizontal_margins(&self) -> bool {
+        self.horizontal_margins != (Column(0)..Column(self.columns()))
+    }
+
+    /// Last column the cursor can reach before wrapping.
+    ///
+    /// This is the right margin, unless the cursor is already past it.
+    fn wrap_column(&self) -> Column {
+        let right_margin = self.horizontal_margins.end - 1;
+        let line_end = self.line_last_column(self.grid.cursor.point.line);
+        if self.grid.cursor.point.column <= right_margin {
+            cmp::min(right_margin, line_end)
+        } else {
+            line_end
+        }
+    }
+
+    /// Last column of a line, taking double-width lines into account.
+    fn line_last_column(&self, line: Line) -> Column {
+        if self.grid[line].line_attribute().is_double_width() {
+            Column(cmp::max(self.columns() / 2, 1) - 1)
+        } else {
+            self.last_column()
+        }
+    }
+
+    /// First column of the line for the cursor.
+    ///
+    /// This is the left margin, unless the cursor is left of it.
+    fn line_start(&self) -> Column {
+        let left_margin = self.horizontal_margins.start;
+        if self.grid.cursor.point.column >= left_margin { left_margin } else { Column(0) }
+    }
+
     fn deccolm(&mut self)
     where
         T: EventListener,
@@ -933,6 +1129,67 @@ impl<T> Term<T> {
         point
     }
 
+    /// Iterate over all shell integration marks within a range of lines.
+    #[inline]
+    pub fn semantic_marks<R: RangeBounds<Line>>(
+        &self,
+        lines: R,
+    ) -> impl DoubleEndedIterator<Item = (Point, SemanticMarkKind)> + '_ {
+        self.grid.semantic_marks(lines)
+    }
+
+    /// Find the start of the closest prompt above `line`.
+    pub fn prompt_before(&self, line: Line) -> Option<Point> {
+        self.semantic_marks(..line)
+            .rev()
+            .find(|(_, kind)| *kind == SemanticMarkKind::PromptStart)
+            .map(|(point, _)| point)
+    }
+
+    /// Find the start of the closest prompt below `line`.
+    pub fn prompt_after(&self, line: Line) -> Option<Point> {
+        self.semantic_marks(line + 1..)
+            .find(|(_, kind)| *kind == SemanticMarkKind::PromptStart)
+            .map(|(point, _)| point)
+    }
+
+    /// Find the output of the command containing `point`.
+    pub fn command_output(&self, point: Point) -> Option<RangeInclusive<Point>> {
+        // Point is only part of an output if the closest preceding mark is an output start.
+        let (start, kind) =
+            self.semantic_marks(..=point.line).rev().find(|(mark, _)| *mark <= point)?;
+        if kind != SemanticMarkKind::OutputStart {
+            return None;
+        }
+
+        self.command_output_from(start)
+    }
+
+    /// Find the output of the most recent command.
+    pub fn last_command_output(&self) -> Option<RangeInclusive<Point>> {
+        self.semantic_marks(..)
+            .rev()
+            .filter(|(_, kind)| *kind == SemanticMarkKind::OutputStart)
+            .find_map(|(start, _)| self.command_output_from(start))
+    }
+
+    /// Get the output of the command starting at `start`.
+    ///
+    /// The output ends before the next mark, or at the cursor line if the command is still
+    /// running.
+    fn command_output_from(&self, start: Point) -> Option<RangeInclusive<Point>> {
+        let mut marks = self.semantic_marks(start.line..).filter(|(mark, kind)| {
+            *mark > start || (*mark == start && *kind != SemanticMarkKind::OutputStart)
+        });
+
+        let end = match marks.next() {
+            Some((end, _)) => end.sub(self, Boundary::Grid, 1),
+            None => Point::new(self.grid.cursor.point.line, self.last_column()),
+        };
+
+        (end >= start).then_some(start..=end)
+    }
+
     #[inline]
     pub fn semantic_escape_chars(&self) -> &str {
         &self.config.semantic_escape_chars
@@ -973,7 +1230,12 @@ impl<T> Term<T> {
 
         trace!("Wrapping input");
 
-        self.grid.cursor_cell().flags.insert(Flags::WRAPLINE);
+        // Lines wrapped inside of horizontal margins do not continue on the next line.
+        if !self.has_horizontal_margins() {
+            self.grid.cursor_cell().flags.insert(Flags::WRAPLINE);
+        }
+
+        let line_start = self.line_start();
 
         if self.grid.cursor.point.line + 1 >= self.scroll_region.end {
             self.linefeed();
@@ -982,11 +1244,142 @@ impl<T> Term<T> {
             self.grid.cursor.point.line += 1;
         }
 
-        self.grid.cursor.point.column = Column(0);
+        self.grid.cursor.point.column = line_start;
         self.grid.cursor.input_needs_wrap = false;
         self.damage_cursor();
     }
 
+    /// Write a character occupying `width` cells at the cursor and advance the cursor.
+    fn write_char(&mut self, c: char, width: usize)
+    where
+        T: EventListener,
+    {
+        let columns = self.wrap_column().0 + 1;
+        if width == 1 {
+            self.write_at_cursor(c);
+        } else {
+            if self.grid.cursor.point.column + 1 >= columns {
+                if self.mode.contains(TermMode::LINE_WRAP) {
+                    // Insert placeholder before wide char if glyph does not fit in this row.
+                    self.grid.cursor.template.flags.insert(Flags::LEADING_WIDE_CHAR_SPACER);
+                    self.write_at_cursor(' ');
+                    self.grid.cursor.template.flags.remove(Flags::LEADING_WIDE_CHAR_SPACER);
+                    self.wrapline();
+                } else {
+                    // Prevent out of bounds crash when linewrapping is disabled.
+                    self.grid.cursor.input_needs_wrap = true;
+                    return;
+                }
+            }
+
+            // Write full width glyph to current cursor cell.
+            self.grid.cursor.template.flags.insert(Flags::WIDE_CHAR);
+            self.write_at_cursor(c);
+            self.grid.cursor.template.flags.remove(Flags::WIDE_CHAR);
+
+            // Write spacer to cell following the wide glyph.
+            self.grid.cursor.point.column += 1;
+            self.grid.cursor.template.flags.insert(Flags::WIDE_CHAR_SPACER);
+            self.write_at_cursor(' ');
+            self.grid.cursor.template.flags.remove(Flags::WIDE_CHAR_SPACER);
+        }
+
+        if self.grid.cursor.point.column + 1 < columns {
+            self.grid.cursor.point.column += 1;
+        } else {
+            self.grid.cursor.input_needs_wrap = true;
+        }
+    }
+
+    /// Shift the cells at the cursor to the right in insert mode, making space for `width` cells.
+    fn insert_blank_cells(&mut self, width: usize) {
+        let columns = self.wrap_column().0 + 1;
+        if self.mode.contains(TermMode::INSERT) && self.grid.cursor.point.column + width < columns {
+            let line = self.grid.cursor.point.line;
+            let col = self.grid.cursor.point.column;
+            let row = &mut self.grid[line][..];
+
+            for col in (col.0..(columns - width)).rev() {
+                row.swap(col + width, col);
+            }
+        }
+    }
+
+    /// Cell before the cursor, which zero-width characters are attached to.
+    fn zerowidth_point(&self) -> Point {
+        // Get previous column.
+        let mut column = self.grid.cursor.point.column;
+        if !self.grid.cursor.input_needs_wrap {
+            column.0 = column.saturating_sub(1);
+        }
+
+        // Use the first fullwidth character cell, instead of its spacer.
+        let line = self.grid.cursor.point.line;
+        if self.grid[line][column].flags.contains(Flags::WIDE_CHAR_SPACER) {
+            column.0 = column.saturating_sub(1);
+        }
+
+        Point::new(line, column)
+    }
+
+    /// Add `c` to the grapheme cluster before the cursor.
+    ///
+    /// Returns `false` if `c` starts a new grapheme cluster.
+    fn extend_grapheme_cluster(&mut self, c: char) -> bool
+    where
+        T: EventListener,
+    {
+        // Clusters only continue right after the previous character, and never at line start.
+        let cursor = (self.grid.cursor.point, self.grid.cursor.input_needs_wrap);
+        if self.last_input != Some(cursor) || (cursor.0.column == 0 && !cursor.1) {
+            return false;
+        }
+
+        let point = self.zerowidth_point();
+        let cell = &self.grid[point];
+        if !GraphemeCluster::new(cell.c, cell.zerowidth().unwrap_or_default()).continues(c) {
+            return false;
+        }
+
+        let cell = &mut self.grid[point];
+        cell.push_zerowidth(c);
+
+        let width = GraphemeCluster::new(cell.c, cell.zerowidth().unwrap_or_default()).width();
+        if width > 1 && !cell.flags.contains(Flags::WIDE_CHAR) {
+            self.widen_grapheme_cluster(point);
+            self.last_input = Some((self.grid.cursor.point, self.grid.cursor.input_needs_wrap));
+        }
+
+        true
+    }
+
+    /// Rewrite the single cell grapheme cluster at `point` as a fullwidth character.
+    fn widen_grapheme_cluster(&mut self, point: Point)
+    where
+        T: EventListener,
+    {
+        // Without line wrapping, the last column cannot hold fullwidth characters.
+        if point.column == self.last_column() && !self.mode.contains(TermMode::LINE_WRAP) {
+            return;
+        }
+
+        let cell = self.grid[point].clone();
+
+        // Make space for the spacer following the cluster.
+        if !self.grid.cursor.input_needs_wrap {
+            self.insert_blank_cells(1);
+        }
+
+        self.grid.cursor.point = point;
+        self.grid.cursor.input_needs_wrap = false;
+        self.write_char(cell.c, 2);
+
+        let point = self.zerowidth_point();
+        for &c in cell.zerowidth().into_iter().flatten() {
+            self.grid[point].push_zerowidth(c);
+        }
+    }
+
     /// Write `c` to the cell at the cursor position.
     #[inline(always)]
     fn write_at_cursor(&mut self, c: char) {
@@ -1074,75 +1467,36 @@ impl<T: EventListener> Handler for Term<T> {
             None => return,
         };
 
+        // Add characters to the previous grapheme cluster if there's no boundary between them.
+        if self.mode.contains(TermMode::GRAPHEME_CLUSTERING) && self.extend_grapheme_cluster(c) {
+            return;
+        }
+
         // Handle zero-width characters.
         if width == 0 {
-            // Get previous column.
-            let mut column = self.grid.cursor.point.column;
-            if !self.grid.cursor.input_needs_wrap {
-                column.0 = column.saturating_sub(1);
-            }
-
             // Put zerowidth characters over first fullwidth character cell.
-            let line = self.grid.cursor.point.line;
-            if self.grid[line][column].flags.contains(Flags::WIDE_CHAR_SPACER) {
-                column.0 = column.saturating_sub(1);
-            }
-
-            self.grid[line][column].push_zerowidth(c);
+            let point = self.zerowidth_point();
+            self.grid[point].push_zerowidth(c);
             return;
         }
 
+        // Keep the cursor inside of double-width lines.
+        let wrap_column = self.wrap_column();
+        if self.grid.cursor.point.column > wrap_column {
+            self.grid.cursor.point.column = wrap_column;
+        }
+
         // Move cursor to next line.
         if self.grid.cursor.input_needs_wrap {
             self.wrapline();
         }
 
         // If in insert mode, first shift cells to the right.
-        let columns = self.columns();
-        if self.mode.contains(TermMode::INSERT) && self.grid.cursor.point.column + width < columns {
-            let line = self.grid.cursor.point.line;
-            let col = self.grid.cursor.point.column;
-            let row = &mut self.grid[line][..];
+        self.insert_blank_cells(width);
 
-            for col in (col.0..(columns - width)).rev() {
-                row.swap(col + width, col);
-            }
-        }
-
-        if width == 1 {
-            self.write_at_cursor(c);
-        } else {
-            if self.grid.cursor.point.column + 1 >= columns {
-                if self.mode.contains(TermMode::LINE_WRAP) {
-                    // Insert placeholder before wide char if glyph does not fit in this row.
-                    self.grid.cursor.template.flags.insert(Flags::LEADING_WIDE_CHAR_SPACER);
-                    self.write_at_cursor(' ');
-                    self.grid.cursor.template.flags.remove(Flags::LEADING_WIDE_CHAR_SPACER);
-                    self.wrapline();
-                } else {
-                    // Prevent out of bounds crash when linewrapping is disabled.
-                    self.grid.cursor.input_needs_wrap = true;
-                    return;
-                }
-            }
-
-            // Write full width glyph to current cursor cell.
-            self.grid.cursor.template.flags.insert(Flags::WIDE_CHAR);
-            self.write_at_cursor(c);
-            self.grid.cursor.template.flags.remove(Flags::WIDE_CHAR);
-
-            // Write spacer to cell following the wide glyph.
-            self.grid.cursor.point.column += 1;
-            self.grid.cursor.template.flags.insert(Flags::WIDE_CHAR_SPACER);
-            self.write_at_cursor(' ');
-            self.grid.cursor.template.flags.remove(Flags::WIDE_CHAR_SPACER);
-        }
+        self.write_char(c, width);
 
-        if self.grid.cursor.point.column + 1 < columns {
-            self.grid.cursor.point.column += 1;
-        } else {
-            self.grid.cursor.input_needs_wrap = true;
-        }
+        self.last_input = Some((self.grid.cursor.point, self.grid.cursor.input_needs_wrap));
     }
 
     #[inline]
@@ -1150,6 +1504,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Decalnning");
 
         for line in (0..self.screen_lines()).map(Line::from) {
+            self.grid[line].set_line_attribute(LineAttribute::Normal);
             for column in 0..self.columns() {
                 let cell = &mut self.grid[line][Column(column)];
                 *cell = Cell::default();
@@ -1166,15 +1521,17 @@ impl<T: EventListener> Handler for Term<T> {
         let col = Column(col);
 
         trace!("Going to: line={line}, col={col}");
-        let (y_offset, max_y) = if self.mode.contains(TermMode::ORIGIN) {
-            (self.scroll_region.start, self.scroll_region.end - 1)
+        let (y_offset, max_y, x_offset, max_x) = if self.mode.contains(TermMode::ORIGIN) {
+            let margins = &self.horizontal_margins;
+            (self.scroll_region.start, self.scroll_region.end - 1, margins.start, margins.end - 1)
         } else {
-            (Line(0), self.bottommost_line())
+            (Line(0), self.bottommost_line(), Column(0), self.last_column())
         };
 
         self.damage_cursor();
         self.grid.cursor.point.line = cmp::max(cmp::min(line + y_offset, max_y), Line(0));
-        self.grid.cursor.point.column = cmp::min(col, self.last_column());
+        let max_x = cmp::min(max_x, self.line_last_column(self.grid.cursor.point.line));
+        self.grid.cursor.point.column = cmp::min(col + x_offset, max_x);
         self.damage_cursor();
         self.grid.cursor.input_needs_wrap = false;
     }
@@ -1196,21 +1553,24 @@ impl<T: EventListener> Handler for Term<T> {
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
 
+        // Characters are only shifted within the horizontal margins.
+        if !self.horizontal_margins.contains(&cursor.point.column) {
+            return;
+        }
+        let end = self.horizontal_margins.end.0;
+
         // Ensure inserting within terminal bounds
-        let count = cmp::min(count, self.columns() - cursor.point.column.0);
+        let count = cmp::min(count, end - cursor.point.column.0);
 
         let source = cursor.point.column;
         let destination = cursor.point.column.0 + count;
-        let num_cells = self.columns() - destination;
 
         let line = cursor.point.line;
         self.damage.damage_line(line.0 as usize, 0, self.columns() - 1);
 
         let row = &mut self.grid[line][..];
 
-        for offset in (0..num_cells).rev() {
-            row.swap(destination + offset, source.0 + offset);
-        }
+        row[source.0..end].rotate_right(count);
 
         // Cells were just moved out toward the end of the line;
         // fill in between source and dest with blanks.
@@ -1240,7 +1600,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn move_forward(&mut self, cols: usize) {
         trace!("Moving forward: {cols}");
-        let last_column = cmp::min(self.grid.cursor.point.column + cols, self.last_column());
+        let last_column = cmp::min(self.grid.cursor.point.column + cols, self.wrap_column());
 
         let cursor_line = self.grid.cursor.point.line.0 as usize;
         self.damage.damage_line(cursor_line, self.grid.cursor.point.column.0, last_column.0);
@@ -1252,7 +1612,8 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn move_backward(&mut self, cols: usize) {
         trace!("Moving backward: {cols}");
-        let column = self.grid.cursor.point.column.saturating_sub(cols);
+        let line_start = self.line_start().0;
+        let column = cmp::max(self.grid.cursor.point.column.saturating_sub(cols), line_start);
 
         let cursor_line = self.grid.cursor.point.line.0 as usize;
         self.damage.damage_line(cursor_line, column, self.grid.cursor.point.column.0);
@@ -1266,7 +1627,7 @@ impl<T: EventListener> Handler for Term<T> {
         match intermediate {
             None => {
                 trace!("Reporting primary device attributes");
-                let text = String::from("\x1b[?6c");
+                let text = String::from("\x1b[?6;4c");
                 self.event_proxy.send_event(Event::PtyWrite(text));
             },
             Some('>') => {
@@ -1378,7 +1739,8 @@ impl<T: EventListener> Handler for Term<T> {
             return;
         }
 
-        while self.grid.cursor.point.column < self.columns() && count != 0 {
+        let wrap_column = self.wrap_column();
+        while self.grid.cursor.point.column <= wrap_column && count != 0 {
             count -= 1;
 
             let c = self.grid.cursor.charsets[self.active_charset].map('\t');
@@ -1388,7 +1750,7 @@ impl<T: EventListener> Handler for Term<T> {
             }
 
             loop {
-                if (self.grid.cursor.point.column + 1) == self.columns() {
+                if self.grid.cursor.point.column == wrap_column {
                     break;
                 }
 
@@ -1406,7 +1768,7 @@ impl<T: EventListener> Handler for Term<T> {
     fn backspace(&mut self) {
         trace!("Backspace");
 
-        if self.grid.cursor.point.column > Column(0) {
+        if self.grid.cursor.point.column > self.line_start() {
             let line = self.grid.cursor.point.line.0 as usize;
             let column = self.grid.cursor.point.column.0;
             self.grid.cursor.point.column -= 1;
@@ -1419,7 +1781,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn carriage_return(&mut self) {
         trace!("Carriage return");
-        let new_col = 0;
+        let new_col = self.line_start().0;
         let line = self.grid.cursor.point.line.0 as usize;
         self.damage.damage_line(line, new_col, self.grid.cursor.point.column.0);
         self.grid.cursor.point.column = Column(new_col);
@@ -1506,7 +1868,8 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Inserting blank {lines} lines");
 
         let origin = self.grid.cursor.point.line;
-        if self.scroll_region.contains(&origin) {
+        let column = self.grid.cursor.point.column;
+        if self.scroll_region.contains(&origin) && self.horizontal_margins.contains(&column) {
             self.scroll_down_relative(origin, lines);
         }
     }
@@ -1518,7 +1881,11 @@ impl<T: EventListener> Handler for Term<T> {
 
         trace!("Deleting {lines} lines");
 
-        if lines > 0 && self.scroll_region.contains(&origin) {
+        let column = self.grid.cursor.point.column;
+        if lines > 0
+            && self.scroll_region.contains(&origin)
+            && self.horizontal_margins.contains(&column)
+        {
             self.scroll_up_relative(origin, lines);
         }
     }
@@ -1544,29 +1911,29 @@ impl<T: EventListener> Handler for Term<T> {
 
     #[inline]
     fn delete_chars(&mut self, count: usize) {
-        let columns = self.columns();
         let cursor = &self.grid.cursor;
         let bg = cursor.template.bg;
 
-        // Ensure deleting within terminal bounds.
-        let count = cmp::min(count, columns);
+        // Characters are only shifted within the horizontal margins.
+        if !self.horizontal_margins.contains(&cursor.point.column) {
+            return;
+        }
+        let columns = self.horizontal_margins.end.0;
 
+        // Ensure deleting within terminal bounds.
         let start = cursor.point.column.0;
-        let end = cmp::min(start + count, columns - 1);
-        let num_cells = columns - end;
+        let count = cmp::min(count, columns - start);
 
         let line = cursor.point.line;
         self.damage.damage_line(line.0 as usize, 0, self.columns() - 1);
         let row = &mut self.grid[line][..];
 
-        for offset in 0..num_cells {
-            row.swap(start + offset, end + offset);
-        }
+        row[start..columns].rotate_left(count);
 
-        // Clear last `count` cells in the row. If deleting 1 char, need to delete
+        // Clear last `count` cells in the region. If deleting 1 char, need to delete
         // 1 cell.
         let end = columns - count;
-        for cell in &mut row[end..] {
+        for cell in &mut row[end..columns] {
             *cell = bg.into();
         }
     }
@@ -1849,13 +2216,18 @@ impl<T: EventListener> Handler for Term<T> {
         self.grid.reset();
         self.inactive_grid.reset();
         self.scroll_region = Line(0)..Line(self.screen_lines() as i32);
+        self.horizontal_margins = Column(0)..Column(self.columns());
         self.tabs = TabStops::new(self.columns());
+        if self.progress.take().is_some() {
+            self.event_proxy.send_event(Event::Progress(None));
+        }
         self.title_stack = Vec::new();
         self.title = None;
         self.selection = None;
         self.vi_mode_cursor = Default::default();
         self.keyboard_mode_stack = Default::default();
         self.inactive_keyboard_mode_stack = Default::default();
+        self.kitty_images.clear();
 
         // Preserve vi mode across resets.
         self.mode &= TermMode::VI;
@@ -1896,7 +2268,8 @@ impl<T: EventListener> Handler for Term<T> {
             Attr::Reset => {
                 cursor.template.fg = Color::Named(NamedColor::Foreground);
                 cursor.template.bg = Color::Named(NamedColor::Background);
-                cursor.template.flags = Flags::empty();
+                // Character protection is not reset, since it's not an SGR attribute.
+                cursor.template.flags &= Flags::PROTECTED;
                 cursor.template.set_underline_color(None);
             },
             Attr::Reverse => cursor.template.flags.insert(Flags::INVERSE),
@@ -1942,6 +2315,14 @@ impl<T: EventListener> Handler for Term<T> {
     fn set_private_mode(&mut self, mode: PrivateMode) {
         let mode = match mode {
             PrivateMode::Named(mode) => mode,
+            PrivateMode::Unknown(GRAPHEME_CLUSTERING_MODE) => {
+                self.mode.insert(TermMode::GRAPHEME_CLUSTERING);
+                return;
+            },
+            PrivateMode::Unknown(LEFT_RIGHT_MARGIN_MODE) => {
+                self.mode.insert(TermMode::LEFT_RIGHT_MARGIN);
+                return;
+            },
             PrivateMode::Unknown(mode) => {
                 debug!("Ignoring unknown mode {mode} in set_private_mode");
                 return;
@@ -2005,6 +2386,15 @@ impl<T: EventListener> Handler for Term<T> {
     fn unset_private_mode(&mut self, mode: PrivateMode) {
         let mode = match mode {
             PrivateMode::Named(mode) => mode,
+            PrivateMode::Unknown(GRAPHEME_CLUSTERING_MODE) => {
+                self.mode.remove(TermMode::GRAPHEME_CLUSTERING);
+                return;
+            },
+            PrivateMode::Unknown(LEFT_RIGHT_MARGIN_MODE) => {
+                self.mode.remove(TermMode::LEFT_RIGHT_MARGIN);
+                self.horizontal_margins = Column(0)..Column(self.columns());
+                return;
+            },
             PrivateMode::Unknown(mode) => {
                 debug!("Ignoring unknown mode {mode} in unset_private_mode");
                 return;
@@ -2092,6 +2482,12 @@ impl<T: EventListener> Handler for Term<T> {
                 NamedPrivateMode::SyncUpdate => ModeState::Reset,
                 NamedPrivateMode::ColumnMode => ModeState::NotSupported,
             },
+            PrivateMode::Unknown(GRAPHEME_CLUSTERING_MODE) => {
+                self.mode.contains(TermMode::GRAPHEME_CLUSTERING).into()
+            },
+            PrivateMode::Unknown(LEFT_RIGHT_MARGIN_MODE) => {
+                self.mode.contains(TermMode::LEFT_RIGHT_MARGIN).into()
+            },
             PrivateMode::Unknown(_) => ModeState::NotSupported,
         };
 
@@ -2279,6 +2675,228 @@ impl<T: EventListener> Handler for Term<T> {
     }
 }
 
+impl<T: EventListener> parser::Handler for Term<T> {
+    #[inline]
+    fn semantic_mark(&mut self, kind: SemanticMarkKind) {
+        trace!("Setting semantic mark: {kind:?}");
+        let point = self.grid.cursor.point;
+        self.grid[point.line].set_semantic_mark(SemanticMark::new(kind, point.column));
+    }
+
+    #[inline]
+    fn set_working_directory(&mut self, working_directory: WorkingDirectory) {
+        trace!("Setting working directory to {working_directory:?}");
+
+        if self.working_directory.as_ref() == Some(&working_directory) {
+            return;
+        }
+
+        self.working_directory = Some(working_directory.clone());
+        self.event_proxy.send_event(Event::WorkingDirectory(working_directory));
+    }
+
+    fn sixel_graphic(&mut self, image: Image) {
+        let (cell_width, cell_height) = self.graphics.cell_size();
+        if cell_width == 0 || cell_height == 0 {
+            debug!("Ignoring sixel image without known cell size");
+            return;
+        }
+
+        trace!("Placing {}x{} sixel image", image.width, image.height);
+
+        let columns = image.width.div_ceil(cell_width);
+        let lines = image.height.div_ceil(cell_height);
+        let graphic = self.graphics.insert_sixel(Arc::new(image));
+
+        // Cover all cells from the cursor, scrolling when the image exceeds the screen.
+        let start_column = self.grid.cursor.point.column;
+        let end_column = cmp::min(start_column.0 + columns, self.columns());
+        for line in 0..lines {
+            if line != 0 {
+                self.linefeed();
+            }
+
+            let cursor_line = self.grid.cursor.point.line;
+            for column in start_column.0..end_column {
+                let cell = &mut self.grid[cursor_line][Column(column)];
+                *cell = Cell::default();
+                cell.set_graphic(Some(GraphicCell {
+                    graphic: graphic.clone(),
+                    offset_x: ((column - start_column.0) * cell_width) as i32,
+                    offset_y: (line * cell_height) as i32,
+                    width: cell_width as u32,
+                    height: cell_height as u32,
+                    z_index: 0,
+                    placement: None,
+                }));
+            }
+        }
+
+        // Text continues below the image.
+        self.linefeed();
+        self.grid.cursor.point.column = start_column;
+        self.grid.cursor.input_needs_wrap = false;
+
+        self.mark_fully_damaged();
+    }
+
+    #[inline]
+    fn kitty_graphics(&mut self, command: kitty::Command) {
+        self.kitty_graphics_command(command);
+    }
+
+    #[inline]
+    fn copy_rectangle(&mut self, source: Rectangle, top: usize, left: usize) {
+        trace!("Copying rectangle {source:?} to line {top}, column {left}");
+        self.copy_rectangle_area(source, top, left);
+    }
+
+    #[inline]
+    fn fill_rectangle(&mut self, area: Rectangle, c: char) {
+        trace!("Filling rectangle {area:?} with {c:?}");
+        self.fill_rectangle_area(area, c);
+    }
+
+    #[inline]
+    fn erase_rectangle(&mut self, area: Rectangle) {
+        trace!("Erasing rectangle {area:?}");
+        self.erase_rectangle_area(area, false);
+    }
+
+    #[inline]
+    fn selective_erase_rectangle(&mut self, area: Rectangle) {
+        trace!("Selectively erasing rectangle {area:?}");
+        self.erase_rectangle_area(area, true);
+    }
+
+    #[inline]
+    fn change_rectangle_attributes(&mut self, area: Rectangle, change: AttributeChange) {
+        trace!("Changing attributes of rectangle {area:?}: {change:?}");
+        self.change_area_attributes(area, change);
+    }
+
+    #[inline]
+    fn set_attribute_change_extent(&mut self, rectangle: bool) {
+        trace!("Setting attribute change extent to rectangle: {rectangle}");
+        self.mode.set(TermMode::RECTANGULAR_ATTRIBUTE_CHANGE, rectangle);
+    }
+
+    #[inline]
+    fn set_character_protection(&mut self, protected: bool) {
+        trace!("Setting character protection: {protected}");
+        self.grid.cursor.template.flags.set(Flags::PROTECTED, protected);
+    }
+
+    #[inline]
+    fn left_right_margin_mode(&self) -> bool {
+        self.mode.contains(TermMode::LEFT_RIGHT_MARGIN)
+    }
+
+    #[inline]
+    fn set_horizontal_margins(&mut self, left: usize, right: Option<usize>) {
+        // Fallback to the last column as default.
+        let right = cmp::min(right.unwrap_or_else(|| self.columns()), self.columns());
+        let left = cmp::max(left, 1);
+
+        if left >= right {
+            debug!("Invalid horizontal margins: ({left};{right})");
+            return;
+        }
+
+        trace!("Setting horizontal margins: ({left};{right})");
+
+        self.horizontal_margins = Column(left - 1)..Column(right);
+        self.goto(0, 0);
+    }
+
+    #[inline]
+    fn desktop_notification(&mut self, notification: Notification) {
+        trace!("Requesting desktop notification: {notification:?}");
+        let Notification { title, body, urgency } = notification;
+        self.event_proxy.send_event(Event::Notification { title, body, urgency });
+    }
+
+    #[inline]
+    fn desktop_notification_chunk(&mut self, chunk: NotificationChunk) {
+        // Chunks with a different identifier start a new notification.
+        let notification = match self.pending_notification.take() {
+            Some((id, notification)) if id == chunk.id => notification,
+            _ => Some(Notification::default()),
+        };
+
+        let (id, done) = (chunk.id.clone(), chunk.done);
+        let notification = notification.and_then(|mut notification| {
+            chunk.apply(&mut notification);
+
+            let size = notification.title.len() + notification.body.len();
+            if size > NOTIFICATION_MAX_SIZE {
+                debug!("Dropping notification exceeding {NOTIFICATION_MAX_SIZE} bytes");
+                return None;
+            }
+
+            Some(notification)
+        });
+
+        if !done {
+            self.pending_notification = Some((id, notification));
+        } else if let Some(notification) = notification {
+            self.desktop_notification(notification);
+        }
+    }
+
+    #[inline]
+    fn set_progress(&mut self, progress: Option<Progress>) {
+        trace!("Setting progress to {progress:?}");
+
+        // Keep the previous percentage when only the state changes.
+        let progress = progress.map(|mut progress| {
+            if progress.state != ProgressState::Indeterminate {
+                progress.percent = progress.percent.or(self.progress.and_then(|p| p.percent));
+            }
+            progress
+        });
+
+        if self.progress == progress {
+            return;
+        }
+
+        self.progress = progress;
+        self.event_proxy.send_event(Event::Progress(progress));
+    }
+
+    #[inline]
+    fn report_terminfo_capabilities(&mut self, names: String) {
+        self.reply_terminfo_capabilities(&names);
+    }
+
+    #[inline]
+    fn report_setting(&mut self, setting: String) {
+        self.reply_setting(&setting);
+    }
+
+    #[inline]
+    fn set_line_attribute(&mut self, attribute: LineAttribute) {
+        trace!("Setting line attribute: {attribute:?}");
+
+        let line = self.grid.cursor.point.line;
+        self.grid[line].set_line_attribute(attribute);
+
+        // Characters which no longer fit into a double-width line are lost.
+        if attribute.is_double_width() {
+            let last_column = self.line_last_column(line);
+            for cell in &mut self.grid[line][last_column + 1..] {
+                *cell = Cell::default();
+            }
+
+            let column = &mut self.grid.cursor.point.column;
+            *column = cmp::min(*column, last_column);
+        }
+
+        let columns = self.columns();
+        self.damage.damage_line(line.0 as usize, 0, columns - 1);
+    }
+}
+
 /// The state of the [`Mode`] and [`PrivateMode`].
 #[repr(u8)]
 #[derive(Debug, Clone, Copy)]
@@ -2424,6 +3042,9 @@ impl<'a> RenderableContent<'a> {
 pub mod test {
     use super::*;
 
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
     #[cfg(feature = "serde")]
     use serde::{Deserialize, Serialize};
 
@@ -2455,6 +3076,34 @@ pub mod test {
         }
     }
 
+    /// Listener collecting all events sent by the terminal.
+    #[derive(Clone, Default)]
+    pub struct EventCollector(Rc<RefCell<Vec<Event>>>);
+
+    impl EventCollector {
+        /// Take all events collected so far.
+        pub fn take(&self) -> Vec<Event> {
+            self.0.take()
+        }
+
+        /// Take the text of all [`Event::PtyWrite`] events collected so far.
+        pub fn take_pty_writes(&self) -> Vec<String> {
+            let events = self.take().into_iter();
+            events
+                .filter_map(|event| match event {
+                    Event::PtyWrite(text) => Some(text),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    impl EventListener for EventCollector {
+        fn send_event(&self, event: Event) {
+            self.0.borrow_mut().push(event);
+        }
+    }
+
     /// Construct a terminal from its content as string.
     ///
     /// A `\n` will break line and `\r\n` will break line without wrapping.
@@ -2523,8 +3172,8 @@ mod tests {
     use crate::index::{Column, Point, Side};
     use crate::selection::{Selection, SelectionType};
     use crate::term::cell::{Cell, Flags};
-    use crate::term::test::TermSize;
-    use crate::vte::ansi::{self, CharsetIndex, Handler, StandardCharset};
+    use crate::term::test::{EventCollector, TermSize};
+    use crate::vte::ansi::{self, CharsetIndex, Handler, PrivateMode, StandardCharset};
 
     #[test]
     fn scroll_display_page_up() {
@@ -2754,6 +3403,76 @@ mod tests {
         assert_eq!(deserialized, grid);
     }
 
+    #[test]
+    fn grapheme_clusters() {
+        let size = TermSize::new(5, 2);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        term.set_private_mode(PrivateMode::Unknown(2027));
+
+        // Emoji joined by ZWJ share a single fullwidth cell.
+        for c in "\u{1F468}\u{200D}\u{1F469}x".chars() {
+            term.input(c);
+        }
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, '\u{1F468}');
+        assert_eq!(
+            term.grid()[Line(0)][Column(0)].zerowidth(),
+            Some(&['\u{200D}', '\u{1F469}'][..])
+        );
+        assert!(term.grid()[Line(0)][Column(1)].flags.contains(Flags::WIDE_CHAR_SPACER));
+        assert_eq!(term.grid()[Line(0)][Column(2)].c, 'x');
+
+        // Emoji presentation grows to two cells, wrapping to the next line if necessary.
+        term.input('y');
+        term.input('\u{2764}');
+        term.input('\u{FE0F}');
+        assert!(term.grid()[Line(0)][Column(4)].flags.contains(Flags::LEADING_WIDE_CHAR_SPACER));
+        assert_eq!(term.grid()[Line(1)][Column(0)].c, '\u{2764}');
+        assert!(term.grid()[Line(1)][Column(0)].flags.contains(Flags::WIDE_CHAR));
+        assert_eq!(term.grid()[Line(1)][Column(0)].zerowidth(), Some(&['\u{FE0F}'][..]));
+        assert_eq!(term.grid().cursor.point, Point::new(Line(1), Column(2)));
+
+        // Regional indicator pairs form a single flag.
+        term.input('\u{1F1FA}');
+        term.input('\u{1F1F8}');
+        assert_eq!(term.grid()[Line(1)][Column(2)].c, '\u{1F1FA}');
+        assert!(term.grid()[Line(1)][Column(2)].flags.contains(Flags::WIDE_CHAR));
+        assert_eq!(term.grid()[Line(1)][Column(2)].zerowidth(), Some(&['\u{1F1F8}'][..]));
+        assert_eq!(term.grid().cursor.point, Point::new(Line(1), Column(4)));
+
+        // Selecting part of a cluster copies all of it.
+        let point = Point::new(Line(1), Column(3));
+        let mut selection = Selection::new(SelectionType::Simple, point, Side::Left);
+        selection.update(point, Side::Right);
+        term.selection = Some(selection);
+        assert_eq!(term.selection_to_string(), Some(String::from("\u{1F1FA}\u{1F1F8}")));
+    }
+
+    #[test]
+    fn grapheme_clusters_after_cursor_motion() {
+        let size = TermSize::new(5, 1);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        term.set_private_mode(PrivateMode::Unknown(2027));
+
+        // Clusters are not continued after moving the cursor away from them.
+        term.input('\u{1F1FA}');
+        term.goto(0, 3);
+        term.input('\u{1F1F8}');
+        assert_eq!(term.grid()[Line(0)][Column(0)].zerowidth(), None);
+        assert_eq!(term.grid()[Line(0)][Column(3)].c, '\u{1F1F8}');
+    }
+
+    #[test]
+    fn grapheme_clusters_disabled() {
+        let size = TermSize::new(5, 1);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+
+        term.input('\u{1F1FA}');
+        term.input('\u{1F1F8}');
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, '\u{1F1FA}');
+        assert_eq!(term.grid()[Line(0)][Column(1)].c, '\u{1F1F8}');
+        assert_eq!(term.grid().cursor.point, Point::new(Line(0), Column(2)));
+    }
+
     #[test]
     fn input_line_drawing_character() {
         let size = TermSize::new(7, 17);
@@ -2881,6 +3600,207 @@ mod tests {
         assert_eq!(term.grid, scrolled_grid);
     }
 
+    #[test]
+    fn semantic_marks_survive_scrolling() {
+        let size = TermSize::new(10, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: parser::Processor = parser::Processor::new();
+
+        parser.advance(&mut term, b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07");
+        parser.advance(&mut term, b"a\r\nb\r\n\x1b]133;D;2\x07\x1b]133;A\x07$ ");
+
+        let marks: Vec<_> = term.semantic_marks(..).collect();
+        assert_eq!(marks, vec![
+            (Point::new(Line(-1), Column(0)), SemanticMarkKind::PromptStart),
+            (Point::new(Line(-1), Column(2)), SemanticMarkKind::CommandStart),
+            (Point::new(Line(0), Column(0)), SemanticMarkKind::OutputStart),
+            (Point::new(Line(2), Column(0)), SemanticMarkKind::CommandFinished(Some(2))),
+            (Point::new(Line(2), Column(0)), SemanticMarkKind::PromptStart),
+        ]);
+
+        assert_eq!(term.prompt_before(Line(2)), Some(Point::new(Line(-1), Column(0))));
+        assert_eq!(term.prompt_after(Line(-1)), Some(Point::new(Line(2), Column(0))));
+        assert_eq!(term.prompt_after(Line(2)), None);
+
+        // Clearing the history only removes marks from the history.
+        term.clear_screen(ansi::ClearMode::Saved);
+        assert_eq!(term.semantic_marks(..).count(), 3);
+        assert_eq!(term.prompt_before(Line(2)), None);
+    }
+
+    #[test]
+    fn horizontal_margins() {
+        let size = TermSize::new(6, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: parser::Processor = parser::Processor::new();
+
+        let text = |term: &Term<_>, line| -> String {
+            term.grid[Line(line)][..].iter().map(|cell| cell.c).collect()
+        };
+
+        parser.advance(&mut term, b"123456\r\n123456\r\n123456\x1b[?69h\x1b[2;4s");
+        assert_eq!(term.grid.cursor.point, Point::new(Line(0), Column(0)));
+
+        // Text wraps at the right margin, continuing at the left margin.
+        parser.advance(&mut term, b"abcdefg");
+        assert_eq!(text(&term, 0), "abcd56");
+        assert_eq!(text(&term, 1), "1efg56");
+
+        // Only the text inside the margins is scrolled.
+        parser.advance(&mut term, b"\r\n\n");
+        assert_eq!(term.grid.cursor.point, Point::new(Line(2), Column(1)));
+        assert_eq!(text(&term, 0), "aefg56");
+        assert_eq!(text(&term, 1), "123456");
+        assert_eq!(text(&term, 2), "1   56");
+
+        // Characters are deleted and inserted inside the margins.
+        parser.advance(&mut term, b"\x1b[2;3H\x1b[P");
+        assert_eq!(text(&term, 1), "124 56");
+        parser.advance(&mut term, b"\x1b[@");
+        assert_eq!(text(&term, 1), "12 456");
+
+        // Without DECLRMM, `CSI s` saves the cursor and margins are reset.
+        parser.advance(&mut term, b"\x1b[?69l\x1b[3;4s\r");
+        assert_eq!(term.horizontal_margins, Column(0)..Column(6));
+        assert_eq!(term.grid.cursor.point, Point::new(Line(1), Column(0)));
+    }
+
+    #[test]
+    fn double_width_lines() {
+        let size = TermSize::new(10, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: parser::Processor = parser::Processor::new();
+
+        let text = |term: &Term<_>, line| -> String {
+            term.grid[Line(line)][..].iter().map(|cell| cell.c).collect()
+        };
+
+        // Text beyond the middle of the line is lost.
+        parser.advance(&mut term, b"abcdefgh\x1b#6");
+        assert_eq!(term.grid[Line(0)].line_attribute(), LineAttribute::DoubleWidth);
+        assert_eq!(text(&term, 0), "abcde     ");
+        assert_eq!(term.grid.cursor.point.column, Column(4));
+
+        // Cursor addressing and wrapping stops at the middle of the line.
+        parser.advance(&mut term, b"\x1b[1;9HXYZ");
+        assert_eq!(text(&term, 0), "abcdX     ");
+        assert_eq!(text(&term, 1), "YZ        ");
+
+        parser.advance(&mut term, b"\x1b#3\r\n\x1b#4");
+        assert_eq!(term.grid[Line(1)].line_attribute(), LineAttribute::DoubleHeightTop);
+        assert_eq!(term.grid[Line(2)].line_attribute(), LineAttribute::DoubleHeightBottom);
+
+        // Clearing the screen resets all lines to single width.
+        parser.advance(&mut term, b"\x1b[2J");
+        assert_eq!(term.grid[Line(0)].line_attribute(), LineAttribute::Normal);
+        assert_eq!(term.grid[Line(2)].line_attribute(), LineAttribute::Normal);
+    }
+
+    #[test]
+    fn progress() {
+        let size = TermSize::new(10, 3);
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut parser: parser::Processor = parser::Processor::new();
+
+        parser.advance(&mut term, b"\x1b]9;4;1;30\x07");
+        assert_eq!(
+            term.progress(),
+            Some(Progress { state: ProgressState::Normal, percent: Some(30) })
+        );
+
+        // Errors without a percentage keep the previous one.
+        parser.advance(&mut term, b"\x1b]9;4;2\x1b\\");
+        assert_eq!(
+            term.progress(),
+            Some(Progress { state: ProgressState::Error, percent: Some(30) })
+        );
+
+        // A notification with the text `4` doesn't change the progress.
+        parser.advance(&mut term, b"\x1b]9;4\x07");
+        assert!(term.progress().is_some());
+
+        parser.advance(&mut term, b"\x1b]9;4;0\x07");
+        assert_eq!(term.progress(), None);
+    }
+
+    #[test]
+    fn notification_size_limit() {
+        let size = TermSize::new(10, 3);
+        let events = EventCollector::default();
+        let mut term = Term::new(Config::default(), &size, events.clone());
+        let mut parser: parser::Processor = parser::Processor::new();
+
+        let chunk = "x".repeat(NOTIFICATION_MAX_SIZE / 2 + 1);
+        parser.advance(&mut term, format!("\x1b]99;i=1:d=0;{chunk}\x07").as_bytes());
+        parser.advance(&mut term, format!("\x1b]99;i=1:d=0:p=body;{chunk}\x07").as_bytes());
+        parser.advance(&mut term, b"\x1b]99;i=1:p=body;done\x07");
+        parser.advance(&mut term, b"\x1b]99;i=2;title\x07");
+
+        let titles: Vec<_> = events
+            .take()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Notification { title, .. } => Some(title),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(titles, vec![String::from("title")]);
+    }
+
+    #[test]
+    fn working_directory_change() {
+        let size = TermSize::new(10, 3);
+        let events = EventCollector::default();
+        let mut term = Term::new(Config::default(), &size, events.clone());
+        let mut parser: parser::Processor = parser::Processor::new();
+
+        parser.advance(&mut term, b"\x1b]7;file://host/tmp/a%3Bb\x07");
+        parser.advance(&mut term, b"\x1b]7;file://host/tmp/a;b\x1b\\\x1b]7;invalid\x07");
+
+        let expected = WorkingDirectory { hostname: Some("host".into()), path: "/tmp/a;b".into() };
+        assert_eq!(term.working_directory(), Some(&expected));
+        let changes: Vec<_> = events
+            .take()
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::WorkingDirectory(cwd) => Some(cwd),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changes, vec![expected]);
+    }
+
+    #[test]
+    fn sixel_placement_and_eviction() {
+        let size = TermSize::new(5, 3);
+        let config = Config { scrolling_history: 1, ..Config::default() };
+        let mut term = Term::new(config, &size, VoidListener);
+        term.graphics_mut().set_cell_size(2, 4);
+        let mut parser: parser::Processor = parser::Processor::new();
+
+        // 3x6 pixel image covering 2x2 cells.
+        parser.advance(&mut term, b"a\x1bP0;1q!3~-!3A\x1b\\b");
+
+        let updates = term.graphics_mut().take_updates();
+        assert_eq!(updates.added.len(), 1);
+        let id = updates.added[0].id;
+
+        let graphic = term.grid[Line(1)][Column(2)].graphic().unwrap();
+        assert_eq!(graphic.graphic.id(), id);
+        assert_eq!((graphic.offset_x, graphic.offset_y), (2, 4));
+        assert!(term.grid[Line(1)][Column(3)].graphic().is_none());
+        assert_eq!(term.grid[Line(2)][Column(1)].c, 'b');
+
+        // Image is kept while it's in the scrollback history.
+        term.newline();
+        assert!(term.graphics_mut().take_updates().is_empty());
+
+        // Rotating the image out of the history removes it.
+        term.newline();
+        term.newline();
+        assert_eq!(term.graphics_mut().take_updates().removed, vec![id]);
+    }
+
     #[test]
     fn vi_cursor_keep_pos_on_scrollback_buffer() {
         let size = TermSize::new(5, 10);
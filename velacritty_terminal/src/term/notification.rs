@@ -0,0 +1,196 @@
+//! Desktop notifications requested by applications.
+//!
+//! Three escape sequences are commonly used for this: iTerm2's `OSC 9`, urxvt's
+//! `OSC 777 ; notify` and kitty's `OSC 99`. The latter allows splitting a notification into
+//! multiple chunks, which are combined by the terminal before the notification is shown.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as Base64;
+
+/// Urgency of a desktop notification.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// Desktop notification.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// Notification title, empty if the application didn't provide one.
+    pub title: String,
+
+    /// Notification text.
+    pub body: String,
+
+    /// Importance of the notification.
+    pub urgency: Urgency,
+}
+
+impl Notification {
+    /// Parse the parameters of an `OSC 9` escape, excluding the `9` itself.
+    pub fn parse_osc9(params: &[&[u8]]) -> Option<Self> {
+        // Numeric first parameters are ConEmu's extensions, like progress reports.
+        let first = params.first()?;
+        if params.len() > 1 && first.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+
+        let body = join_params(params)?;
+        Some(Self { body, ..Default::default() })
+    }
+
+    /// Parse the parameters of an `OSC 777` escape, excluding the `777` itself.
+    pub fn parse_osc777(params: &[&[u8]]) -> Option<Self> {
+        let (command, params) = params.split_first()?;
+        if *command != b"notify" {
+            return None;
+        }
+
+        let (title, params) = params.split_first()?;
+        let title = String::from_utf8_lossy(title).into_owned();
+        let body = join_params(params).unwrap_or_default();
+
+        Some(Self { title, body, ..Default::default() })
+    }
+}
+
+/// Chunk of a kitty notification (`OSC 99`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationChunk {
+    /// Identifier of the notification the chunk belongs to.
+    pub id: String,
+
+    /// Whether this is the last chunk of the notification.
+    pub done: bool,
+
+    urgency: Option<Urgency>,
+    payload: Payload,
+    text: String,
+}
+
+/// Part of the notification which is transmitted by a chunk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Payload {
+    Title,
+    Body,
+}
+
+impl NotificationChunk {
+    /// Parse the parameters of an `OSC 99` escape, excluding the `99` itself.
+    ///
+    /// Only the title, body and urgency of notifications are supported; chunks for any other
+    /// payload type are ignored.
+    pub fn parse(params: &[&[u8]]) -> Option<Self> {
+        let (metadata, params) = params.split_first()?;
+
+        let mut chunk = Self {
+            id: String::new(),
+            done: true,
+            urgency: None,
+            payload: Payload::Title,
+            text: String::new(),
+        };
+        let mut base64 = false;
+
+        for option in metadata.split(|&byte| byte == b':').filter(|option| !option.is_empty()) {
+            let (key, value) = match option.iter().position(|&byte| byte == b'=') {
+                Some(index) => (&option[..index], &option[index + 1..]),
+                None => (option, &[][..]),
+            };
+
+            match key {
+                b"i" => chunk.id = String::from_utf8_lossy(value).into_owned(),
+                b"d" => chunk.done = value != b"0",
+                b"e" => base64 = value == b"1",
+                b"u" => {
+                    chunk.urgency = match value {
+                        b"0" => Some(Urgency::Low),
+                        b"2" => Some(Urgency::Critical),
+                        _ => Some(Urgency::Normal),
+                    }
+                },
+                b"p" => {
+                    chunk.payload = match value {
+                        b"title" => Payload::Title,
+                        b"body" => Payload::Body,
+                        _ => return None,
+                    }
+                },
+                _ => (),
+            }
+        }
+
+        let text = params.join(&b';');
+        let text = if base64 { Base64.decode(text).ok()? } else { text };
+        chunk.text = String::from_utf8_lossy(&text).into_owned();
+
+        Some(chunk)
+    }
+
+    /// Add the chunk's content to a notification.
+    pub fn apply(self, notification: &mut Notification) {
+        match self.payload {
+            Payload::Title => notification.title.push_str(&self.text),
+            Payload::Body => notification.body.push_str(&self.text),
+        }
+
+        if let Some(urgency) = self.urgency {
+            notification.urgency = urgency;
+        }
+    }
+}
+
+/// Join escape sequence parameters which were split at semicolons.
+fn join_params(params: &[&[u8]]) -> Option<String> {
+    let text = params.join(&b';');
+    (!text.is_empty()).then(|| String::from_utf8_lossy(&text).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_osc9() {
+        let notification = Notification::parse_osc9(&[b"build", b" done"]).unwrap();
+        assert_eq!(notification.title, "");
+        assert_eq!(notification.body, "build; done");
+
+        assert_eq!(Notification::parse_osc9(&[b"4", b"1", b"50"]), None);
+        assert_eq!(Notification::parse_osc9(&[b""]), None);
+        assert!(Notification::parse_osc9(&[b"42"]).is_some());
+    }
+
+    #[test]
+    fn parse_osc777() {
+        let notification = Notification::parse_osc777(&[b"notify", b"make", b"done"]).unwrap();
+        assert_eq!(notification.title, "make");
+        assert_eq!(notification.body, "done");
+
+        assert_eq!(Notification::parse_osc777(&[b"preexec"]), None);
+    }
+
+    #[test]
+    fn kitty_chunks() {
+        let mut notification = Notification::default();
+
+        let title = NotificationChunk::parse(&[b"i=1:d=0:u=2", b"Build"]).unwrap();
+        assert_eq!((title.id.as_str(), title.done), ("1", false));
+        title.apply(&mut notification);
+
+        let body = NotificationChunk::parse(&[b"i=1:p=body:e=1", b"ZG9uZQ=="]).unwrap();
+        assert!(body.done);
+        body.apply(&mut notification);
+
+        assert_eq!(notification, Notification {
+            title: "Build".into(),
+            body: "done".into(),
+            urgency: Urgency::Critical,
+        });
+
+        assert_eq!(NotificationChunk::parse(&[b"i=1:p=close", b""]), None);
+    }
+}
@@ -0,0 +1,84 @@
+//! Progress reports from the `OSC 9 ; 4` escape sequence.
+//!
+//! The sequence was introduced by ConEmu and is also supported by Windows Terminal. It is used
+//! by tools like `winget`, `cargo` and `systemd` to report the progress of long-running tasks.
+
+/// State of a progress report.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProgressState {
+    /// Task is progressing normally.
+    #[default]
+    Normal,
+
+    /// Task has failed.
+    Error,
+
+    /// Task is running, but its progress is unknown.
+    Indeterminate,
+
+    /// Task is paused or needs attention.
+    Paused,
+}
+
+/// Progress of a long-running task.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub state: ProgressState,
+
+    /// Completion percentage from 0 to 100, `None` if the application didn't provide one.
+    pub percent: Option<u8>,
+}
+
+impl Progress {
+    /// Parse the parameters of an `OSC 9 ; 4` escape, excluding the `9 ; 4` itself.
+    ///
+    /// Returns `Some(None)` when the progress report should be removed.
+    pub fn parse(params: &[&[u8]]) -> Option<Option<Self>> {
+        let state = match params.first().copied().unwrap_or_default() {
+            b"0" | b"" => return Some(None),
+            b"1" => ProgressState::Normal,
+            b"2" => ProgressState::Error,
+            b"3" => ProgressState::Indeterminate,
+            b"4" => ProgressState::Paused,
+            _ => return None,
+        };
+
+        let percent = match params.get(1).filter(|percent| !percent.is_empty()) {
+            Some(percent) if percent.iter().all(u8::is_ascii_digit) => {
+                // Values too large to be parsed are clamped like all other values above 100.
+                let percent = std::str::from_utf8(percent).ok()?.parse::<u32>().unwrap_or(u32::MAX);
+                Some(percent.min(100) as u8)
+            },
+            Some(_) => return None,
+            // Progress without a value starts at zero.
+            None if state == ProgressState::Normal => Some(0),
+            None => None,
+        };
+
+        Some(Some(Self { state, percent }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let progress = |state, percent| Some(Some(Progress { state, percent }));
+
+        assert_eq!(Progress::parse(&[b"1", b"42"]), progress(ProgressState::Normal, Some(42)));
+        assert_eq!(Progress::parse(&[b"1"]), progress(ProgressState::Normal, Some(0)));
+        assert_eq!(Progress::parse(&[b"2", b"250"]), progress(ProgressState::Error, Some(100)));
+        assert_eq!(Progress::parse(&[b"1", b"1000"]), progress(ProgressState::Normal, Some(100)));
+        let huge = progress(ProgressState::Normal, Some(100));
+        assert_eq!(Progress::parse(&[b"1", b"99999999999999999999"]), huge);
+        assert_eq!(Progress::parse(&[b"3"]), progress(ProgressState::Indeterminate, None));
+        assert_eq!(Progress::parse(&[b"4", b""]), progress(ProgressState::Paused, None));
+        assert_eq!(Progress::parse(&[b"0", b"50"]), Some(None));
+        assert_eq!(Progress::parse(&[]), Some(None));
+
+        assert_eq!(Progress::parse(&[b"5"]), None);
+        assert_eq!(Progress::parse(&[b"1", b"x"]), None);
+    }
+}
@@ -0,0 +1,173 @@
+//! Replies to XTGETTCAP and DECRQSS queries.
+//!
+//! Applications use these to look up terminfo capabilities and the current terminal settings
+//! directly from the terminal, which is useful when the terminfo entry isn't installed on the
+//! host they are running on.
+
+use std::fmt::Write;
+
+use log::trace;
+
+use crate::event::{Event, EventListener};
+use crate::grid::Dimensions;
+use crate::term::cell::Flags;
+use crate::term::{Term, TermMode};
+use crate::vte::ansi::CursorShape;
+
+/// Terminal name reported through the `TN` capability.
+const TERMINAL_NAME: &str = "velacritty";
+
+/// Terminfo capabilities which can be queried with XTGETTCAP.
+///
+/// Boolean capabilities don't have a value.
+const CAPABILITIES: &[(&str, Option<&str>)] = &[
+    ("TN", Some(TERMINAL_NAME)),
+    ("name", Some(TERMINAL_NAME)),
+    ("Co", Some("256")),
+    ("colors", Some("256")),
+    ("RGB", None),
+    ("Tc", None),
+    ("bce", None),
+    ("kbs", Some("\x7f")),
+    ("setrgbf", Some("\x1b[38:2:%p1%d:%p2%d:%p3%dm")),
+    ("setrgbb", Some("\x1b[48:2:%p1%d:%p2%d:%p3%dm")),
+    ("Smulx", Some("\x1b[4:%p1%dm")),
+    ("Setulc", Some("\x1b[58:2::%p1%{65536}%/%d:%p1%{256}%/%{255}%&%d:%p1%{255}%&%d%;m")),
+    ("Ss", Some("\x1b[%p1%d q")),
+    ("Se", Some("\x1b[0 q")),
+    ("Cs", Some("\x1b]12;%p1%s\x07")),
+    ("Cr", Some("\x1b]112\x07")),
+    ("Ms", Some("\x1b]52;%p1%s;%p2%s\x07")),
+    ("Sync", Some("\x1b[?2026%?%p1%{1}%-%tl%eh%;")),
+    ("BD", Some("\x1b[?2004l")),
+    ("BE", Some("\x1b[?2004h")),
+    ("PS", Some("\x1b[200~")),
+    ("PE", Some("\x1b[201~")),
+    ("smcup", Some("\x1b[?1049h\x1b[22;0;0t")),
+    ("rmcup", Some("\x1b[?1049l\x1b[23;0;0t")),
+];
+
+impl<T: EventListener> Term<T> {
+    /// Reply to an XTGETTCAP request for the hex-encoded, semicolon separated capability names.
+    pub(super) fn reply_terminfo_capabilities(&mut self, names: &str) {
+        for hex_name in names.split(';') {
+            let capability = decode_hex(hex_name)
+                .and_then(|name| CAPABILITIES.iter().find(|(capability, _)| *capability == name));
+
+            trace!("Reporting terminfo capability {hex_name}: {capability:?}");
+
+            let text = match capability {
+                Some((_, Some(value))) => format!("\x1bP1+r{hex_name}={}\x1b\\", encode_hex(value)),
+                Some((_, None)) => format!("\x1bP1+r{hex_name}\x1b\\"),
+                None => format!("\x1bP0+r{hex_name}\x1b\\"),
+            };
+            self.event_proxy.send_event(Event::PtyWrite(text));
+        }
+    }
+
+    /// Reply to a DECRQSS request for the current value of a setting.
+    pub(super) fn reply_setting(&mut self, setting: &str) {
+        let value = match setting {
+            "m" => Some(self.grid.cursor.template.sgr_parameters()),
+            "r" => {
+                let region = &self.scroll_region;
+                Some(format!("{};{}", region.start.0 + 1, region.end.0))
+            },
+            "s" => {
+                let margins = &self.horizontal_margins;
+                Some(format!("{};{}", margins.start.0 + 1, margins.end.0))
+            },
+            " q" => {
+                let style = self.cursor_style.unwrap_or(self.config.default_cursor_style);
+                let shape = match style.shape {
+                    CursorShape::Underline => 3,
+                    CursorShape::Beam => 5,
+                    _ => 1,
+                };
+                Some((shape + u8::from(!style.blinking)).to_string())
+            },
+            "\"q" => {
+                let protected = self.grid.cursor.template.flags.contains(Flags::PROTECTED);
+                Some(u8::from(protected).to_string())
+            },
+            "*x" => {
+                let rectangle = self.mode.contains(TermMode::RECTANGULAR_ATTRIBUTE_CHANGE);
+                Some((u8::from(rectangle) + 1).to_string())
+            },
+            "t" => Some(self.screen_lines().to_string()),
+            _ => None,
+        };
+
+        trace!("Reporting setting {setting:?}: {value:?}");
+
+        let text = match value {
+            Some(value) => format!("\x1bP1$r{value}{setting}\x1b\\"),
+            None => String::from("\x1bP0$r\x1b\\"),
+        };
+        self.event_proxy.send_event(Event::PtyWrite(text));
+    }
+}
+
+/// Decode a hex-encoded string.
+fn decode_hex(hex: &str) -> Option<String> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Encode a string as uppercase hex.
+fn encode_hex(text: &str) -> String {
+    text.bytes().fold(String::with_capacity(text.len() * 2), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02X}");
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::parser::Processor;
+    use crate::term::Config;
+    use crate::term::test::{EventCollector, TermSize};
+
+    fn replies(bytes: &[u8]) -> Vec<String> {
+        let replies = EventCollector::default();
+        let size = TermSize::new(10, 5);
+        let mut term = Term::new(Config::default(), &size, replies.clone());
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, bytes);
+
+        replies.take_pty_writes()
+    }
+
+    #[test]
+    fn terminfo_capabilities() {
+        // Query for `TN`, `RGB` and `xx`.
+        assert_eq!(replies(b"\x1bP+q544E;524742;7878\x1b\\"), [
+            "\x1bP1+r544E=76656C61637269747479\x1b\\",
+            "\x1bP1+r524742\x1b\\",
+            "\x1bP0+r7878\x1b\\",
+        ]);
+
+        assert_eq!(replies(b"\x1bP+q5\x1b\\"), ["\x1bP0+r5\x1b\\"]);
+    }
+
+    #[test]
+    fn settings() {
+        let sgr = b"\x1b[1;4:3;38;5;100;41;58:2::1:2:3m\x1bP$qm\x1b\\";
+        assert_eq!(replies(sgr), ["\x1bP1$r0;1;4:3;38:5:100;41;58:2::1:2:3m\x1b\\"]);
+
+        assert_eq!(replies(b"\x1b[2;4r\x1bP$qr\x1b\\"), ["\x1bP1$r2;4r\x1b\\"]);
+        assert_eq!(replies(b"\x1b[6 q\x1bP$q q\x1b\\"), ["\x1bP1$r6 q\x1b\\"]);
+        assert_eq!(replies(b"\x1bP$qx\x1b\\"), ["\x1bP0$r\x1b\\"]);
+    }
+}
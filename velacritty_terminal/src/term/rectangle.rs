@@ -0,0 +1,442 @@
+//! Rectangular area operations of the VT400 series.
+//!
+//! These allow applications to copy, fill, erase and change the attributes of a rectangular part
+//! of the screen using a single escape sequence (DECCRA, DECFRA, DECERA, DECSERA, DECCARA and
+//! DECRARA).
+
+use std::cmp;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::event::EventListener;
+use crate::grid::Dimensions;
+use crate::index::{Column, Line};
+use crate::term::cell::{Cell, Flags};
+use crate::term::{Term, TermMode};
+
+/// Attributes which can be changed by DECCARA.
+const ATTRIBUTES: Flags = Flags::BOLD
+    .union(Flags::DIM)
+    .union(Flags::ITALIC)
+    .union(Flags::ALL_UNDERLINES)
+    .union(Flags::INVERSE)
+    .union(Flags::HIDDEN)
+    .union(Flags::STRIKEOUT);
+
+/// Attributes toggled by DECRARA's default parameter.
+const DEFAULT_REVERSE_ATTRIBUTES: Flags = Flags::BOLD.union(Flags::UNDERLINE).union(Flags::INVERSE);
+
+/// Rectangular area of the screen.
+///
+/// Coordinates are 1-based and inclusive, like they are sent by the application. Zero selects the
+/// default, which is the edge of the screen.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Rectangle {
+    pub top: usize,
+    pub left: usize,
+    pub bottom: usize,
+    pub right: usize,
+}
+
+impl Rectangle {
+    /// Parse the rectangle from the first four escape sequence parameters.
+    pub fn from_params(params: &[u16]) -> Self {
+        let param = |index: usize| params.get(index).copied().unwrap_or(0) as usize;
+        Self { top: param(0), left: param(1), bottom: param(2), right: param(3) }
+    }
+}
+
+/// Change of cell attributes requested by DECCARA or DECRARA.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AttributeChange {
+    /// Attributes which are enabled.
+    pub set: Flags,
+
+    /// Attributes which are disabled.
+    pub clear: Flags,
+
+    /// Attributes which are toggled.
+    pub reverse: Flags,
+}
+
+impl Default for AttributeChange {
+    fn default() -> Self {
+        Self { set: Flags::empty(), clear: Flags::empty(), reverse: Flags::empty() }
+    }
+}
+
+impl AttributeChange {
+    /// Parse the SGR parameters of DECCARA.
+    pub fn change(params: &[u16]) -> Self {
+        let mut change = Self::default();
+
+        for param in params.iter().copied().chain(params.is_empty().then_some(0)) {
+            match param {
+                0 => change.disable(ATTRIBUTES),
+                1 => change.enable(Flags::BOLD),
+                2 => change.enable(Flags::DIM),
+                3 => change.enable(Flags::ITALIC),
+                4 => {
+                    change.disable(Flags::ALL_UNDERLINES);
+                    change.enable(Flags::UNDERLINE);
+                },
+                7 => change.enable(Flags::INVERSE),
+                8 => change.enable(Flags::HIDDEN),
+                9 => change.enable(Flags::STRIKEOUT),
+                21 => {
+                    change.disable(Flags::ALL_UNDERLINES);
+                    change.enable(Flags::DOUBLE_UNDERLINE);
+                },
+                22 => change.disable(Flags::BOLD | Flags::DIM),
+                23 => change.disable(Flags::ITALIC),
+                24 => change.disable(Flags::ALL_UNDERLINES),
+                27 => change.disable(Flags::INVERSE),
+                28 => change.disable(Flags::HIDDEN),
+                29 => change.disable(Flags::STRIKEOUT),
+                _ => (),
+            }
+        }
+
+        change
+    }
+
+    /// Parse the SGR parameters of DECRARA.
+    pub fn reverse(params: &[u16]) -> Self {
+        let mut change = Self::default();
+
+        for param in params.iter().copied().chain(params.is_empty().then_some(0)) {
+            change.reverse ^= match param {
+                0 => DEFAULT_REVERSE_ATTRIBUTES,
+                1 => Flags::BOLD,
+                2 => Flags::DIM,
+                3 => Flags::ITALIC,
+                4 => Flags::UNDERLINE,
+                7 => Flags::INVERSE,
+                8 => Flags::HIDDEN,
+                9 => Flags::STRIKEOUT,
+                _ => continue,
+            };
+        }
+
+        change
+    }
+
+    fn enable(&mut self, flags: Flags) {
+        self.set.insert(flags);
+        self.clear.remove(flags);
+    }
+
+    fn disable(&mut self, flags: Flags) {
+        self.set.remove(flags);
+        self.clear.insert(flags);
+    }
+
+    fn apply(&self, cell: &mut Cell) {
+        cell.flags.insert(self.set);
+        cell.flags.remove(self.clear);
+        cell.flags.toggle(self.reverse);
+    }
+}
+
+/// Inclusive screen coordinates of an area.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Bounds {
+    top: Line,
+    left: Column,
+    bottom: Line,
+    right: Column,
+}
+
+impl Bounds {
+    fn lines(&self) -> impl Iterator<Item = Line> {
+        (self.top.0..=self.bottom.0).map(Line)
+    }
+}
+
+impl<T: EventListener> Term<T> {
+    /// DECCRA copy of a rectangle, with `top` and `left` as its new top-left corner.
+    pub(super) fn copy_rectangle_area(&mut self, source: Rectangle, top: usize, left: usize) {
+        let destination = Rectangle { top, left, ..Default::default() };
+        let (source, destination) =
+            match (self.rectangle_bounds(source), self.rectangle_bounds(destination)) {
+                (Some(source), Some(destination)) => (source, destination),
+                _ => return,
+            };
+
+        // Clip the copy at the bottom right of the screen.
+        let lines =
+            cmp::min(source.bottom.0 - source.top.0, destination.bottom.0 - destination.top.0);
+        let columns = cmp::min(source.right - source.left, destination.right - destination.left);
+        let destination = Bounds {
+            bottom: destination.top + lines as usize,
+            right: destination.left + columns.0,
+            ..destination
+        };
+
+        // Read all cells first, since source and destination might overlap.
+        let cells: Vec<Vec<Cell>> = source
+            .lines()
+            .take(lines as usize + 1)
+            .map(|line| self.grid[line][source.left..source.left + columns.0 + 1].to_vec())
+            .collect();
+
+        self.split_wide_chars(destination);
+        for (line, cells) in destination.lines().zip(cells) {
+            let row = &mut self.grid[line];
+            row[destination.left..destination.right + 1].clone_from_slice(&cells);
+        }
+
+        self.damage_area(destination);
+    }
+
+    /// DECFRA fill of a rectangle with a character, using the current attributes.
+    pub(super) fn fill_rectangle_area(&mut self, area: Rectangle, c: char) {
+        let bounds = match self.rectangle_bounds(area) {
+            Some(bounds) if c.width() == Some(1) => bounds,
+            _ => return,
+        };
+
+        let mut template = self.grid.cursor.template.clone();
+        template.c = c;
+
+        self.split_wide_chars(bounds);
+        for line in bounds.lines() {
+            for cell in &mut self.grid[line][bounds.left..bounds.right + 1] {
+                *cell = template.clone();
+            }
+        }
+
+        self.damage_area(bounds);
+    }
+
+    /// DECERA and DECSERA erase of a rectangle.
+    ///
+    /// A selective erase only resets the characters without protection, keeping their
+    /// attributes.
+    pub(super) fn erase_rectangle_area(&mut self, area: Rectangle, selective: bool) {
+        let bounds = match self.rectangle_bounds(area) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        // Cleared cells have current background color set.
+        let bg = self.grid.cursor.template.bg;
+
+        self.split_wide_chars(bounds);
+        for line in bounds.lines() {
+            for cell in &mut self.grid[line][bounds.left..bounds.right + 1] {
+                if !selective {
+                    *cell = bg.into();
+                } else if !cell.flags.contains(Flags::PROTECTED) {
+                    cell.c = ' ';
+                    cell.clear_wide();
+                    cell.flags.remove(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER);
+                }
+            }
+        }
+
+        self.damage_area(bounds);
+    }
+
+    /// DECCARA and DECRARA change of cell attributes.
+    ///
+    /// Depending on DECSACE, this either affects the rectangle or all cells from its top-left to
+    /// its bottom-right corner.
+    pub(super) fn change_area_attributes(&mut self, area: Rectangle, change: AttributeChange) {
+        let bounds = self.area_bounds(area);
+        let rectangle = self.mode.contains(TermMode::RECTANGULAR_ATTRIBUTE_CHANGE)
+            || bounds.top == bounds.bottom;
+        if bounds.top > bounds.bottom || (rectangle && bounds.left > bounds.right) {
+            return;
+        }
+
+        let last_column = self.last_column();
+        for line in bounds.lines() {
+            let (left, right) = if rectangle {
+                (bounds.left, bounds.right)
+            } else if line == bounds.top {
+                (bounds.left, last_column)
+            } else if line == bounds.bottom {
+                (Column(0), bounds.right)
+            } else {
+                (Column(0), last_column)
+            };
+
+            // The top line is skipped entirely when the area starts past its last column.
+            if left > right {
+                continue;
+            }
+
+            for cell in &mut self.grid[line][left..right + 1] {
+                change.apply(cell);
+            }
+            self.damage.damage_line(line.0 as usize, left.0, right.0);
+        }
+    }
+
+    /// Convert a rectangle to screen coordinates, returning `None` if it is empty.
+    fn rectangle_bounds(&self, area: Rectangle) -> Option<Bounds> {
+        let bounds = self.area_bounds(area);
+        (bounds.top <= bounds.bottom && bounds.left <= bounds.right).then_some(bounds)
+    }
+
+    /// Convert an area to screen coordinates.
+    ///
+    /// In origin mode, lines are relative to the scrolling region and cannot exceed it.
+    fn area_bounds(&self, area: Rectangle) -> Bounds {
+        let (offset, max_line, column_offset, max_column) = if self.mode.contains(TermMode::ORIGIN)
+        {
+            let margins = &self.horizontal_margins;
+            (self.scroll_region.start, self.scroll_region.end - 1, margins.start, margins.end - 1)
+        } else {
+            (Line(0), self.bottommost_line(), Column(0), self.last_column())
+        };
+
+        let top = offset + area.top.saturating_sub(1);
+        let bottom = match area.bottom {
+            0 => max_line,
+            bottom => cmp::min(offset + (bottom - 1), max_line),
+        };
+
+        let left = column_offset + area.left.saturating_sub(1);
+        let right = match area.right {
+            0 => max_column,
+            right => cmp::min(column_offset + (right - 1), max_column),
+        };
+
+        Bounds { top, left, bottom, right }
+    }
+
+    /// Clear wide chars which are cut in half by the edges of an area.
+    fn split_wide_chars(&mut self, bounds: Bounds) {
+        let last_column = self.last_column();
+        for line in bounds.lines() {
+            let row = &mut self.grid[line];
+
+            if bounds.left > 0 && row[bounds.left].flags.contains(Flags::WIDE_CHAR_SPACER) {
+                let cell = &mut row[bounds.left - 1];
+                cell.c = ' ';
+                cell.clear_wide();
+                self.damage.damage_line(line.0 as usize, bounds.left.0 - 1, bounds.left.0 - 1);
+            }
+
+            if bounds.right < last_column && row[bounds.right].flags.contains(Flags::WIDE_CHAR) {
+                let cell = &mut row[bounds.right + 1];
+                cell.c = ' ';
+                cell.flags.remove(Flags::WIDE_CHAR_SPACER);
+                self.damage.damage_line(line.0 as usize, bounds.right.0 + 1, bounds.right.0 + 1);
+            }
+        }
+    }
+
+    /// Damage all cells of an area and drop selections touching it.
+    fn damage_area(&mut self, bounds: Bounds) {
+        for line in bounds.lines() {
+            self.damage.damage_line(line.0 as usize, bounds.left.0, bounds.right.0);
+        }
+
+        let range = bounds.top..=bounds.bottom;
+        self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::event::VoidListener;
+    use crate::parser::Processor;
+    use crate::term::test::mock_term;
+
+    fn text(term: &Term<VoidListener>, line: i32) -> String {
+        term.grid[Line(line)].into_iter().map(|cell| cell.c).collect()
+    }
+
+    #[test]
+    fn copy_overlapping() {
+        let mut term = mock_term("abc\r\ndef\r\nghi");
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[1;1;2;2;1;2;2;1$v");
+
+        assert_eq!(text(&term, 0), "abc");
+        assert_eq!(text(&term, 1), "dab");
+        assert_eq!(text(&term, 2), "gde");
+    }
+
+    #[test]
+    fn fill_and_erase() {
+        let mut term = mock_term("abc\r\ndef\r\nghi");
+        let mut parser: Processor = Processor::new();
+
+        // Protected fill survives a selective erase.
+        parser.advance(&mut term, b"\x1b[1\"q\x1b[42;1;1;1;2$x\x1b[0\"q");
+        parser.advance(&mut term, b"\x1b[1;1;3;2${");
+        assert_eq!(text(&term, 0), "**c");
+        assert_eq!(text(&term, 1), "  f");
+        assert_eq!(text(&term, 2), "  i");
+
+        // Regular erase ignores protection.
+        parser.advance(&mut term, b"\x1b[$z");
+        assert_eq!(text(&term, 0), "   ");
+        assert!(!term.grid[Line(0)][Column(0)].flags.contains(Flags::PROTECTED));
+    }
+
+    #[test]
+    fn change_attributes() {
+        let mut term = mock_term("abc\r\ndef\r\nghi");
+        let mut parser: Processor = Processor::new();
+
+        // Rectangle extent.
+        parser.advance(&mut term, b"\x1b[2*x\x1b[1;2;2;3;1;4$r");
+        let flags =
+            |term: &Term<VoidListener>, line, column| term.grid[Line(line)][Column(column)].flags;
+        assert_eq!(flags(&term, 0, 0), Flags::empty());
+        assert_eq!(flags(&term, 0, 1), Flags::BOLD | Flags::UNDERLINE);
+        assert_eq!(flags(&term, 1, 2), Flags::BOLD | Flags::UNDERLINE);
+        assert_eq!(flags(&term, 2, 1), Flags::empty());
+
+        // Stream extent.
+        parser.advance(&mut term, b"\x1b[0*x\x1b[1;3;2;1;7$t");
+        assert_eq!(flags(&term, 0, 2), Flags::BOLD | Flags::UNDERLINE | Flags::INVERSE);
+        assert_eq!(flags(&term, 1, 0), Flags::INVERSE);
+        assert_eq!(flags(&term, 1, 1), Flags::BOLD | Flags::UNDERLINE);
+
+        // Default parameter clears all attributes.
+        parser.advance(&mut term, b"\x1b[2*x\x1b[$r");
+        assert_eq!(flags(&term, 0, 2), Flags::empty());
+    }
+
+    #[test]
+    fn change_attributes_past_last_column() {
+        let mut term = mock_term("abcdefghij\r\nabcdefghij\r\nabcdefghij");
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[2;20;3;5;1$t\x1b[2;20;3;5;4$r");
+
+        let flags =
+            |term: &Term<VoidListener>, line, column| term.grid[Line(line)][Column(column)].flags;
+        assert_eq!(flags(&term, 1, 9), Flags::empty());
+        assert_eq!(flags(&term, 2, 0), Flags::BOLD | Flags::UNDERLINE);
+        assert_eq!(flags(&term, 2, 4), Flags::BOLD | Flags::UNDERLINE);
+        assert_eq!(flags(&term, 2, 5), Flags::empty());
+    }
+
+    #[test]
+    fn origin_mode() {
+        let mut term = mock_term("abc\r\ndef\r\nghi");
+        let mut parser: Processor = Processor::new();
+
+        parser.advance(&mut term, b"\x1b[2;3r\x1b[?6h\x1b[88;1;1;9;1$x");
+
+        assert_eq!(text(&term, 0), "abc");
+        assert_eq!(text(&term, 1), "Xef");
+        assert_eq!(text(&term, 2), "Xhi");
+
+        // Columns are relative to the left and right margins.
+        parser.advance(&mut term, b"\x1b[?69h\x1b[2;3s\x1b[89;1;1;9;9$x");
+
+        assert_eq!(text(&term, 1), "XYY");
+        assert_eq!(text(&term, 2), "XYY");
+    }
+}
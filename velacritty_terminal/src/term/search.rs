@@ -18,6 +18,11 @@ use crate::term::cell::{Cell, Flags};
 /// Used to match equal brackets, when performing a bracket-pair selection.
 const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
 
+/// Number of lines paged in from the spill file at once, when searching past the history.
+///
+/// Searching continues with the next page until a match is found or the spill file is exhausted.
+const SPILL_SEARCH_LINES: usize = 10_000;
+
 pub type Match = RangeInclusive<Point>;
 
 /// Terminal regex search state.
@@ -136,6 +141,86 @@ impl<T> Term<T> {
         }
     }
 
+    /// Get next search match in the specified direction, including lines written to disk.
+    ///
+    /// Without a line limit, lines written to disk are paged back in whenever the search would
+    /// wrap around, until a match is found or the entire spilled history has been searched.
+    pub fn search_next_paged(
+        &mut self,
+        regex: &mut RegexSearch,
+        origin: Point,
+        direction: Direction,
+        side: Side,
+        max_lines: Option<usize>,
+    ) -> Option<Match> {
+        let regex_match = self.search_next(regex, origin, direction, side, max_lines);
+
+        let max_lines = max_lines.filter(|max_lines| max_lines + 1 < self.total_lines());
+        if max_lines.is_some() || self.grid.spilled_lines() == 0 {
+            return regex_match;
+        }
+
+        let origin = self.expand_wide(origin, direction);
+        match direction {
+            Direction::Right => self.spilled_match_right(regex, origin, side, regex_match),
+            Direction::Left => self.spilled_match_left(regex, origin, side, regex_match),
+        }
+    }
+
+    /// Search the lines written to disk, if the search to the right wrapped around.
+    fn spilled_match_right(
+        &mut self,
+        regex: &mut RegexSearch,
+        origin: Point,
+        side: Side,
+        regex_match: Option<Match>,
+    ) -> Option<Match> {
+        if regex_match.as_ref().is_some_and(|rm| Self::match_side(rm, side) >= origin) {
+            return regex_match;
+        }
+
+        // The first match after wrapping around is in the oldest lines, so everything is paged in.
+        let previous_topmost_line = self.topmost_line();
+        while self.grid.page_in(SPILL_SEARCH_LINES) != 0 {}
+        if self.topmost_line() == previous_topmost_line {
+            return regex_match;
+        }
+
+        let start = Point::new(self.topmost_line(), Column(0));
+        let end = self.line_search_right(Point::new(previous_topmost_line - 1, self.last_column()));
+        RegexIter::new(start, end, Direction::Right, self, regex).next().or(regex_match)
+    }
+
+    /// Search the lines written to disk, if the search to the left wrapped around.
+    fn spilled_match_left(
+        &mut self,
+        regex: &mut RegexSearch,
+        origin: Point,
+        side: Side,
+        regex_match: Option<Match>,
+    ) -> Option<Match> {
+        if regex_match.as_ref().is_some_and(|rm| Self::match_side(rm, side) <= origin) {
+            return regex_match;
+        }
+
+        // Page in one chunk at a time, since the closest match is in the newest lines.
+        loop {
+            let previous_topmost_line = self.topmost_line();
+            if self.grid.page_in(SPILL_SEARCH_LINES) == 0 {
+                return regex_match;
+            }
+
+            let start =
+                self.line_search_right(Point::new(previous_topmost_line - 1, self.last_column()));
+            let end = Point::new(self.topmost_line(), Column(0));
+            let spilled_match = RegexIter::new(start, end, Direction::Left, self, regex)
+                .find(|rm| Self::match_side(rm, side).line < previous_topmost_line);
+            if spilled_match.is_some() {
+                return spilled_match;
+            }
+        }
+    }
+
     /// Find the next match to the right of the origin.
     fn next_match_right(
         &self,
@@ -299,6 +384,7 @@ impl<T> Term<T> {
         let mut cell = iter.cell();
         self.skip_fullwidth(&mut iter, &mut cell, regex.direction);
         let mut c = cell.c;
+        let mut zerowidth = cell.zerowidth().unwrap_or_default().to_vec();
         let mut last_wrapped = iter.cell().flags.contains(Flags::WRAPLINE);
 
         let mut point = iter.point();
@@ -315,58 +401,78 @@ impl<T> Term<T> {
         }
 
         'outer: loop {
-            // Convert char to array of bytes.
-            let mut buf = [0; 4];
-            let utf8_len = c.encode_utf8(&mut buf).len();
-
-            // Pass char to DFA as individual bytes.
-            for i in 0..utf8_len {
-                // Inverse byte order when going left.
-                let byte = match regex.direction {
-                    Direction::Right => buf[i],
-                    Direction::Left => buf[utf8_len - i - 1],
+            // Pass all characters of the cell's grapheme cluster to the DFA.
+            let mut index = 0;
+            'cluster: while index <= zerowidth.len() {
+                // Inverse character order when going left.
+                let (c, cluster_start) = match regex.direction {
+                    Direction::Right if index == 0 => (c, true),
+                    Direction::Right => (zerowidth[index - 1], false),
+                    Direction::Left if index == zerowidth.len() => (c, index == 0),
+                    Direction::Left => (zerowidth[zerowidth.len() - index - 1], index == 0),
                 };
 
-                state = regex.dfa.next_state(&mut regex.cache, state, byte)?;
-                consumed_bytes += 1;
-
-                if i == 0 && state.is_match() {
-                    // Matches require one additional BYTE of lookahead, so we check the match state
-                    // for the first byte of every new character to determine if the last character
-                    // was a match.
-                    regex_match = Some(last_point);
-                } else if state.is_dead() {
-                    if consumed_bytes == 2 {
-                        // Reset search if we found an empty match.
-                        //
-                        // With an unanchored search, a dead state only occurs after the end of a
-                        // match has been found. While we want to abort after the first match has
-                        // ended, we don't want empty matches since we cannot highlight them.
-                        //
-                        // So once we encounter an empty match, we reset our parser state and clear
-                        // the match, effectively starting a new search one character farther than
-                        // before.
-                        //
-                        // An empty match requires consuming `2` bytes, since the first byte will
-                        // report the match for the empty string, while the second byte then
-                        // reports the dead state indicating the first character isn't part of the
-                        // match.
-                        reset_state!();
-
-                        // Retry this character if first byte caused failure.
-                        //
-                        // After finding an empty match, we want to advance the search start by one
-                        // character. So if the first character has multiple bytes and the dead
-                        // state isn't reached at `i == 0`, then we continue with the rest of the
-                        // loop to advance the parser by one character.
-                        if i == 0 {
-                            continue 'outer;
+                // Convert char to array of bytes.
+                let mut buf = [0; 4];
+                let utf8_len = c.encode_utf8(&mut buf).len();
+
+                // Pass char to DFA as individual bytes.
+                for i in 0..utf8_len {
+                    // Inverse byte order when going left.
+                    let byte = match regex.direction {
+                        Direction::Right => buf[i],
+                        Direction::Left => buf[utf8_len - i - 1],
+                    };
+
+                    state = regex.dfa.next_state(&mut regex.cache, state, byte)?;
+                    consumed_bytes += 1;
+
+                    if i == 0 && cluster_start && state.is_match() {
+                        // Matches require one additional BYTE of lookahead, so we check the match
+                        // state for the first byte of every new grapheme cluster to determine if
+                        // the last one was a match. Matches ending inside a
+                        // cluster are ignored.
+                        regex_match = Some(last_point);
+                    } else if state.is_dead() {
+                        if consumed_bytes == 2 {
+                            // Reset search if we found an empty match.
+                            //
+                            // With an unanchored search, a dead state only occurs after the end of
+                            // a match has been found. While we want to
+                            // abort after the first match has ended, we
+                            // don't want empty matches since we cannot highlight them.
+                            //
+                            // So once we encounter an empty match, we reset our parser state and
+                            // clear the match, effectively starting a
+                            // new search one character farther than
+                            // before.
+                            //
+                            // An empty match requires consuming `2` bytes, since the first byte
+                            // will report the match for the empty
+                            // string, while the second byte then
+                            // reports the dead state indicating the first character isn't part of
+                            // the match.
+                            reset_state!();
+
+                            // Retry this character if first byte caused failure.
+                            //
+                            // After finding an empty match, we want to advance the search start by
+                            // one character. So if the first character
+                            // has multiple bytes and the dead
+                            // state isn't reached at `i == 0`, then we continue with the rest of
+                            // the loop to advance the parser by one
+                            // character.
+                            if i == 0 {
+                                continue 'cluster;
+                            }
+                        } else {
+                            // Abort on dead state.
+                            break 'outer;
                         }
-                    } else {
-                        // Abort on dead state.
-                        break 'outer;
                     }
                 }
+
+                index += 1;
             }
 
             // Stop once we've reached the target point.
@@ -402,6 +508,8 @@ impl<T> Term<T> {
             self.skip_fullwidth(&mut iter, &mut cell, regex.direction);
 
             c = cell.c;
+            zerowidth.clear();
+            zerowidth.extend_from_slice(cell.zerowidth().unwrap_or_default());
             let wrapped = iter.cell().flags.contains(Flags::WRAPLINE);
 
             last_point = mem::replace(&mut point, iter.point());
@@ -688,6 +796,8 @@ impl<T> Iterator for RegexIter<'_, T> {
 mod tests {
     use super::*;
 
+    use crate::event::VoidListener;
+    use crate::grid::SpillMode;
     use crate::index::{Column, Line};
     use crate::term::Config;
     use crate::term::test::{TermSize, mock_term};
@@ -1248,4 +1358,95 @@ mod tests {
         let match_end = Point::new(Line(0), Column(2));
         assert_eq!(term.regex_search_left(&mut regex, start, end), Some(match_end..=match_start));
     }
+
+    #[test]
+    fn grapheme_cluster() {
+        let mut term = mock_term("ae\nb");
+        term.grid_mut()[Line(0)][Column(1)].push_zerowidth('\u{301}');
+
+        // Only entire grapheme clusters can be matched.
+        let mut regex = RegexSearch::new("ae\u{301}").unwrap();
+        let start = Point::new(Line(0), Column(0));
+        let end = Point::new(Line(1), Column(0));
+        let match_end = Point::new(Line(0), Column(1));
+        assert_eq!(term.regex_search_right(&mut regex, start, end), Some(start..=match_end));
+        assert_eq!(term.regex_search_left(&mut regex, end, start), Some(start..=match_end));
+
+        let mut regex = RegexSearch::new("e").unwrap();
+        assert_eq!(term.regex_search_right(&mut regex, start, end), None);
+
+        let mut regex = RegexSearch::new("\u{301}").unwrap();
+        assert_eq!(term.regex_search_left(&mut regex, end, start), None);
+
+        // Empty matches inside a cluster are skipped.
+        let mut regex = RegexSearch::new("x*b").unwrap();
+        let match_start = Point::new(Line(1), Column(0));
+        assert_eq!(term.regex_search_right(&mut regex, start, end), Some(match_start..=end));
+    }
+
+    #[test]
+    fn spilled_history() {
+        let config = Config {
+            scrolling_history: 2,
+            history_spill: SpillMode::Plaintext,
+            ..Config::default()
+        };
+        let mut term = Term::new(config, &TermSize::new(5, 1), VoidListener);
+
+        for text in ["match", "xxxxx", "xxxxx", "xxxxx", "xxxxx"] {
+            for (i, c) in text.chars().enumerate() {
+                term.grid[Line(0)][Column(i)].c = c;
+            }
+            term.grid.scroll_up(&(Line(0)..Line(1)), 1);
+        }
+        assert_eq!(term.grid.spilled_lines(), 3);
+
+        // Lines written to disk are paged in before the search wraps around.
+        let mut regex = RegexSearch::new("match").unwrap();
+        let origin = Point::new(Line(0), Column(0));
+        let start = Point::new(Line(-5), Column(0));
+        let end = Point::new(Line(-5), Column(4));
+        let regex_match =
+            term.search_next_paged(&mut regex, origin, Direction::Left, Side::Left, None);
+        assert_eq!(regex_match, Some(start..=end));
+        assert_eq!(term.history_size(), 5);
+
+        // Limited searches only cover the lines in memory.
+        term.grid.page_out();
+        let regex_match =
+            term.search_next_paged(&mut regex, origin, Direction::Left, Side::Left, Some(1));
+        assert_eq!(regex_match, None);
+        assert_eq!(term.history_size(), 2);
+    }
+
+    #[test]
+    fn spilled_history_deep_match() {
+        let config = Config {
+            scrolling_history: 2,
+            history_spill: SpillMode::Plaintext,
+            ..Config::default()
+        };
+        let mut term = Term::new(config, &TermSize::new(5, 1), VoidListener);
+
+        for (i, c) in "match".chars().enumerate() {
+            term.grid[Line(0)][Column(i)].c = c;
+        }
+        for _ in 0..SPILL_SEARCH_LINES * 2 {
+            term.grid.scroll_up(&(Line(0)..Line(1)), 1);
+        }
+
+        // Matches beyond the first page are found, in both directions.
+        let mut regex = RegexSearch::new("match").unwrap();
+        let origin = Point::new(Line(0), Column(0));
+        let line = Line(-(SPILL_SEARCH_LINES as i32 * 2));
+        let expected = Some(Point::new(line, Column(0))..=Point::new(line, Column(4)));
+        let regex_match =
+            term.search_next_paged(&mut regex, origin, Direction::Left, Side::Left, None);
+        assert_eq!(regex_match, expected);
+
+        term.grid.page_out();
+        let regex_match =
+            term.search_next_paged(&mut regex, origin, Direction::Right, Side::Left, None);
+        assert_eq!(regex_match, expected);
+    }
 }
@@ -0,0 +1,302 @@
+//! Shell integration through semantic prompt marks (OSC 133) and working directory reports (OSC 7).
+//!
+//! Shells supporting the FinalTerm protocol announce where prompts, commands and their output
+//! begin. These positions are stored as marks on the affected grid rows, which allows them to
+//! travel with the content through scrolling and reflow.
+
+use std::path::PathBuf;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::index::Column;
+
+/// Kind of a semantic prompt mark.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SemanticMarkKind {
+    /// Start of the prompt (`OSC 133 ; A`).
+    PromptStart,
+    /// End of the prompt and start of the user's command (`OSC 133 ; B`).
+    CommandStart,
+    /// Command was executed and its output starts (`OSC 133 ; C`).
+    OutputStart,
+    /// Command finished, with its exit code if the shell reported one (`OSC 133 ; D`).
+    CommandFinished(Option<i32>),
+}
+
+impl SemanticMarkKind {
+    /// Parse the parameters of an `OSC 133` escape, excluding the `133` itself.
+    pub fn parse(params: &[&[u8]]) -> Option<Self> {
+        let (kind, options) = params.split_first()?;
+
+        match *kind {
+            // Secondary prompts for line continuation are not the start of a new command.
+            b"A" if options.iter().any(|option| matches!(*option, b"k=s" | b"k=c")) => None,
+            b"A" => Some(Self::PromptStart),
+            b"B" => Some(Self::CommandStart),
+            b"C" => Some(Self::OutputStart),
+            b"D" => {
+                let exit_code = options
+                    .first()
+                    .and_then(|code| std::str::from_utf8(code).ok())
+                    .and_then(|code| code.parse().ok());
+                Some(Self::CommandFinished(exit_code))
+            },
+            _ => None,
+        }
+    }
+
+    /// Check if two marks are of the same kind, ignoring their attached data.
+    #[inline]
+    fn same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// Semantic prompt mark stored on a grid row.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SemanticMark {
+    pub kind: SemanticMarkKind,
+    pub column: Column,
+}
+
+impl SemanticMark {
+    pub fn new(kind: SemanticMarkKind, column: Column) -> Self {
+        Self { kind, column }
+    }
+}
+
+/// Semantic marks of a single row, ordered by column.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SemanticMarks {
+    marks: Vec<SemanticMark>,
+}
+
+impl SemanticMarks {
+    #[inline]
+    pub fn as_slice(&self) -> &[SemanticMark] {
+        &self.marks
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    /// Add a new mark, replacing any existing mark of the same kind.
+    pub fn insert(&mut self, mark: SemanticMark) {
+        self.marks.retain(|old| !old.kind.same_kind(&mark.kind));
+
+        let index = self.marks.partition_point(|old| old.column <= mark.column);
+        self.marks.insert(index, mark);
+    }
+
+    /// Remove all marks at or after `column`.
+    ///
+    /// The removed marks are returned with their column relative to `column`.
+    pub fn split_off(&mut self, column: usize) -> Vec<SemanticMark> {
+        let index = self.marks.partition_point(|mark| mark.column < column);
+        let mut split = self.marks.split_off(index);
+        for mark in &mut split {
+            mark.column -= column;
+        }
+        split
+    }
+
+    /// Remove all marks before `column`.
+    ///
+    /// The remaining marks are moved left by `column` columns.
+    pub fn front_split_off(&mut self, column: usize) -> Vec<SemanticMark> {
+        let index = self.marks.partition_point(|mark| mark.column < column);
+        let split = self.marks.drain(..index).collect();
+        for mark in &mut self.marks {
+            mark.column -= column;
+        }
+        split
+    }
+
+    /// Move all marks right by `columns` columns.
+    pub fn shift_right(&mut self, columns: usize) {
+        for mark in &mut self.marks {
+            mark.column += columns;
+        }
+    }
+
+    /// Add marks after offsetting them by `offset` columns.
+    ///
+    /// Marks are clamped to `max_column`.
+    pub fn extend(&mut self, marks: Vec<SemanticMark>, offset: usize, max_column: usize) {
+        for mut mark in marks {
+            mark.column = Column((mark.column.0 + offset).min(max_column));
+            self.insert(mark);
+        }
+    }
+
+    /// Clamp all marks to `max_column`.
+    pub fn clamp(&mut self, max_column: usize) {
+        for mark in &mut self.marks {
+            mark.column = Column(mark.column.0.min(max_column));
+        }
+    }
+}
+
+/// Working directory reported by the shell (`OSC 7`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingDirectory {
+    /// Host the directory is located on, `None` if the shell did not report one.
+    pub hostname: Option<String>,
+
+    /// Absolute path of the directory.
+    pub path: PathBuf,
+}
+
+impl WorkingDirectory {
+    /// Parse the URI of an `OSC 7` escape.
+    ///
+    /// Both `file://host/path` with percent-encoded paths and kitty's unencoded
+    /// `kitty-shell-cwd://host/path` are accepted.
+    pub fn parse(uri: &[u8]) -> Option<Self> {
+        let (location, encoded) = if let Some(location) = uri.strip_prefix(b"file://") {
+            (location, true)
+        } else {
+            (uri.strip_prefix(b"kitty-shell-cwd://")?, false)
+        };
+
+        let path_start = location.iter().position(|&byte| byte == b'/')?;
+        let (hostname, path) = location.split_at(path_start);
+
+        let path = if encoded { percent_decode(path)? } else { path.to_vec() };
+        let path = String::from_utf8(path).ok()?;
+
+        let hostname = match std::str::from_utf8(hostname).ok()? {
+            "" => None,
+            hostname => Some(hostname.to_owned()),
+        };
+
+        Some(Self { hostname, path: PathBuf::from(path) })
+    }
+
+    /// Check if the directory is located on this machine.
+    pub fn is_local(&self) -> bool {
+        match self.hostname.as_deref() {
+            None | Some("localhost") => true,
+            Some(hostname) => local_hostname().is_some_and(|local| local == hostname),
+        }
+    }
+}
+
+/// Decode `%XX` escapes in a URI component.
+fn percent_decode(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter();
+    while let Some(&byte) = iter.next() {
+        if byte != b'%' {
+            decoded.push(byte);
+            continue;
+        }
+
+        let high = (*iter.next()? as char).to_digit(16)?;
+        let low = (*iter.next()? as char).to_digit(16)?;
+        decoded.push((high << 4 | low) as u8);
+    }
+    Some(decoded)
+}
+
+/// Name of the local machine.
+#[cfg(not(windows))]
+fn local_hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+
+    let len = buf.iter().position(|&byte| byte == 0).unwrap_or(buf.len());
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+/// Name of the local machine.
+#[cfg(windows)]
+fn local_hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_marks() {
+        assert_eq!(SemanticMarkKind::parse(&[b"A"]), Some(SemanticMarkKind::PromptStart));
+        assert_eq!(SemanticMarkKind::parse(&[b"A", b"aid=1"]), Some(SemanticMarkKind::PromptStart));
+        assert_eq!(SemanticMarkKind::parse(&[b"A", b"k=s"]), None);
+        assert_eq!(SemanticMarkKind::parse(&[b"B"]), Some(SemanticMarkKind::CommandStart));
+        assert_eq!(SemanticMarkKind::parse(&[b"C"]), Some(SemanticMarkKind::OutputStart));
+        assert_eq!(SemanticMarkKind::parse(&[b"D"]), Some(SemanticMarkKind::CommandFinished(None)));
+        assert_eq!(
+            SemanticMarkKind::parse(&[b"D", b"127"]),
+            Some(SemanticMarkKind::CommandFinished(Some(127)))
+        );
+        assert_eq!(SemanticMarkKind::parse(&[b"P", b"k=i"]), None);
+        assert_eq!(SemanticMarkKind::parse(&[]), None);
+    }
+
+    #[test]
+    fn insert_replaces_same_kind() {
+        let mut marks = SemanticMarks::default();
+        marks.insert(SemanticMark::new(SemanticMarkKind::CommandStart, Column(5)));
+        marks.insert(SemanticMark::new(SemanticMarkKind::PromptStart, Column(0)));
+        marks.insert(SemanticMark::new(SemanticMarkKind::CommandStart, Column(7)));
+
+        assert_eq!(marks.as_slice(), &[
+            SemanticMark::new(SemanticMarkKind::PromptStart, Column(0)),
+            SemanticMark::new(SemanticMarkKind::CommandStart, Column(7)),
+        ]);
+    }
+
+    #[test]
+    fn split_marks() {
+        let mut marks = SemanticMarks::default();
+        marks.insert(SemanticMark::new(SemanticMarkKind::PromptStart, Column(1)));
+        marks.insert(SemanticMark::new(SemanticMarkKind::CommandStart, Column(6)));
+
+        let split = marks.split_off(4);
+        assert_eq!(split, vec![SemanticMark::new(SemanticMarkKind::CommandStart, Column(2))]);
+        assert_eq!(marks.as_slice(), &[SemanticMark::new(
+            SemanticMarkKind::PromptStart,
+            Column(1)
+        )]);
+
+        marks.extend(split, 4, 9);
+        let front = marks.front_split_off(3);
+        assert_eq!(front, vec![SemanticMark::new(SemanticMarkKind::PromptStart, Column(1))]);
+        assert_eq!(marks.as_slice(), &[SemanticMark::new(
+            SemanticMarkKind::CommandStart,
+            Column(3)
+        )]);
+    }
+
+    #[test]
+    fn parse_working_directory() {
+        assert_eq!(
+            WorkingDirectory::parse(b"file://host/home/user/with%20space"),
+            Some(WorkingDirectory {
+                hostname: Some("host".into()),
+                path: PathBuf::from("/home/user/with space"),
+            })
+        );
+        assert_eq!(
+            WorkingDirectory::parse(b"file:///tmp"),
+            Some(WorkingDirectory { hostname: None, path: PathBuf::from("/tmp") })
+        );
+        assert_eq!(
+            WorkingDirectory::parse(b"kitty-shell-cwd://host/100%"),
+            Some(WorkingDirectory { hostname: Some("host".into()), path: PathBuf::from("/100%") })
+        );
+        assert_eq!(WorkingDirectory::parse(b"file://host/bad%2"), None);
+        assert_eq!(WorkingDirectory::parse(b"file://host"), None);
+        assert_eq!(WorkingDirectory::parse(b"http://host/tmp"), None);
+    }
+}
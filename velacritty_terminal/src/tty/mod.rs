@@ -12,6 +12,9 @@ mod unix;
 #[cfg(not(windows))]
 pub use self::unix::*;
 
+#[cfg(not(windows))]
+pub mod replay;
+
 #[cfg(windows)]
 pub mod windows;
 #[cfg(windows)]
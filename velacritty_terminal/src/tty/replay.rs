@@ -0,0 +1,250 @@
+//! Playback of recorded PTY output without a child process.
+
+use std::io::{self, ErrorKind, Sink, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use log::info;
+use polling::{Event, PollMode, Poller};
+
+use crate::event::{OnResize, WindowSize};
+use crate::thread;
+use crate::tty::{ChildEvent, EventedPty, EventedReadWrite, PTY_READ_WRITE_TOKEN};
+
+/// Output written by the application at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Time since the start of the recording.
+    pub time: Duration,
+
+    pub data: Vec<u8>,
+}
+
+/// Playback commands sent to the replay thread.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Command {
+    TogglePause,
+    Step,
+}
+
+/// Handle for controlling the playback of a [`Replay`].
+#[derive(Debug, Clone)]
+pub struct ReplayControl {
+    sender: Sender<Command>,
+}
+
+impl ReplayControl {
+    /// Pause or resume the playback.
+    pub fn toggle_pause(&self) {
+        let _ = self.sender.send(Command::TogglePause);
+    }
+
+    /// Pause the playback and play the next frame.
+    pub fn step(&self) {
+        let _ = self.sender.send(Command::Step);
+    }
+}
+
+/// Pseudoterminal which plays back recorded output instead of running a child process.
+///
+/// Everything written to the terminal is discarded.
+pub struct Replay {
+    stream: UnixStream,
+    sink: Sink,
+    control: ReplayControl,
+}
+
+impl Replay {
+    /// Start the playback of `frames`.
+    ///
+    /// The recorded delays between frames are divided by `speed`, an infinite `speed` plays all
+    /// frames without any delay. Returns an error if `speed` is not positive.
+    pub fn new(frames: Vec<Frame>, speed: f64, paused: bool) -> io::Result<Self> {
+        if speed.is_nan() || speed <= 0. {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "replay speed must be positive"));
+        }
+
+        let (stream, writer) = UnixStream::pair()?;
+        stream.set_nonblocking(true)?;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn_named("replay", move || play(writer, frames, speed, paused, receiver));
+
+        Ok(Self { stream, sink: io::sink(), control: ReplayControl { sender } })
+    }
+
+    /// Handle for controlling the playback.
+    pub fn control(&self) -> ReplayControl {
+        self.control.clone()
+    }
+}
+
+impl EventedReadWrite for Replay {
+    type Reader = UnixStream;
+    type Writer = Sink;
+
+    #[inline]
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: Event,
+        poll_opts: PollMode,
+    ) -> io::Result<()> {
+        interest.key = PTY_READ_WRITE_TOKEN;
+        unsafe { poll.add_with_mode(&self.stream, interest, poll_opts) }
+    }
+
+    #[inline]
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: Event,
+        poll_opts: PollMode,
+    ) -> io::Result<()> {
+        interest.key = PTY_READ_WRITE_TOKEN;
+        poll.modify_with_mode(&self.stream, interest, poll_opts)
+    }
+
+    #[inline]
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        poll.delete(&self.stream)
+    }
+
+    #[inline]
+    fn reader(&mut self) -> &mut UnixStream {
+        &mut self.stream
+    }
+
+    #[inline]
+    fn writer(&mut self) -> &mut Sink {
+        &mut self.sink
+    }
+}
+
+impl EventedPty for Replay {
+    #[inline]
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        None
+    }
+}
+
+impl OnResize for Replay {
+    fn on_resize(&mut self, _: WindowSize) {}
+}
+
+/// Write frames to `writer` at their recorded time.
+fn play(
+    mut writer: UnixStream,
+    frames: Vec<Frame>,
+    speed: f64,
+    mut paused: bool,
+    commands: Receiver<Command>,
+) {
+    // Playback position, as instant and recording time, from which delays are measured.
+    let mut start = (Instant::now(), Duration::ZERO);
+    let mut previous = Duration::ZERO;
+
+    for frame in frames {
+        loop {
+            let command = match due(start, frame.time, speed).filter(|_| !paused) {
+                Some(due) => commands.recv_timeout(due.saturating_duration_since(Instant::now())),
+                None => commands.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match command {
+                Ok(Command::TogglePause) => paused = !paused,
+                Ok(Command::Step) => {
+                    paused = true;
+                    break;
+                },
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+
+            // Wait for the whole delay since the previous frame after resuming.
+            start = (Instant::now(), previous);
+        }
+        previous = frame.time;
+
+        if writer.write_all(&frame.data).is_err() {
+            return;
+        }
+    }
+
+    info!("Replay finished");
+
+    // Keep the stream open, since reaching its end would wake up the event loop continuously.
+    while commands.recv().is_ok() {}
+}
+
+/// Instant at which the frame at `time` is due, when playing from the `start` position.
+///
+/// Returns `None` if the delay is too long to be represented, in which case the frame is only
+/// played by stepping.
+fn due(start: (Instant, Duration), time: Duration, speed: f64) -> Option<Instant> {
+    let delay = time.saturating_sub(start.1).as_secs_f64() / speed;
+    start.0.checked_add(Duration::try_from_secs_f64(delay).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{ErrorKind, Read};
+
+    /// Block until `len` bytes were played.
+    fn read(replay: &mut Replay, len: usize) -> Vec<u8> {
+        let mut data = vec![0; len];
+        let reader = replay.reader();
+        reader.set_nonblocking(false).unwrap();
+        reader.read_exact(&mut data).unwrap();
+        reader.set_nonblocking(true).unwrap();
+        data
+    }
+
+    #[test]
+    fn step() {
+        let frame =
+            |secs, data: &[u8]| Frame { time: Duration::from_secs(secs), data: data.into() };
+        let frames = vec![frame(0, b"a"), frame(1, b"b"), frame(1000, b"c")];
+
+        let mut replay = Replay::new(frames, 1., true).unwrap();
+        let control = replay.control();
+
+        control.step();
+        control.step();
+        assert_eq!(read(&mut replay, 2), b"ab");
+
+        // Stepping while playing plays the next frame without waiting for it.
+        control.toggle_pause();
+        control.step();
+        assert_eq!(read(&mut replay, 1), b"c");
+    }
+
+    #[test]
+    fn schedule() {
+        let now = Instant::now();
+        let start = (now, Duration::from_secs(1));
+
+        // Delays are measured from the start position and divided by the speed.
+        let due = |time, speed| due(start, Duration::from_secs(time), speed);
+        assert_eq!(due(3, 1.), Some(now + Duration::from_secs(2)));
+        assert_eq!(due(3, 4.), Some(now + Duration::from_millis(500)));
+        assert_eq!(due(3, f64::INFINITY), Some(now));
+        assert_eq!(due(0, 1.), Some(now));
+
+        // Delays which cannot be represented are never due.
+        assert_eq!(due(3, 1e-20), None);
+        assert_eq!(due(3, f64::MIN_POSITIVE), None);
+    }
+
+    #[test]
+    fn invalid_speed() {
+        for speed in [0., -1., f64::NAN] {
+            let err = Replay::new(Vec::new(), speed, false).err().unwrap();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+    }
+}
@@ -56,6 +56,10 @@ pub enum ViMotion {
     ParagraphUp,
     /// Move below the current paragraph.
     ParagraphDown,
+    /// Move to the start of the previous shell prompt.
+    PreviousPrompt,
+    /// Move to the start of the next shell prompt.
+    NextPrompt,
 }
 
 /// Cursor tracking vi mode position.
@@ -178,6 +182,12 @@ impl ViModeCursor {
                     .map_or(bottommost_line, Line);
                 self.point.column = Column(0);
             },
+            ViMotion::PreviousPrompt => {
+                self.point = term.prompt_before(self.point.line).unwrap_or(self.point);
+            },
+            ViMotion::NextPrompt => {
+                self.point = term.prompt_after(self.point.line).unwrap_or(self.point);
+            },
         }
 
         term.scroll_to_point(self.point);
@@ -415,6 +425,7 @@ mod tests {
 
     use crate::event::VoidListener;
     use crate::index::{Column, Line};
+    use crate::term::shell_integration::{SemanticMark, SemanticMarkKind};
     use crate::term::test::TermSize;
     use crate::term::{Config, Term};
     use crate::vte::ansi::Handler;
@@ -443,6 +454,24 @@ mod tests {
         assert_eq!(cursor.point, Point::new(Line(0), Column(0)));
     }
 
+    #[test]
+    fn grapheme_cluster() {
+        let mut term = term();
+        term.grid_mut()[Line(0)][Column(0)].c = '\u{1F468}';
+        term.grid_mut()[Line(0)][Column(0)].push_zerowidth('\u{200D}');
+        term.grid_mut()[Line(0)][Column(0)].push_zerowidth('\u{1F469}');
+        term.grid_mut()[Line(0)][Column(0)].flags.insert(Flags::WIDE_CHAR);
+        term.grid_mut()[Line(0)][Column(1)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        term.grid_mut()[Line(0)][Column(2)].c = 'a';
+
+        let mut cursor = ViModeCursor::new(Point::new(Line(0), Column(0)));
+        cursor = cursor.motion(&mut term, ViMotion::Right);
+        assert_eq!(cursor.point, Point::new(Line(0), Column(2)));
+
+        cursor = cursor.motion(&mut term, ViMotion::Left);
+        assert_eq!(term.expand_wide(cursor.point, Direction::Left), Point::new(Line(0), Column(0)));
+    }
+
     #[test]
     fn simple_wide() {
         let mut term = term();
@@ -528,6 +557,30 @@ mod tests {
         assert_eq!(cursor.point, Point::new(Line(0), Column(0)));
     }
 
+    #[test]
+    fn motion_prompt() {
+        let mut term = term();
+        let prompt = |column| SemanticMark::new(SemanticMarkKind::PromptStart, Column(column));
+        term.grid_mut()[Line(2)].set_semantic_mark(prompt(0));
+        term.grid_mut()[Line(5)].set_semantic_mark(prompt(2));
+        term.grid_mut()[Line(5)]
+            .set_semantic_mark(SemanticMark::new(SemanticMarkKind::CommandStart, Column(4)));
+
+        let mut cursor = ViModeCursor::new(Point::new(Line(3), Column(7)));
+
+        cursor = cursor.motion(&mut term, ViMotion::NextPrompt);
+        assert_eq!(cursor.point, Point::new(Line(5), Column(2)));
+
+        cursor = cursor.motion(&mut term, ViMotion::NextPrompt);
+        assert_eq!(cursor.point, Point::new(Line(5), Column(2)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PreviousPrompt);
+        assert_eq!(cursor.point, Point::new(Line(2), Column(0)));
+
+        cursor = cursor.motion(&mut term, ViMotion::PreviousPrompt);
+        assert_eq!(cursor.point, Point::new(Line(2), Column(0)));
+    }
+
     fn motion_semantic_term() -> Term<VoidListener> {
         let mut term = term();
 